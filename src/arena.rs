@@ -0,0 +1,110 @@
+use std::{borrow::Cow, fmt};
+#[cfg(feature = "arena")]
+use std::{cell::RefCell, slice, str};
+
+// With the `arena` feature, strings share chunks of this size
+#[cfg(feature = "arena")]
+const CHUNK_SIZE: usize = 4096;
+
+// Strings a parse derives from its input, e.g. expanded clusters or normalized option names, live
+// here until the parse is done and are then freed together
+#[cfg(feature = "arena")]
+#[derive(Default)]
+pub(crate) struct Arena {
+    chunks: RefCell<Vec<String>>,
+}
+
+// Without the feature every string is simply owned by whoever ends up with it
+#[cfg(not(feature = "arena"))]
+#[derive(Default)]
+pub(crate) struct Arena {}
+
+#[cfg(feature = "arena")]
+impl Arena {
+    pub(crate) fn alloc_fmt(&self, args: fmt::Arguments) -> Cow<'_, str> {
+        let mut chunks = self.chunks.borrow_mut();
+        let (start, end) = Self::write(&mut chunks, args);
+        let chunk = chunks.last().expect("no chunk was written");
+
+        // SAFETY: chunks are only ever appended to within their capacity and never dropped before
+        // the arena is, so their bytes stay where they are for as long as `self` is borrowed, even
+        // when the `Vec` holding them moves them around. The bytes are valid UTF-8, coming from a
+        // single `str` write.
+        unsafe {
+            let bytes = slice::from_raw_parts(chunk.as_ptr().add(start), end - start);
+
+            Cow::Borrowed(str::from_utf8_unchecked(bytes))
+        }
+    }
+
+    // A string that doesn't fit in what is left of the last chunk starts a new one, formatted
+    // straight into it; one longer than a chunk simply gets a bigger one
+    fn write(chunks: &mut Vec<String>, args: fmt::Arguments) -> (usize, usize) {
+        if let Some(chunk) = chunks.last_mut() {
+            let start = chunk.len();
+
+            if fmt::Write::write_fmt(&mut Bounded { chunk: &mut *chunk }, args).is_ok() {
+                return (start, chunk.len());
+            }
+
+            chunk.truncate(start);
+        }
+
+        let mut chunk = String::with_capacity(CHUNK_SIZE);
+        let _ = fmt::Write::write_fmt(&mut chunk, args);
+        let len = chunk.len();
+
+        chunks.push(chunk);
+
+        (0, len)
+    }
+
+    #[cfg(test)]
+    fn chunks(&self) -> usize {
+        self.chunks.borrow().len()
+    }
+}
+
+#[cfg(not(feature = "arena"))]
+impl Arena {
+    pub(crate) fn alloc_fmt(&self, args: fmt::Arguments) -> Cow<'_, str> {
+        Cow::Owned(fmt::format(args))
+    }
+}
+
+// Refuses to grow the chunk, which would move the strings already handed out
+#[cfg(feature = "arena")]
+struct Bounded<'a> {
+    chunk: &'a mut String,
+}
+
+#[cfg(feature = "arena")]
+impl fmt::Write for Bounded<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if self.chunk.len() + s.len() > self.chunk.capacity() {
+            return Err(fmt::Error);
+        }
+
+        self.chunk.push_str(s);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "arena")]
+#[test]
+fn test_arena() {
+    let arena = Arena::default();
+    let strings = (0..1000)
+        .map(|n| arena.alloc_fmt(format_args!("-{}", n)))
+        .collect::<Vec<_>>();
+    let long = arena.alloc_fmt(format_args!("{}", "x".repeat(10_000)));
+
+    for (n, s) in strings.iter().enumerate() {
+        assert_eq!(format!("-{}", n), *s);
+    }
+
+    assert_eq!(10_000, long.len());
+    assert_eq!("", arena.alloc_fmt(format_args!("")));
+
+    assert_eq!(2, arena.chunks());
+}
@@ -0,0 +1,113 @@
+use super::{ArgParser, ArgParserError};
+use std::io::{self, Read};
+
+impl ArgParser {
+    pub fn max_args(&mut self, limit: usize) -> &mut Self {
+        self.max_args = Some(limit);
+        self
+    }
+
+    pub fn max_input_bytes(&mut self, limit: usize) -> &mut Self {
+        self.max_input_bytes = Some(limit);
+        self
+    }
+
+    pub(crate) fn check_arg_count(&self, count: usize) -> Result<(), ArgParserError> {
+        match self.max_args {
+            Some(limit) if count > limit => Err(ArgParserError::TooManyArgs { limit }),
+            _ => Ok(()),
+        }
+    }
+
+    pub(crate) fn check_input_bytes(&self, read: usize) -> Result<(), ArgParserError> {
+        match self.max_input_bytes {
+            Some(limit) if read > limit => Err(ArgParserError::InputTooLarge { limit }),
+            _ => Ok(()),
+        }
+    }
+
+    // Never reads more than one byte past the budget, so that oversized sources are rejected
+    // without being loaded into memory first
+    pub(crate) fn read_limited(
+        &self,
+        source: &mut dyn Read,
+        read: &mut usize,
+    ) -> Result<io::Result<String>, ArgParserError> {
+        let remaining = self
+            .max_input_bytes
+            .map_or(u64::MAX, |limit| limit.saturating_sub(*read) as u64 + 1);
+
+        let mut bytes = vec![];
+
+        if let Err(err) = source.take(remaining).read_to_end(&mut bytes) {
+            return Ok(Err(err));
+        }
+
+        *read += bytes.len();
+        self.check_input_bytes(*read)?;
+
+        Ok(String::from_utf8(bytes).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err)))
+    }
+}
+
+#[test]
+fn test_max_args() -> Result<(), ArgParserError> {
+    use super::PositionalArg;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_positional(PositionalArg::rest())?
+        .args_from_stdin(true)
+        .max_args(3);
+
+    assert!(parser.parse_str("a b c").is_ok());
+    assert_eq!(
+        Err(ArgParserError::TooManyArgs { limit: 3 }),
+        parser.parse_str("a b c d").map(|_| ())
+    );
+    assert_eq!(
+        Err(ArgParserError::TooManyArgs { limit: 3 }),
+        parser
+            .expand_response_files(&["a", "@-"], &mut "b\nc\nd\n".as_bytes())
+            .map(|_| ())
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_max_input_bytes() -> Result<(), ArgParserError> {
+    use super::PositionalArg;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_positional(PositionalArg::rest())?
+        .args_from_stdin(true)
+        .max_input_bytes(8);
+
+    assert!(parser.parse_str("abc def").is_ok());
+    assert_eq!(
+        Err(ArgParserError::InputTooLarge { limit: 8 }),
+        parser.parse_str("abc defgh").map(|_| ())
+    );
+    assert_eq!(
+        Ok(Some(vec!["@-".to_string(), "abc".to_string()])),
+        parser
+            .expand_response_files(&["--", "@-", "abc"], &mut "".as_bytes())
+            .map(|args| args.map(|args| args[1..].to_vec()))
+    );
+    assert_eq!(
+        Ok(Some(vec!["ab".to_string(), "cd".to_string()])),
+        parser.expand_response_files(&["ab", "@-"], &mut "cd\n".as_bytes())
+    );
+    assert_eq!(
+        Err(ArgParserError::InputTooLarge { limit: 8 }),
+        parser
+            .expand_response_files(&["ab", "@-"], &mut "cd\nefgh\n".as_bytes())
+            .map(|_| ())
+    );
+
+    Ok(())
+}
@@ -0,0 +1,320 @@
+use super::{ArgGroupKind, ArgParser, OptionalArgKind, PositionalArgKind, ValueStyle};
+use std::fmt::Write;
+
+fn quote(value: &str) -> String {
+    let mut quoted = String::from('"');
+
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(quoted, "\\u{:04x}", c as u32);
+            }
+            c => quoted.push(c),
+        }
+    }
+
+    quoted.push('"');
+    quoted
+}
+
+fn list(values: &[&str]) -> String {
+    let values = values.iter().map(|value| quote(value)).collect::<Vec<_>>();
+
+    format!("[{}]", values.join(", "))
+}
+
+impl ArgParser {
+    // In the YAML format of clap 3, there is no equivalent for `--no-` negation or validators
+    pub fn to_clap_yaml(&self, name: &str) -> String {
+        let mut yaml = format!("name: {}\n", quote(name));
+        let mut names = self.options.keys().copied().collect::<Vec<_>>();
+
+        names.sort_unstable();
+
+        if !names.is_empty() || !self.positional.is_empty() {
+            yaml.push_str("args:\n");
+        }
+
+        for name in names {
+            let option = &self.options[name];
+            let mut fields = vec![("long", quote(name))];
+
+            let (short, long): (Vec<&str>, Vec<&str>) = option
+                .aliases
+                .iter()
+                .partition(|alias| alias.chars().count() == 1);
+
+            if let Some((first, others)) = short.split_first() {
+                fields.push(("short", quote(first)));
+
+                if !others.is_empty() {
+                    fields.push(("visible_short_aliases", list(others)));
+                }
+            }
+
+            if !long.is_empty() {
+                fields.push(("visible_aliases", list(&long)));
+            }
+
+            if let Some(help) = option.help {
+                fields.push(("help", quote(help)));
+            }
+
+            match option.kind {
+                OptionalArgKind::Flag => {}
+                OptionalArgKind::Count => fields.push(("multiple_occurrences", "true".into())),
+                OptionalArgKind::RequiredValue => fields.push(("takes_value", "true".into())),
+                OptionalArgKind::OptionalValue => {
+                    fields.push(("takes_value", "true".into()));
+                    fields.push(("min_values", "0".into()));
+                }
+                OptionalArgKind::Values => {
+                    fields.push(("takes_value", "true".into()));
+                    fields.push(("min_values", option.arity.start().to_string()));
+
+                    if *option.arity.end() != usize::MAX {
+                        fields.push(("max_values", option.arity.end().to_string()));
+                    }
+                }
+            }
+
+            if let Some(terminator) = option.value_terminator {
+                fields.push(("value_terminator", quote(terminator)));
+            }
+
+            if let Some(delimiter) = option.delimiter {
+                fields.push(("use_value_delimiter", "true".into()));
+                fields.push(("value_delimiter", quote(&delimiter.to_string())));
+            }
+
+            if let Some(value_name) = option.value_name {
+                fields.push(("value_name", quote(value_name)));
+            }
+
+            // Counts already have it from their kind
+            if option.multiple && option.kind != OptionalArgKind::Count {
+                fields.push(("multiple_occurrences", "true".into()));
+            }
+
+            if self.value_style_of(option) == ValueStyle::EqualsOnly {
+                fields.push(("require_equals", "true".into()));
+            }
+
+            if option.allow_hyphen_values {
+                fields.push(("allow_hyphen_values", "true".into()));
+            }
+
+            if !option.choices.is_empty() && !option.or_other {
+                fields.push(("possible_values", list(&option.choices)));
+            }
+
+            if let Some(value) = option.default {
+                fields.push(("default_value", quote(value)));
+            }
+
+            if let Some(value) = option.default_missing {
+                fields.push(("default_missing_value", quote(value)));
+            }
+
+            if let Some(var) = self.env_var(option) {
+                fields.push(("env", quote(&var)));
+            }
+
+            if !option.requires.is_empty() {
+                fields.push(("requires", list(&option.requires)));
+            }
+
+            if !option.conflicts.is_empty() {
+                fields.push(("conflicts_with", list(&option.conflicts)));
+            }
+
+            if option.hidden || option.env_only {
+                fields.push(("hide", "true".into()));
+            }
+
+            Self::write_clap_entry(&mut yaml, name, &fields);
+        }
+
+        // Options, positionals and groups share one namespace of ids in clap
+        let mut ids = self
+            .positional
+            .iter()
+            .filter_map(|arg| arg.name)
+            .chain(self.options.keys().copied())
+            .chain(self.groups.iter().map(|group| group.name))
+            .map(String::from)
+            .collect::<Vec<_>>();
+
+        for (idx, arg) in self.positional.iter().enumerate() {
+            let name = arg.name.map_or_else(
+                || {
+                    let mut name = format!("arg{}", idx + 1);
+
+                    while ids.contains(&name) {
+                        name.push('_');
+                    }
+
+                    ids.push(name.clone());
+                    name
+                },
+                String::from,
+            );
+            let mut fields = vec![("index", (idx + 1).to_string())];
+
+            match arg.kind {
+                PositionalArgKind::Named => fields.push(("required", "true".into())),
+                PositionalArgKind::Optional => {}
+                PositionalArgKind::Rest => {
+                    fields.push(("multiple_values", "true".into()));
+
+                    if arg.min > 0 {
+                        fields.push(("required", "true".into()));
+                        fields.push(("min_values", arg.min.to_string()));
+                    }
+                }
+            }
+
+            if !arg.choices.is_empty() {
+                fields.push(("possible_values", list(&arg.choices)));
+            }
+
+            if let Some(value) = arg.default {
+                fields.push(("default_value", quote(value)));
+            }
+
+            Self::write_clap_entry(&mut yaml, &name, &fields);
+        }
+
+        if !self.groups.is_empty() {
+            yaml.push_str("groups:\n");
+        }
+
+        for group in &self.groups {
+            let mut fields = vec![("args", list(&group.options))];
+
+            match group.kind {
+                ArgGroupKind::AtMostOne => fields.push(("multiple", "false".into())),
+                ArgGroupKind::ExactlyOne => {
+                    fields.push(("multiple", "false".into()));
+                    fields.push(("required", "true".into()));
+                }
+                ArgGroupKind::AtLeastOne => {
+                    fields.push(("multiple", "true".into()));
+                    fields.push(("required", "true".into()));
+                }
+            }
+
+            Self::write_clap_entry(&mut yaml, group.name, &fields);
+        }
+
+        yaml
+    }
+
+    fn write_clap_entry(yaml: &mut String, name: &str, fields: &[(&str, String)]) {
+        let _ = writeln!(yaml, "  - {}:", quote(name));
+
+        for (key, value) in fields {
+            let _ = writeln!(yaml, "      {}: {}", key, value);
+        }
+    }
+}
+
+#[test]
+fn test_quote() {
+    assert_eq!("\"foo\"", quote("foo"));
+    assert_eq!(
+        "\"say \\\"hi\\\"\\n\\\\\\u001b\"",
+        quote("say \"hi\"\n\\\x1b")
+    );
+}
+
+#[test]
+fn test_to_clap_yaml() -> Result<(), super::ArgParserError> {
+    use super::{ArgGroup, OptionalArg, PositionalArg};
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::flag("json").alias("j").help("Print JSON"))?
+        .add_option(OptionalArg::flag("yaml"))?
+        .add_option(
+            OptionalArg::optional_value("color")
+                .alias("colour")
+                .choices(&["auto", "never"])
+                .default_missing("auto"),
+        )?
+        .add_option(
+            OptionalArg::count("verbose")
+                .alias("v")
+                .alias("V")
+                .multiple(),
+        )?
+        .add_option(
+            OptionalArg::values("size", 2..=3)
+                .value_name("N")
+                .env("SIZE"),
+        )?
+        .add_option(OptionalArg::values("tags", 1..=1).greedy())?
+        .add_option(OptionalArg::flag("arg3"))?
+        .add_positional(PositionalArg::named().name("input"))?
+        .add_positional(PositionalArg::optional())?
+        .add_positional(PositionalArg::rest().choices(&["a", "b"]))?
+        .add_group(ArgGroup::at_most_one("format", &["json", "yaml"]))?;
+
+    assert_eq!(
+        r#"name: "tool"
+args:
+  - "arg3":
+      long: "arg3"
+  - "color":
+      long: "color"
+      visible_aliases: ["colour"]
+      takes_value: true
+      min_values: 0
+      possible_values: ["auto", "never"]
+      default_missing_value: "auto"
+  - "json":
+      long: "json"
+      short: "j"
+      help: "Print JSON"
+  - "size":
+      long: "size"
+      takes_value: true
+      min_values: 2
+      max_values: 3
+      value_name: "N"
+      env: "SIZE"
+  - "tags":
+      long: "tags"
+      takes_value: true
+      min_values: 1
+  - "verbose":
+      long: "verbose"
+      short: "v"
+      visible_short_aliases: ["V"]
+      multiple_occurrences: true
+  - "yaml":
+      long: "yaml"
+  - "input":
+      index: 1
+      required: true
+  - "arg2":
+      index: 2
+  - "arg3_":
+      index: 3
+      multiple_values: true
+      possible_values: ["a", "b"]
+groups:
+  - "format":
+      args: ["json", "yaml"]
+      multiple: false
+"#,
+        parser.to_clap_yaml("tool")
+    );
+
+    Ok(())
+}
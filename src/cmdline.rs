@@ -0,0 +1,215 @@
+use super::{ArgParserError, ParsedArgs, SplitStyle};
+use std::{ffi::OsStr, mem};
+
+// Windows counts UTF-16 units including the terminating NUL, while for Unix the smallest ARG_MAX
+// of the common systems is used; it's shared with the environment, which isn't included here
+const WINDOWS_LIMIT: usize = 32767;
+const POSIX_LIMIT: usize = 256 * 1024;
+// Linux also limits every single string to MAX_ARG_STRLEN, 32 pages including the NUL
+const POSIX_ARG_LIMIT: usize = 32 * 4096;
+
+pub(crate) fn quote_windows(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains([' ', '\t', '\n', '"']) {
+        return arg.to_string();
+    }
+
+    let mut quoted = String::from('"');
+    let mut backslashes = 0;
+
+    for c in arg.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            '"' => {
+                quoted.push_str(&"\\".repeat(backslashes * 2 + 1));
+                backslashes = 0;
+            }
+            _ => {
+                quoted.push_str(&"\\".repeat(backslashes));
+                backslashes = 0;
+            }
+        }
+
+        if c != '\\' {
+            quoted.push(c);
+        }
+    }
+
+    quoted.push_str(&"\\".repeat(backslashes * 2));
+    quoted.push('"');
+    quoted
+}
+
+impl SplitStyle {
+    pub fn native() -> Self {
+        if cfg!(windows) {
+            Self::Windows
+        } else {
+            Self::Posix
+        }
+    }
+
+    pub fn command_line_limit(self) -> usize {
+        match self {
+            Self::Posix => POSIX_LIMIT,
+            Self::Windows => WINDOWS_LIMIT,
+        }
+    }
+
+    // Windows arguments only have to fit in the whole command line
+    pub fn argument_limit(self) -> Option<usize> {
+        match self {
+            Self::Posix => Some(POSIX_ARG_LIMIT),
+            Self::Windows => None,
+        }
+    }
+
+    // Includes the program name if it's passed as the first argument, as it has to be for exec
+    pub fn command_line_length<S: AsRef<OsStr>>(self, args: &[S]) -> usize {
+        let total = args
+            .iter()
+            .map(|arg| self.argument_length(arg))
+            .sum::<usize>();
+
+        match self {
+            Self::Posix => total + (args.len() + 1) * mem::size_of::<usize>(),
+            Self::Windows => total + args.len().max(1),
+        }
+    }
+
+    // `OsStr` bytes plus the NUL for exec, or quoted UTF-16 units, where unpaired surrogates, the
+    // only thing that isn't unicode, are one unit as well as their replacement
+    fn argument_length(self, arg: impl AsRef<OsStr>) -> usize {
+        match self {
+            Self::Posix => arg.as_ref().len() + 1,
+            Self::Windows => quote_windows(&arg.as_ref().to_string_lossy())
+                .encode_utf16()
+                .count(),
+        }
+    }
+
+    pub fn check_command_line<S: AsRef<OsStr>>(self, args: &[S]) -> Result<usize, ArgParserError> {
+        if let Some(limit) = self.argument_limit() {
+            for (idx, arg) in args.iter().enumerate() {
+                let length = self.argument_length(arg);
+
+                if length > limit {
+                    return Err(ArgParserError::ArgumentTooLong {
+                        index: idx + 1,
+                        length,
+                        limit,
+                    });
+                }
+            }
+        }
+
+        let (length, limit) = (self.command_line_length(args), self.command_line_limit());
+
+        if length > limit {
+            return Err(ArgParserError::CommandLineTooLong { length, limit });
+        }
+
+        Ok(length)
+    }
+
+    // The command line the args were parsed from, as given, e.g. before re-executing with it
+    pub fn check_parsed_args(self, args: &ParsedArgs) -> Result<usize, ArgParserError> {
+        self.check_command_line(args.raw_args())
+    }
+}
+
+#[test]
+fn test_quote_windows() {
+    use super::split::split_windows;
+
+    let args = [
+        "foo",
+        "",
+        "a b",
+        "C:\\Program Files\\",
+        "say \"hi\"",
+        "\\\\server\\share",
+        "a\\\"b",
+    ];
+
+    let line = args.map(quote_windows).join(" ");
+
+    assert_eq!(
+        "foo \"\" \"a b\" \"C:\\Program Files\\\\\" \"say \\\"hi\\\"\" \\\\server\\share \"a\\\\\\\"b\"",
+        line
+    );
+    assert_eq!(args.to_vec(), split_windows(&line));
+}
+
+#[test]
+fn test_command_line_length() {
+    let args = ["cc", "-o", "a b", "main.c"];
+    let pointer = mem::size_of::<usize>();
+
+    assert_eq!(
+        17 + 5 * pointer,
+        SplitStyle::Posix.command_line_length(&args)
+    );
+    assert_eq!(19, SplitStyle::Windows.command_line_length(&args));
+    assert_eq!(1, SplitStyle::Windows.command_line_length::<&str>(&[]));
+    assert_eq!(Ok(19), SplitStyle::Windows.check_command_line(&args));
+
+    let long = "x".repeat(WINDOWS_LIMIT);
+
+    assert_eq!(
+        Err(ArgParserError::CommandLineTooLong {
+            length: WINDOWS_LIMIT + 1,
+            limit: WINDOWS_LIMIT
+        }),
+        SplitStyle::Windows.check_command_line(&[&long])
+    );
+    assert!(SplitStyle::Posix.check_command_line(&[&long]).is_ok());
+
+    // Fine as a whole, but too long for a single string on Linux
+    let long = "x".repeat(POSIX_ARG_LIMIT);
+
+    assert_eq!(
+        Err(ArgParserError::ArgumentTooLong {
+            index: 2,
+            length: POSIX_ARG_LIMIT + 1,
+            limit: POSIX_ARG_LIMIT
+        }),
+        SplitStyle::Posix.check_command_line(&["cc", &long])
+    );
+    assert_eq!(
+        "argument #2 would be 131073 long, but at most 131072 is allowed",
+        SplitStyle::Posix
+            .check_command_line(&["cc", &long])
+            .unwrap_err()
+            .to_string()
+    );
+    assert_eq!(None, SplitStyle::Windows.argument_limit());
+}
+
+#[test]
+fn test_check_parsed_args() -> Result<(), ArgParserError> {
+    use super::{ArgParser, OptionalArg, PositionalArg};
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::required_value("out").alias("o"))?
+        .add_positional(PositionalArg::rest())?;
+
+    let args = parser.parse(&["-o", "a b", "main.c"])?;
+
+    assert_eq!(Ok(16), SplitStyle::Windows.check_parsed_args(&args));
+    assert_eq!(
+        SplitStyle::Posix.command_line_length(&["-o", "a b", "main.c"]),
+        SplitStyle::Posix.check_parsed_args(&args)?
+    );
+
+    let long = "x".repeat(POSIX_ARG_LIMIT);
+    let args = parser.parse(&["-o", &long])?;
+
+    assert!(matches!(
+        SplitStyle::Posix.check_parsed_args(&args),
+        Err(ArgParserError::ArgumentTooLong { index: 2, .. })
+    ));
+
+    Ok(())
+}
@@ -0,0 +1,834 @@
+use super::{
+    parser::dashes, schema::canonical_positional, ArgParser, DuplicateKeys, DuplicateOptions,
+    OptionalArg, OptionalArgKind, PositionalArg, PositionalArgKind, ValueStyle,
+};
+use std::{fmt, ops::RangeInclusive};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SchemaChange {
+    AddedOption {
+        name: &'static str,
+    },
+    RemovedOption {
+        name: &'static str,
+    },
+    ChangedKind {
+        name: &'static str,
+        old: OptionalArgKind,
+        new: OptionalArgKind,
+    },
+    AddedAlias {
+        name: &'static str,
+        alias: &'static str,
+    },
+    RemovedAlias {
+        name: &'static str,
+        alias: &'static str,
+    },
+    Tightened {
+        name: &'static str,
+        constraint: &'static str,
+    },
+    Loosened {
+        name: &'static str,
+        constraint: &'static str,
+    },
+    ChangedOption {
+        name: &'static str,
+        setting: &'static str,
+    },
+    AddedPositional {
+        index: usize,
+        kind: PositionalArgKind,
+    },
+    RemovedPositional {
+        index: usize,
+        kind: PositionalArgKind,
+    },
+    ChangedPositional {
+        index: usize,
+        old: PositionalArgKind,
+        new: PositionalArgKind,
+    },
+    TightenedPositional {
+        index: usize,
+        constraint: &'static str,
+    },
+    LoosenedPositional {
+        index: usize,
+        constraint: &'static str,
+    },
+    AddedGroup {
+        name: &'static str,
+    },
+    RemovedGroup {
+        name: &'static str,
+    },
+    ChangedGroup {
+        name: &'static str,
+    },
+    ChangedParser {
+        setting: &'static str,
+    },
+    LoosenedParser {
+        setting: &'static str,
+    },
+}
+
+impl SchemaChange {
+    // Anything that makes a command line accepted by the old schema fail or mean something else
+    pub fn is_breaking(&self) -> bool {
+        use SchemaChange::*;
+
+        match self {
+            AddedOption { .. }
+            | AddedAlias { .. }
+            | Loosened { .. }
+            | LoosenedPositional { .. }
+            | RemovedGroup { .. }
+            | LoosenedParser { .. } => false,
+            AddedPositional { kind, .. } => *kind == PositionalArgKind::Named,
+            _ => true,
+        }
+    }
+}
+
+impl fmt::Display for SchemaChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use SchemaChange::*;
+
+        match self {
+            AddedOption { name } => write!(f, "added --{}", name),
+            RemovedOption { name } => write!(f, "removed --{}", name),
+            ChangedKind { name, old, new } => {
+                write!(f, "--{} changed from {:?} to {:?}", name, old, new)
+            }
+            AddedAlias { name, alias } => {
+                write!(f, "added {}{} to --{}", dashes(alias), alias, name)
+            }
+            RemovedAlias { name, alias } => {
+                write!(f, "removed {}{} from --{}", dashes(alias), alias, name)
+            }
+            Tightened { name, constraint } => write!(f, "--{} has a tighter {}", name, constraint),
+            Loosened { name, constraint } => write!(f, "--{} has a looser {}", name, constraint),
+            ChangedOption { name, setting } => write!(f, "changed the {} of --{}", setting, name),
+            AddedPositional { index, kind } => {
+                write!(f, "added positional argument #{} ({:?})", index + 1, kind)
+            }
+            RemovedPositional { index, kind } => {
+                write!(f, "removed positional argument #{} ({:?})", index + 1, kind)
+            }
+            ChangedPositional { index, old, new } => write!(
+                f,
+                "positional argument #{} changed from {:?} to {:?}",
+                index + 1,
+                old,
+                new
+            ),
+            TightenedPositional { index, constraint } => write!(
+                f,
+                "positional argument #{} has a tighter {}",
+                index + 1,
+                constraint
+            ),
+            LoosenedPositional { index, constraint } => write!(
+                f,
+                "positional argument #{} has a looser {}",
+                index + 1,
+                constraint
+            ),
+            AddedGroup { name } => write!(f, "added group {}", name),
+            RemovedGroup { name } => write!(f, "removed group {}", name),
+            ChangedGroup { name } => write!(f, "changed group {}", name),
+            ChangedParser { setting } => write!(f, "changed the {} of the parser", setting),
+            LoosenedParser { setting } => write!(f, "loosened the {} of the parser", setting),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CompatReport {
+    pub changes: Vec<SchemaChange>,
+}
+
+impl CompatReport {
+    pub fn is_breaking(&self) -> bool {
+        self.changes.iter().any(SchemaChange::is_breaking)
+    }
+
+    pub fn breaking_changes(&self) -> impl Iterator<Item = &SchemaChange> {
+        self.changes.iter().filter(|change| change.is_breaking())
+    }
+}
+
+impl fmt::Display for CompatReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for change in &self.changes {
+            let label = if change.is_breaking() {
+                "breaking"
+            } else {
+                "compatible"
+            };
+
+            writeln!(f, "{}: {}", label, change)?;
+        }
+
+        Ok(())
+    }
+}
+
+pub fn compare_schemas(old: &ArgParser, new: &ArgParser) -> CompatReport {
+    use SchemaChange::*;
+
+    let mut changes = vec![];
+
+    for (setting, changed) in [
+        ("mode", old.mode != new.mode),
+        (
+            "option prefixes",
+            (old.long_prefix, old.short_prefix) != (new.long_prefix, new.short_prefix),
+        ),
+        ("terminator", old.terminator != new.terminator),
+        (
+            "terminator handling",
+            old.keep_terminator != new.keep_terminator,
+        ),
+        ("rest capture", old.capture_rest != new.capture_rest),
+        ("split style", old.split_style != new.split_style),
+        ("response files", old.response_files != new.response_files),
+        (
+            "stdin arguments",
+            old.args_from_stdin != new.args_from_stdin,
+        ),
+        (
+            "ignored options",
+            diff(&old.ignored_options, &new.ignored_options) != (vec![], vec![]),
+        ),
+    ] {
+        if changed {
+            changes.push(ChangedParser { setting });
+        }
+    }
+
+    if old.value_style != new.value_style {
+        changes.push(if new.value_style == ValueStyle::Both {
+            LoosenedParser {
+                setting: "value style",
+            }
+        } else {
+            ChangedParser {
+                setting: "value style",
+            }
+        });
+    }
+
+    // Each of these lets more command lines through when it is on
+    for (setting, old, new) in [
+        ("abbreviations", old.abbreviations, new.abbreviations),
+        ("case sensitivity", old.ignore_case, new.ignore_case),
+        (
+            "underscore normalization",
+            old.normalize_underscores,
+            new.normalize_underscores,
+        ),
+        ("unknown options", old.allow_unknown, new.allow_unknown),
+        ("plus flags", old.plus_flags, new.plus_flags),
+        ("slash options", old.slash_options, new.slash_options),
+        ("colon separator", old.colon_separator, new.colon_separator),
+        ("unicode aliases", old.unicode_aliases, new.unicode_aliases),
+        (
+            "negative numbers",
+            old.negative_numbers,
+            new.negative_numbers,
+        ),
+        (
+            "unknown option handling",
+            old.stop_at_unknown,
+            new.stop_at_unknown,
+        ),
+        (
+            "positional strictness",
+            !old.strict_positional,
+            !new.strict_positional,
+        ),
+    ] {
+        match (old, new) {
+            (true, false) => changes.push(ChangedParser { setting }),
+            (false, true) => changes.push(LoosenedParser { setting }),
+            _ => {}
+        }
+    }
+
+    // No limit at all means any number. The depth only matters while response files are read,
+    // and turning them on or off is already a change of its own.
+    let limit = |max: Option<usize>| max.map(|max| 0..=max);
+    let reading = old.response_files && new.response_files;
+    let depth = |parser: &ArgParser| reading.then_some(0..=parser.max_response_file_depth);
+
+    for (setting, old, new) in [
+        ("argument limit", limit(old.max_args), limit(new.max_args)),
+        (
+            "input size limit",
+            limit(old.max_input_bytes),
+            limit(new.max_input_bytes),
+        ),
+        ("response file depth", depth(old), depth(new)),
+    ] {
+        let (tighter, looser) = bounds(old.as_ref(), new.as_ref());
+
+        if tighter {
+            changes.push(ChangedParser { setting });
+        }
+
+        if looser {
+            changes.push(LoosenedParser { setting });
+        }
+    }
+
+    // Without signatures only the positional arguments apply, so adding the first one narrows
+    // what is accepted, while adding one more next to others only lets more through
+    let signatures = |parser: &ArgParser| {
+        parser
+            .signatures
+            .iter()
+            .map(|args| args.iter().map(canonical_positional).collect::<Vec<_>>())
+            .collect::<Vec<_>>()
+    };
+    let old_signatures = signatures(old);
+    let new_signatures = signatures(new);
+    let removed = old_signatures.iter().any(|s| !new_signatures.contains(s));
+    let added = new_signatures.iter().any(|s| !old_signatures.contains(s));
+
+    if removed || (added && old_signatures.is_empty()) {
+        changes.push(ChangedParser {
+            setting: "signatures",
+        });
+    } else if added {
+        changes.push(LoosenedParser {
+            setting: "signatures",
+        });
+    }
+
+    let mut names = old
+        .options
+        .keys()
+        .chain(new.options.keys())
+        .copied()
+        .collect::<Vec<_>>();
+
+    names.sort_unstable();
+    names.dedup();
+
+    for name in names {
+        match (old.options.get(name), new.options.get(name)) {
+            (Some(_), None) => changes.push(RemovedOption { name }),
+            (None, Some(_)) => changes.push(AddedOption { name }),
+            (Some(old), Some(new)) => compare_options(&mut changes, name, old, new),
+            (None, None) => {}
+        }
+    }
+
+    for index in 0..old.positional.len().max(new.positional.len()) {
+        match (old.positional.get(index), new.positional.get(index)) {
+            (Some(old), None) => changes.push(RemovedPositional {
+                index,
+                kind: old.kind.clone(),
+            }),
+            (None, Some(new)) => changes.push(AddedPositional {
+                index,
+                kind: new.kind.clone(),
+            }),
+            (Some(old), Some(new)) if old.kind != new.kind => changes.push(ChangedPositional {
+                index,
+                old: old.kind.clone(),
+                new: new.kind.clone(),
+            }),
+            (Some(old), Some(new)) => compare_positionals(&mut changes, index, old, new),
+            (None, None) => {}
+        }
+    }
+
+    let mut groups = old
+        .groups
+        .iter()
+        .chain(&new.groups)
+        .map(|group| group.name)
+        .collect::<Vec<_>>();
+
+    groups.sort_unstable();
+    groups.dedup();
+
+    // Any group can reject a command line, and whether different members or a different kind
+    // make it stricter depends on what is on the command line, so every change counts as breaking
+    for name in groups {
+        let find = |parser: &ArgParser| {
+            parser
+                .groups
+                .iter()
+                .find(|group| group.name == name)
+                .map(|group| {
+                    let mut options = group.options.clone();
+                    options.sort_unstable();
+                    (group.kind.clone(), options)
+                })
+        };
+
+        match (find(old), find(new)) {
+            (Some(_), None) => changes.push(RemovedGroup { name }),
+            (None, Some(_)) => changes.push(AddedGroup { name }),
+            (Some(old), Some(new)) if old != new => changes.push(ChangedGroup { name }),
+            _ => {}
+        }
+    }
+
+    CompatReport { changes }
+}
+
+fn compare_positionals(
+    changes: &mut Vec<SchemaChange>,
+    index: usize,
+    old: &PositionalArg,
+    new: &PositionalArg,
+) {
+    use SchemaChange::*;
+
+    let mut compare = |constraint, tighter: bool, looser: bool| {
+        if tighter {
+            changes.push(TightenedPositional { index, constraint });
+        }
+
+        if looser {
+            changes.push(LoosenedPositional { index, constraint });
+        }
+    };
+
+    let (tighter, looser) = bounds(old.range.as_ref(), new.range.as_ref());
+
+    compare("range", tighter, looser);
+    let (tighter, looser) = choices(&old.choices, &new.choices);
+
+    compare("choice list", tighter, looser);
+    compare(
+        "validator",
+        old.validator.is_none() && new.validator.is_some(),
+        old.validator.is_some() && new.validator.is_none(),
+    );
+    compare(
+        "existence check",
+        !old.must_exist && new.must_exist,
+        old.must_exist && !new.must_exist,
+    );
+    compare("minimum count", new.min > old.min, new.min < old.min);
+
+    // Decides whether options after the rest are parsed or taken as values, both ways
+    if old.rest_policy != new.rest_policy {
+        compare("rest policy", true, false);
+    }
+}
+
+fn compare_options(
+    changes: &mut Vec<SchemaChange>,
+    name: &'static str,
+    old: &OptionalArg,
+    new: &OptionalArg,
+) {
+    use SchemaChange::*;
+
+    // Nothing else is comparable once the option takes a different shape of value
+    if old.kind != new.kind {
+        changes.push(ChangedKind {
+            name,
+            old: old.kind.clone(),
+            new: new.kind.clone(),
+        });
+        return;
+    }
+
+    let (removed, added) = diff(&old.aliases, &new.aliases);
+
+    changes.extend(
+        removed
+            .into_iter()
+            .map(|alias| RemovedAlias { name, alias }),
+    );
+    changes.extend(added.into_iter().map(|alias| AddedAlias { name, alias }));
+
+    let mut compare = |constraint, tighter: bool, looser: bool| {
+        if tighter {
+            changes.push(Tightened { name, constraint });
+        }
+
+        if looser {
+            changes.push(Loosened { name, constraint });
+        }
+    };
+
+    for (constraint, old, new) in [
+        ("repetition", old.multiple, new.multiple),
+        ("eager handling", old.eager, new.eager),
+        ("negation", old.negatable, new.negatable),
+        ("plus negation", old.plus_negatable, new.plus_negatable),
+        (
+            "hyphen values",
+            old.allow_hyphen_values,
+            new.allow_hyphen_values,
+        ),
+        ("other values", old.or_other, new.or_other),
+        ("command line presence", !old.env_only, !new.env_only),
+        (
+            "duplicate policy",
+            old.duplicates != DuplicateOptions::Error,
+            new.duplicates != DuplicateOptions::Error,
+        ),
+    ] {
+        compare(constraint, old && !new, !old && new);
+    }
+
+    let (tighter, looser) = bounds(Some(&old.arity), Some(&new.arity));
+
+    compare("arity", tighter, looser);
+
+    let (tighter, looser) = bounds(Some(&old.occurrences), Some(&new.occurrences));
+
+    compare("occurrence count", tighter, looser);
+
+    let old_style = old.value_style.unwrap_or(ValueStyle::Both);
+    let new_style = new.value_style.unwrap_or(ValueStyle::Both);
+
+    compare(
+        "value style",
+        old_style != new_style && new_style != ValueStyle::Both,
+        old_style != new_style && new_style == ValueStyle::Both,
+    );
+
+    // Values that used to be taken as they are now have to be in the encoding, or the other way
+    // round they are no longer decoded
+    compare(
+        "encoding",
+        old.encoding.is_none() && new.encoding.is_some(),
+        false,
+    );
+
+    let (tighter, looser) = match (old.key_values, new.key_values) {
+        (None, Some(_)) => (true, false),
+        (Some(_), None) => (false, true),
+        (Some(old), Some(new)) => (
+            old != DuplicateKeys::Error && new == DuplicateKeys::Error,
+            old == DuplicateKeys::Error && new != DuplicateKeys::Error,
+        ),
+        (None, None) => (false, false),
+    };
+
+    compare("key-value format", tighter, looser);
+
+    let (tighter, looser) = bounds(old.range.as_ref(), new.range.as_ref());
+
+    compare("range", tighter, looser);
+
+    let (tighter, looser) = choices(&old.choices, &new.choices);
+
+    compare("choice list", tighter, looser);
+    compare(
+        "validator",
+        old.validator.is_none() && new.validator.is_some(),
+        old.validator.is_some() && new.validator.is_none(),
+    );
+
+    let (removed, added) = diff(&old.requires, &new.requires);
+
+    compare("requirements", !added.is_empty(), !removed.is_empty());
+
+    let (removed, added) = diff(&old.conflicts, &new.conflicts);
+
+    compare("conflicts", !added.is_empty(), !removed.is_empty());
+
+    let changed_duplicates = (old.duplicates != DuplicateOptions::Error
+        && new.duplicates != DuplicateOptions::Error
+        && old.duplicates != new.duplicates)
+        .then_some("duplicate policy");
+    let changed_keys = matches!(
+        (old.key_values, new.key_values),
+        (Some(old), Some(new)) if old != new
+            && old != DuplicateKeys::Error
+            && new != DuplicateKeys::Error
+    )
+    .then_some("duplicate key policy");
+    let changed_encoding = matches!(
+        (old.encoding, new.encoding),
+        (Some(old), new) if Some(old) != new
+    )
+    .then_some("encoding");
+
+    for setting in [
+        changed_duplicates,
+        changed_keys,
+        changed_encoding,
+        (old.delimiter != new.delimiter).then_some("delimiter"),
+        (old.value_terminator != new.value_terminator).then_some("value terminator"),
+    ]
+    .into_iter()
+    .flatten()
+    {
+        changes.push(ChangedOption { name, setting });
+    }
+}
+
+// No choices at all means any value
+fn choices(old: &[&'static str], new: &[&'static str]) -> (bool, bool) {
+    let (removed, added) = diff(old, new);
+
+    (
+        !new.is_empty() && (old.is_empty() || !removed.is_empty()),
+        !old.is_empty() && (new.is_empty() || !added.is_empty()),
+    )
+}
+
+fn diff<T: Copy + PartialEq>(old: &[T], new: &[T]) -> (Vec<T>, Vec<T>) {
+    (
+        old.iter().filter(|x| !new.contains(x)).copied().collect(),
+        new.iter().filter(|x| !old.contains(x)).copied().collect(),
+    )
+}
+
+// `None` stands for no bounds at all
+fn bounds<T: PartialOrd>(
+    old: Option<&RangeInclusive<T>>,
+    new: Option<&RangeInclusive<T>>,
+) -> (bool, bool) {
+    match (old, new) {
+        (None, None) => (false, false),
+        (None, Some(_)) => (true, false),
+        (Some(_), None) => (false, true),
+        (Some(old), Some(new)) => (
+            new.start() > old.start() || new.end() < old.end(),
+            new.start() < old.start() || new.end() > old.end(),
+        ),
+    }
+}
+
+#[test]
+fn test_compare_schemas() -> Result<(), super::ArgParserError> {
+    use super::PositionalArg;
+    use SchemaChange::*;
+
+    let mut old = ArgParser::default();
+
+    old.add_option(OptionalArg::flag("force").alias("f"))?
+        .add_option(OptionalArg::required_value("color").choices(&["auto", "never"]))?
+        .add_option(OptionalArg::values("files", 1..=3))?
+        .add_option(OptionalArg::count("verbose"))?
+        .add_positional(PositionalArg::named())?;
+
+    assert_eq!(CompatReport::default(), compare_schemas(&old, &old.clone()));
+
+    let mut new = ArgParser::default();
+
+    new.add_option(OptionalArg::flag("quiet"))?
+        .add_option(OptionalArg::flag("force").alias("F"))?
+        .add_option(OptionalArg::required_value("color").choices(&["auto", "never", "always"]))?
+        .add_option(OptionalArg::values("files", 1..=2))?
+        .add_option(OptionalArg::flag("verbose"))?
+        .add_positional(PositionalArg::named())?
+        .add_positional(PositionalArg::optional())?
+        .allow_abbreviations(true);
+
+    let report = compare_schemas(&old, &new);
+
+    assert_eq!(
+        vec![
+            LoosenedParser {
+                setting: "abbreviations"
+            },
+            Loosened {
+                name: "color",
+                constraint: "choice list"
+            },
+            Tightened {
+                name: "files",
+                constraint: "arity"
+            },
+            RemovedAlias {
+                name: "force",
+                alias: "f"
+            },
+            AddedAlias {
+                name: "force",
+                alias: "F"
+            },
+            AddedOption { name: "quiet" },
+            ChangedKind {
+                name: "verbose",
+                old: OptionalArgKind::Count,
+                new: OptionalArgKind::Flag
+            },
+            AddedPositional {
+                index: 1,
+                kind: PositionalArgKind::Optional
+            }
+        ],
+        report.changes
+    );
+    assert!(report.is_breaking());
+    assert_eq!(3, report.breaking_changes().count());
+    assert_eq!(
+        "removed -f from --force",
+        report.breaking_changes().nth(1).unwrap().to_string()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_compare_schema_constraints() -> Result<(), super::ArgParserError> {
+    use super::{ArgGroup, Encoding, PositionalArg};
+    use SchemaChange::*;
+
+    let build = |tag: OptionalArg, exec: OptionalArg, positional: PositionalArg| {
+        let mut parser = ArgParser::default();
+
+        parser
+            .add_option(OptionalArg::flag("json"))?
+            .add_option(OptionalArg::flag("yaml"))?
+            .add_option(tag)?
+            .add_option(exec)?
+            .add_positional(positional)?;
+
+        Ok::<_, super::ArgParserError>(parser)
+    };
+    let tag = || OptionalArg::required_value("tag").multiple();
+    let exec = || OptionalArg::values("exec", 1..=3);
+    let old = build(tag(), exec(), PositionalArg::named())?;
+    let breaking = |new: &ArgParser| {
+        compare_schemas(&old, new)
+            .breaking_changes()
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+    let rebuild = |tag, exec, positional| {
+        Ok::<_, super::ArgParserError>(breaking(&build(tag, exec, positional)?))
+    };
+
+    assert_eq!(
+        vec![Tightened {
+            name: "tag",
+            constraint: "occurrence count"
+        }],
+        rebuild(tag().min_occurrences(1), exec(), PositionalArg::named())?
+    );
+    assert_eq!(
+        vec![ChangedOption {
+            name: "tag",
+            setting: "delimiter"
+        }],
+        rebuild(tag().delimiter(','), exec(), PositionalArg::named())?
+    );
+    assert_eq!(
+        vec![Tightened {
+            name: "tag",
+            constraint: "key-value format"
+        }],
+        rebuild(
+            tag().key_value(super::DuplicateKeys::LastWins),
+            exec(),
+            PositionalArg::named()
+        )?
+    );
+    assert_eq!(
+        vec![Tightened {
+            name: "tag",
+            constraint: "encoding"
+        }],
+        rebuild(
+            tag().encoding(Encoding::Hex),
+            exec(),
+            PositionalArg::named()
+        )?
+    );
+    assert_eq!(
+        vec![ChangedOption {
+            name: "exec",
+            setting: "value terminator"
+        }],
+        rebuild(tag(), exec().value_terminator(";"), PositionalArg::named())?
+    );
+
+    for (positional, constraint) in [
+        (PositionalArg::named().range(0..=9), "range"),
+        (PositionalArg::named().choices(&["a", "b"]), "choice list"),
+    ] {
+        assert_eq!(
+            vec![TightenedPositional {
+                index: 0,
+                constraint
+            }],
+            rebuild(tag(), exec(), positional)?
+        );
+    }
+
+    // Only a rest arg has a minimum
+    let report = compare_schemas(
+        &build(tag(), exec(), PositionalArg::rest())?,
+        &build(tag(), exec(), PositionalArg::rest().min(2))?,
+    );
+
+    assert_eq!(
+        vec![TightenedPositional {
+            index: 0,
+            constraint: "minimum count"
+        }],
+        report.changes
+    );
+
+    let mut new = old.clone();
+
+    new.add_group(ArgGroup::exactly_one("format", &["json", "yaml"]))?;
+
+    assert_eq!(vec![AddedGroup { name: "format" }], breaking(&new));
+
+    new = old.clone();
+    new.strict_positional(true);
+
+    assert_eq!(
+        vec![ChangedParser {
+            setting: "positional strictness"
+        }],
+        breaking(&new)
+    );
+
+    new = old.clone();
+    new.max_args(10);
+
+    assert_eq!(
+        vec![ChangedParser {
+            setting: "argument limit"
+        }],
+        breaking(&new)
+    );
+
+    new = old.clone();
+    new.add_signature(vec![PositionalArg::named()])?;
+
+    assert_eq!(
+        vec![ChangedParser {
+            setting: "signatures"
+        }],
+        breaking(&new)
+    );
+
+    // With a signature already there, another one only adds a way to call the tool
+    let mut with_signature = new.clone();
+
+    with_signature.add_signature(vec![PositionalArg::rest()])?;
+
+    let report = compare_schemas(&new, &with_signature);
+
+    assert_eq!(
+        vec![LoosenedParser {
+            setting: "signatures"
+        }],
+        report.changes
+    );
+    assert!(!report.is_breaking());
+
+    Ok(())
+}
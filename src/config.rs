@@ -0,0 +1,711 @@
+use super::{
+    ArgParser, ArgParserError, FileSource, FileSystem, OptionalArg, OptionalArgKind, ParsedArg,
+    ValueSource,
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+};
+
+type ConfigKey = Vec<String>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum ConfigValue {
+    Str(String),
+    Number(String),
+    Bool(bool),
+    Array(Vec<ConfigValue>),
+}
+
+impl ConfigValue {
+    fn to_scalar(&self) -> Result<String, String> {
+        match self {
+            ConfigValue::Str(s) | ConfigValue::Number(s) => Ok(s.clone()),
+            ConfigValue::Bool(b) => Ok(b.to_string()),
+            ConfigValue::Array(_) => Err("expected a single value".to_string()),
+        }
+    }
+
+    fn to_scalars(&self) -> Result<Vec<String>, String> {
+        match self {
+            ConfigValue::Array(items) => items.iter().map(ConfigValue::to_scalar).collect(),
+            _ => Err("expected an array".to_string()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Config {
+    pub(crate) path: String,
+    pub(crate) values: BTreeMap<ConfigKey, ConfigValue>,
+}
+
+// Only the subset of TOML that can be mapped onto options is supported: tables, dotted keys,
+// inline tables, strings, numbers, booleans and arrays; anything else is reported as an error
+struct TomlParser<'a> {
+    input: &'a str,
+    pos: usize,
+    line: usize,
+}
+
+impl<'a> TomlParser<'a> {
+    fn new(input: &'a str) -> Self {
+        Self {
+            input,
+            pos: 0,
+            line: 1,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+
+        self.pos += c.len_utf8();
+
+        if c == '\n' {
+            self.line += 1;
+        }
+
+        Some(c)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() != Some(c) {
+            return false;
+        }
+
+        self.bump();
+        true
+    }
+
+    fn error(&self, reason: &str) -> String {
+        format!("line {}: {}", self.line, reason)
+    }
+
+    fn skip_ws(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t')) {
+            self.bump();
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        if self.peek() == Some('#') {
+            while !matches!(self.peek(), None | Some('\n')) {
+                self.bump();
+            }
+        }
+    }
+
+    fn skip_blank(&mut self) {
+        loop {
+            self.skip_ws();
+            self.skip_comment();
+
+            if !self.eat('\n') && !self.eat('\r') {
+                break;
+            }
+        }
+    }
+
+    fn expect_eol(&mut self) -> Result<(), String> {
+        self.skip_ws();
+        self.skip_comment();
+        self.eat('\r');
+
+        if self.peek().is_some() && !self.eat('\n') {
+            return Err(self.error("expected a new line"));
+        }
+
+        Ok(())
+    }
+
+    fn parse(mut self) -> Result<BTreeMap<ConfigKey, ConfigValue>, String> {
+        let mut values = BTreeMap::new();
+        let mut tables = vec![];
+        let mut table = vec![];
+
+        loop {
+            self.skip_blank();
+
+            match self.peek() {
+                None => break,
+                Some('[') => {
+                    self.bump();
+
+                    if self.peek() == Some('[') {
+                        return Err(self.error("arrays of tables are not supported"));
+                    }
+
+                    table = self.key()?;
+
+                    if !self.eat(']') {
+                        return Err(self.error("expected ']'"));
+                    }
+
+                    if tables.contains(&table) {
+                        return Err(
+                            self.error(&format!("table '{}' defined twice", table.join(".")))
+                        );
+                    }
+
+                    tables.push(table.clone());
+                }
+                Some(_) => self.key_value(&mut values, table.clone())?,
+            }
+
+            self.expect_eol()?;
+        }
+
+        Ok(values)
+    }
+
+    fn key(&mut self) -> Result<ConfigKey, String> {
+        let mut key = vec![];
+
+        loop {
+            self.skip_ws();
+
+            let segment = match self.peek() {
+                Some('"') => self.basic_string()?,
+                Some('\'') => self.literal_string()?,
+                _ => {
+                    let start = self.pos;
+
+                    while self
+                        .peek()
+                        .is_some_and(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+                    {
+                        self.bump();
+                    }
+
+                    if start == self.pos {
+                        return Err(self.error("expected a key"));
+                    }
+
+                    self.input[start..self.pos].to_string()
+                }
+            };
+
+            key.push(segment);
+            self.skip_ws();
+
+            if !self.eat('.') {
+                return Ok(key);
+            }
+        }
+    }
+
+    fn key_value(
+        &mut self,
+        values: &mut BTreeMap<ConfigKey, ConfigValue>,
+        mut key: ConfigKey,
+    ) -> Result<(), String> {
+        key.extend(self.key()?);
+
+        if !self.eat('=') {
+            return Err(self.error("expected '='"));
+        }
+
+        self.skip_ws();
+
+        if self.eat('{') {
+            self.skip_ws();
+
+            if self.eat('}') {
+                return Ok(());
+            }
+
+            loop {
+                self.key_value(values, key.clone())?;
+                self.skip_ws();
+
+                if self.eat('}') {
+                    return Ok(());
+                }
+
+                if !self.eat(',') {
+                    return Err(self.error("expected ',' or '}'"));
+                }
+            }
+        }
+
+        let value = self.value()?;
+
+        if values
+            .keys()
+            .any(|other: &ConfigKey| other.starts_with(&key) || key.starts_with(other))
+        {
+            return Err(self.error(&format!("key '{}' defined twice", key.join("."))));
+        }
+
+        values.insert(key, value);
+
+        Ok(())
+    }
+
+    fn value(&mut self) -> Result<ConfigValue, String> {
+        match self.peek() {
+            Some('"') => Ok(ConfigValue::Str(self.basic_string()?)),
+            Some('\'') => Ok(ConfigValue::Str(self.literal_string()?)),
+            Some('[') => {
+                self.bump();
+
+                let mut items = vec![];
+
+                loop {
+                    self.skip_blank();
+
+                    if self.eat(']') {
+                        return Ok(ConfigValue::Array(items));
+                    }
+
+                    items.push(self.value()?);
+                    self.skip_blank();
+
+                    if self.eat(']') {
+                        return Ok(ConfigValue::Array(items));
+                    }
+
+                    if !self.eat(',') {
+                        return Err(self.error("expected ',' or ']'"));
+                    }
+                }
+            }
+            _ => {
+                let start = self.pos;
+
+                while !matches!(
+                    self.peek(),
+                    None | Some(',' | ']' | '}' | '#' | ' ' | '\t' | '\r' | '\n')
+                ) {
+                    self.bump();
+                }
+
+                let token = &self.input[start..self.pos];
+                let number = token.replace('_', "");
+
+                match token {
+                    "" => Err(self.error("expected a value")),
+                    "true" => Ok(ConfigValue::Bool(true)),
+                    "false" => Ok(ConfigValue::Bool(false)),
+                    _ if number.parse::<i64>().is_ok()
+                        || (number.parse::<f64>().is_ok()
+                            && number.starts_with(|c: char| {
+                                c.is_ascii_digit() || c == '+' || c == '-'
+                            })) =>
+                    {
+                        Ok(ConfigValue::Number(number))
+                    }
+                    _ => Err(self.error(&format!("unsupported value '{}'", token))),
+                }
+            }
+        }
+    }
+
+    fn basic_string(&mut self) -> Result<String, String> {
+        let line = self.line;
+
+        self.bump();
+
+        if self.input[self.pos..].starts_with("\"\"") {
+            return Err(self.error("multi-line strings are not supported"));
+        }
+
+        let mut s = String::new();
+
+        loop {
+            match self.bump() {
+                None | Some('\n') => return Err(format!("line {}: unterminated string", line)),
+                Some('"') => return Ok(s),
+                Some('\\') => {
+                    let c = match self.bump() {
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some('r') => '\r',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some(u @ ('u' | 'U')) => {
+                            let len = if u == 'u' { 4 } else { 8 };
+                            let hex = self.input.get(self.pos..self.pos + len).unwrap_or("");
+
+                            self.pos += hex.len();
+
+                            u32::from_str_radix(hex, 16)
+                                .ok()
+                                .and_then(char::from_u32)
+                                .ok_or_else(|| self.error("invalid unicode escape"))?
+                        }
+                        _ => return Err(self.error("invalid escape sequence")),
+                    };
+
+                    s.push(c);
+                }
+                Some(c) => s.push(c),
+            }
+        }
+    }
+
+    fn literal_string(&mut self) -> Result<String, String> {
+        let line = self.line;
+
+        self.bump();
+
+        if self.input[self.pos..].starts_with("''") {
+            return Err(self.error("multi-line strings are not supported"));
+        }
+
+        let start = self.pos;
+
+        loop {
+            match self.bump() {
+                None | Some('\n') => return Err(format!("line {}: unterminated string", line)),
+                Some('\'') => return Ok(self.input[start..self.pos - 1].to_string()),
+                Some(_) => {}
+            }
+        }
+    }
+}
+
+impl OptionalArg {
+    fn config_args(&self, value: &ConfigValue) -> Result<Vec<ParsedArg>, String> {
+        use ConfigValue::*;
+
+        let name = self.name;
+
+        match (&self.kind, value) {
+            (OptionalArgKind::Flag, &Bool(value)) => Ok(vec![ParsedArg::Flag { name, value }]),
+            (OptionalArgKind::Flag, _) => Err("expected a boolean".to_string()),
+            (OptionalArgKind::Count, Number(n)) => Ok(vec![ParsedArg::Count {
+                name,
+                value: n
+                    .parse()
+                    .map_err(|_| "expected a non-negative integer".to_string())?,
+            }]),
+            (OptionalArgKind::Count, _) => Err("expected a non-negative integer".to_string()),
+            (OptionalArgKind::Values, Array(items))
+                if self.multiple
+                    && !items.is_empty()
+                    && items.iter().all(|item| matches!(item, Array(_))) =>
+            {
+                items
+                    .iter()
+                    .map(|item| self.parse_values(item.to_scalars()?))
+                    .collect()
+            }
+            (OptionalArgKind::Values, _) => Ok(vec![self.parse_values(value.to_scalars()?)?]),
+            (_, Array(items)) if self.multiple => items
+                .iter()
+                .map(|item| self.parse_value(&item.to_scalar()?))
+                .collect(),
+            _ => self
+                .split_value(&value.to_scalar()?)
+                .into_iter()
+                .map(|item| self.parse_value(item))
+                .collect(),
+        }
+    }
+}
+
+impl ArgParser {
+    pub fn with_config_file(
+        &mut self,
+        path: impl AsRef<Path>,
+    ) -> Result<&mut Self, ArgParserError> {
+        self.with_config_file_from(path, &FileSystem)
+    }
+
+    pub fn with_config_file_from(
+        &mut self,
+        path: impl AsRef<Path>,
+        files: &dyn FileSource,
+    ) -> Result<&mut Self, ArgParserError> {
+        let source =
+            files
+                .read_to_string(path.as_ref())
+                .map_err(|err| ArgParserError::InvalidConfig {
+                    path: path.as_ref().display().to_string(),
+                    reason: err.to_string(),
+                })?;
+
+        self.load_config(path.as_ref().display().to_string(), &source)
+    }
+
+    pub(crate) fn load_config(
+        &mut self,
+        path: String,
+        source: &str,
+    ) -> Result<&mut Self, ArgParserError> {
+        let values =
+            TomlParser::new(source)
+                .parse()
+                .map_err(|reason| ArgParserError::InvalidConfig {
+                    path: path.clone(),
+                    reason,
+                })?;
+
+        self.config = Some(Config { path, values });
+
+        Ok(self)
+    }
+
+    pub fn deny_unknown_config_keys(&mut self, deny: bool) -> &mut Self {
+        self.deny_unknown_config_keys = deny;
+        self
+    }
+
+    pub(crate) fn apply_config(
+        &self,
+        args: &mut Vec<ParsedArg>,
+        sources: &mut HashMap<&'static str, ValueSource>,
+    ) -> Result<Vec<String>, ArgParserError> {
+        let Some(config) = &self.config else {
+            return Ok(vec![]);
+        };
+
+        let mut known_keys = vec![];
+        let mut names = self.options.keys().copied().collect::<Vec<_>>();
+
+        names.sort_unstable();
+
+        for name in names {
+            let option = &self.options[name];
+
+            if option.env_only {
+                continue;
+            }
+
+            let path = option.config_path();
+
+            let Some(value) = config
+                .values
+                .get(&path.iter().map(|s| s.to_string()).collect::<Vec<_>>())
+            else {
+                continue;
+            };
+
+            known_keys.push(path);
+
+            if args.iter().any(|arg| arg.name() == Some(name)) {
+                continue;
+            }
+
+            let key = option.config_key.unwrap_or(name);
+
+            let config_args =
+                option
+                    .config_args(value)
+                    .map_err(|reason| ArgParserError::InvalidConfigValue {
+                        name,
+                        key,
+                        path: config.path.clone(),
+                        reason,
+                    })?;
+
+            args.extend(config_args);
+            sources.insert(name, ValueSource::Config(key));
+        }
+
+        let unknown_keys = config
+            .values
+            .keys()
+            .filter(|key| !known_keys.iter().any(|known| known == *key))
+            .map(|key| key.join("."))
+            .collect::<Vec<_>>();
+
+        if let Some(key) = unknown_keys
+            .first()
+            .filter(|_| self.deny_unknown_config_keys)
+        {
+            return Err(ArgParserError::UnknownConfigKey {
+                key: key.clone(),
+                path: config.path.clone(),
+            });
+        }
+
+        Ok(unknown_keys)
+    }
+}
+
+#[test]
+fn test_toml_parser() {
+    use ConfigValue::*;
+
+    let source = r#"
+# Comment
+verbose = 2
+name = "a \"b\"\tc \u00e9" # trailing comment
+'literal' = 'C:\dir'
+
+[database]
+url = "postgres://localhost"
+pool.size = 8
+ratio = -1.5e3
+tags = [
+    "x",
+    "y", # comment
+]
+ssl = { enabled = true, modes = [] }
+"#;
+
+    let values = TomlParser::new(source).parse().unwrap();
+    let key = |s: &str| s.split('.').map(|s| s.to_string()).collect::<Vec<_>>();
+
+    assert_eq!(
+        vec![
+            (key("database.pool.size"), Number("8".to_string())),
+            (key("database.ratio"), Number("-1.5e3".to_string())),
+            (key("database.ssl.enabled"), Bool(true)),
+            (key("database.ssl.modes"), Array(vec![])),
+            (
+                key("database.tags"),
+                Array(vec![Str("x".to_string()), Str("y".to_string())])
+            ),
+            (key("database.url"), Str("postgres://localhost".to_string())),
+            (key("literal"), Str("C:\\dir".to_string())),
+            (key("name"), Str("a \"b\"\tc é".to_string())),
+            (key("verbose"), Number("2".to_string())),
+        ],
+        values.into_iter().collect::<Vec<_>>()
+    );
+
+    let error = |source| TomlParser::new(source).parse().unwrap_err();
+
+    assert_eq!("line 1: expected '='", error("a b"));
+    assert_eq!("line 2: key 'a' defined twice", error("a = 1\na = 2"));
+    assert_eq!("line 2: key 'a.b' defined twice", error("a = 1\na.b = 2"));
+    assert_eq!("line 3: table 'a' defined twice", error("[a]\n[b]\n[a]"));
+    assert_eq!(
+        "line 1: unsupported value '1979-05-27'",
+        error("a = 1979-05-27")
+    );
+    assert_eq!("line 1: arrays of tables are not supported", error("[[a]]"));
+    assert_eq!(
+        "line 1: multi-line strings are not supported",
+        error("a = \"\"\"\n\"\"\"")
+    );
+    assert_eq!("line 1: unterminated string", error("a = \"b\nc\""));
+    assert_eq!("line 1: expected a new line", error("a = 1 2"));
+}
+
+#[test]
+fn test_parse_config() -> Result<(), ArgParserError> {
+    use super::{ArgSelector, Resolver};
+    use ParsedArg::*;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::required_value("db-url").config_key("database.url"))?
+        .add_option(OptionalArg::required_value("user").env("MYAPP_USER"))?
+        .add_option(OptionalArg::count("verbose"))?
+        .add_option(OptionalArg::required_value("tag").multiple())?
+        .add_option(OptionalArg::flag("dry-run"))?
+        .load_config(
+            "app.toml".to_string(),
+            "verbose = 2\ntag = ['a', 'b']\nuser = 'root'\ncolor = true\n\n[database]\nurl = 'sqlite:app.db'\n",
+        )?;
+
+    let env = |var: &str| (var == "MYAPP_USER").then(|| "admin".to_string());
+    let args = Resolver::new(&parser).env(env).resolve(&["--verbose"])?;
+    let sel = ArgSelector::from(&args);
+
+    assert_eq!(Some(&"sqlite:app.db".to_string()), sel.get_value("db-url"));
+    assert_eq!(Some(&"admin".to_string()), sel.get_value("user"));
+    assert_eq!(1, sel.get_count("verbose"));
+    assert_eq!(vec!["a", "b"], sel.get_values("tag"));
+    assert_eq!(
+        Some(ValueSource::Config("database.url")),
+        args.source("db-url")
+    );
+    assert_eq!(
+        Some(ValueSource::Env("MYAPP_USER".to_string())),
+        args.source("user")
+    );
+    assert_eq!(Some(ValueSource::CommandLine), args.source("verbose"));
+    assert_eq!(["color"], args.unknown_config_keys());
+    assert_eq!(Some("app.toml"), args.config_file());
+    assert_eq!(
+        "config key 'database.url'",
+        ArgSelector::from(&args)
+            .get_source("db-url")
+            .unwrap()
+            .to_string()
+    );
+    assert!(!args.contains(&Flag {
+        name: "dry-run",
+        value: true
+    }));
+
+    parser.deny_unknown_config_keys(true);
+
+    assert_eq!(
+        Err(ArgParserError::UnknownConfigKey {
+            key: "color".to_string(),
+            path: "app.toml".to_string()
+        }),
+        Resolver::new(&parser).env(env).resolve(&[])
+    );
+
+    parser.load_config("app.toml".to_string(), "dry-run = 'yes'")?;
+
+    assert_eq!(
+        "app.toml: cannot set --dry-run from 'dry-run': expected a boolean",
+        Resolver::new(&parser)
+            .env(env)
+            .resolve(&[])
+            .unwrap_err()
+            .to_string()
+    );
+
+    assert_eq!(
+        Err(ArgParserError::InvalidConfig {
+            path: "app.toml".to_string(),
+            reason: "line 1: expected a value".to_string()
+        }),
+        parser
+            .load_config("app.toml".to_string(), "a = ")
+            .map(|_| ())
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_config_file_from() -> Result<(), ArgParserError> {
+    use super::{ArgSelector, MemoryEnv, MemoryFiles, Resolver};
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::required_value("user").env("MYAPP_USER"))?
+        .add_option(OptionalArg::required_value("region"))?;
+
+    let files = MemoryFiles::new().file("/etc/app.toml", "user = 'root'\nregion = 'eu'\n");
+
+    parser.with_config_file_from("/etc/app.toml", &files)?;
+
+    let env = MemoryEnv::new().set("MYAPP_USER", "admin");
+    let args = Resolver::new(&parser).env_source(env).resolve(&[])?;
+    let sel = ArgSelector::from(&args);
+
+    assert_eq!(Some(&"admin".to_string()), sel.get_value("user"));
+    assert_eq!(Some(&"eu".to_string()), sel.get_value("region"));
+    assert_eq!(Some("/etc/app.toml"), args.config_file());
+    assert_eq!(
+        Err(ArgParserError::InvalidConfig {
+            path: "missing.toml".to_string(),
+            reason: "entity not found".to_string()
+        }),
+        parser
+            .with_config_file_from("missing.toml", &files)
+            .map(|_| ())
+    );
+
+    Ok(())
+}
@@ -0,0 +1,121 @@
+use super::{ArgSelector, Encoding, ParsedArg, ParsedArgs};
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+impl Encoding {
+    pub fn decode(self, value: &str) -> Result<Vec<u8>, String> {
+        match self {
+            Self::Base64 => decode_base64(value).ok_or_else(|| "is not valid base64".to_string()),
+            Self::Hex => decode_hex(value).ok_or_else(|| "is not valid hex".to_string()),
+        }
+    }
+}
+
+// Padding is optional, but if present it has to be right
+fn decode_base64(value: &str) -> Option<Vec<u8>> {
+    let data = value.trim_end_matches('=');
+
+    if (data.len() < value.len() && !value.len().is_multiple_of(4)) || data.len() % 4 == 1 {
+        return None;
+    }
+
+    let digits = data
+        .bytes()
+        .map(|b| BASE64.iter().position(|&c| c == b).map(|d| d as u32))
+        .collect::<Option<Vec<_>>>()?;
+
+    let mut bytes = vec![];
+
+    for chunk in digits.chunks(4) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0, |n, (idx, d)| n | d << (18 - 6 * idx));
+
+        bytes.extend(&n.to_be_bytes()[1..chunk.len()]);
+    }
+
+    Some(bytes)
+}
+
+fn decode_hex(value: &str) -> Option<Vec<u8>> {
+    if !value.len().is_multiple_of(2) || !value.is_ascii() {
+        return None;
+    }
+
+    (0..value.len())
+        .step_by(2)
+        .map(|idx| u8::from_str_radix(&value[idx..idx + 2], 16).ok())
+        .collect()
+}
+
+impl<'a> ArgSelector<'a, &'a ParsedArgs> {
+    pub fn get_bytes_value(&self, name: &str) -> Option<Vec<u8>> {
+        let value = self.args.iter().find_map(|arg| match arg {
+            ParsedArg::RequiredValue { name: _name, value }
+            | ParsedArg::OptionalValue {
+                name: _name,
+                value: Some(value),
+            } if name == *_name => Some(value),
+            _ => None,
+        })?;
+
+        match self.parsed.tables.encodings.get(name) {
+            Some(encoding) => encoding.decode(value).ok(),
+            None => Some(value.as_bytes().to_vec()),
+        }
+    }
+}
+
+#[test]
+fn test_decode() {
+    assert_eq!(Ok(b"".to_vec()), Encoding::Base64.decode(""));
+    assert_eq!(Ok(b"f".to_vec()), Encoding::Base64.decode("Zg=="));
+    assert_eq!(Ok(b"fo".to_vec()), Encoding::Base64.decode("Zm8"));
+    assert_eq!(Ok(b"foob".to_vec()), Encoding::Base64.decode("Zm9vYg=="));
+    assert_eq!(Ok(b"foobar".to_vec()), Encoding::Base64.decode("Zm9vYmFy"));
+    assert_eq!(Ok(vec![0xfb, 0xff]), Encoding::Base64.decode("+/8="));
+    assert!(Encoding::Base64.decode("Zm9vY").is_err());
+    assert!(Encoding::Base64.decode("Zm9=").is_ok());
+    assert!(Encoding::Base64.decode("Zg=").is_err());
+    assert!(Encoding::Base64.decode("Zm 9v").is_err());
+
+    assert_eq!(
+        Ok(vec![0xde, 0xad, 0xbe, 0xef]),
+        Encoding::Hex.decode("deADbeef")
+    );
+    assert_eq!(
+        Err("is not valid hex".to_string()),
+        Encoding::Hex.decode("abc")
+    );
+    assert!(Encoding::Hex.decode("zz").is_err());
+    assert!(Encoding::Hex.decode("€a").is_err());
+}
+
+#[test]
+fn test_get_bytes_value() -> Result<(), super::ArgParserError> {
+    use super::{ArgParser, ArgParserError, OptionalArg};
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::required_value("key").encoding(Encoding::Hex))?
+        .add_option(OptionalArg::required_value("name"))?;
+
+    let args = parser.parse(&["--key=00ff", "--name=abc"])?;
+    let sel = ArgSelector::from(&args);
+
+    assert_eq!(Some(vec![0x00, 0xff]), sel.get_bytes_value("key"));
+    assert_eq!(Some(b"abc".to_vec()), sel.get_bytes_value("name"));
+    assert_eq!(None, sel.get_bytes_value("other"));
+    assert_eq!(
+        Err(ArgParserError::RejectedValue {
+            name: "key",
+            value: "0xff".to_string(),
+            reason: "is not valid hex".to_string()
+        }),
+        parser.parse(&["--key=0xff"]).map(|_| ())
+    );
+
+    Ok(())
+}
@@ -0,0 +1,254 @@
+use super::{ArgParser, ArgParserError, EnvSource, OptionalArg, ParsedArg, ValueSource};
+use std::{borrow::Cow, collections::HashMap};
+
+fn env_var_name(prefix: &str, name: &str) -> String {
+    format!("{}{}", prefix, name.to_ascii_uppercase().replace('-', "_"))
+}
+
+impl ArgParser {
+    pub fn env_prefix(&mut self, prefix: &'static str) -> &mut Self {
+        self.env_prefix = Some(prefix);
+        self
+    }
+
+    // Names derived from the prefix are worked out when needed rather than stored with the option
+    pub(crate) fn env_var(&self, option: &OptionalArg) -> Option<Cow<'static, str>> {
+        match (option.env, self.env_prefix) {
+            (Some(var), _) => Some(Cow::Borrowed(var)),
+            (None, Some(prefix)) => Some(Cow::Owned(env_var_name(prefix, option.name))),
+            (None, None) => None,
+        }
+    }
+
+    pub(crate) fn apply_env(
+        &self,
+        args: &mut Vec<ParsedArg>,
+        sources: &mut HashMap<&'static str, ValueSource>,
+        env: &dyn EnvSource,
+    ) -> Result<(), ArgParserError> {
+        let mut names = self.options.keys().copied().collect::<Vec<_>>();
+
+        names.sort_unstable();
+
+        for name in names {
+            let option = &self.options[name];
+
+            let Some(var) = self.env_var(option) else {
+                continue;
+            };
+
+            if args.iter().any(|arg| arg.name() == Some(name)) {
+                continue;
+            }
+
+            let Some(value) = env.var(&var) else {
+                continue;
+            };
+
+            for item in option.split_value(&value) {
+                let arg =
+                    option
+                        .parse_value(item)
+                        .map_err(|reason| ArgParserError::InvalidEnvValue {
+                            name,
+                            var: var.to_string(),
+                            value: value.clone(),
+                            reason,
+                        })?;
+
+                args.push(arg);
+            }
+            sources.insert(name, ValueSource::Env(var.into_owned()));
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn test_parse_env_only() -> Result<(), ArgParserError> {
+    use super::{OptionalArg, Resolver};
+    use ArgParserError::*;
+    use ParsedArg::*;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(
+            OptionalArg::required_value("pool-size")
+                .env_only("MYAPP_POOL_SIZE")
+                .range(1..),
+        )?
+        .add_option(OptionalArg::flag("trace").env_only("MYAPP_TRACE"))?
+        .add_option(OptionalArg::flag("verbose"))?;
+
+    let env = |var: &str| match var {
+        "MYAPP_POOL_SIZE" => Some("8".to_string()),
+        _ => None,
+    };
+
+    let args = Resolver::new(&parser).env(env).resolve(&["--verbose"])?;
+
+    assert_eq!(
+        vec![
+            Flag {
+                name: "verbose",
+                value: true
+            },
+            RequiredValue {
+                name: "pool-size",
+                value: "8".to_string()
+            }
+        ],
+        *args
+    );
+    assert_eq!(
+        Some(ValueSource::Env("MYAPP_POOL_SIZE".to_string())),
+        args.source("pool-size")
+    );
+    assert_eq!(Some(ValueSource::CommandLine), args.source("verbose"));
+    assert_eq!(None, args.source("trace"));
+
+    assert_eq!(
+        Err(UnknownOption {
+            name: "pool-size".to_string(),
+            suggestion: None
+        }),
+        Resolver::new(&parser).env(env).resolve(&["--pool-size=4"])
+    );
+    assert_eq!(
+        Err(InvalidEnvValue {
+            name: "pool-size",
+            var: "MYAPP_POOL_SIZE".to_string(),
+            value: "0".to_string(),
+            reason: "must be at least 1".to_string()
+        }),
+        Resolver::new(&parser)
+            .env(|_| Some("0".to_string()))
+            .resolve(&[])
+    );
+    assert_eq!(
+        "$MYAPP_TRACE cannot set --trace to 'yes': expected 'true' or 'false'",
+        Resolver::new(&parser)
+            .env(|var| Some(if var == "MYAPP_TRACE" { "yes" } else { "1" }.to_string()))
+            .resolve(&[])
+            .unwrap_err()
+            .to_string()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_env() -> Result<(), ArgParserError> {
+    use super::{ArgSelector, OptionalArg, Resolver};
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(
+            OptionalArg::required_value("token")
+                .env("MYAPP_TOKEN")
+                .help("API token"),
+        )?
+        .add_option(
+            OptionalArg::count("verbose")
+                .alias("v")
+                .env("MYAPP_VERBOSE"),
+        )?;
+
+    let env = |var: &str| match var {
+        "MYAPP_TOKEN" => Some("from-env".to_string()),
+        "MYAPP_VERBOSE" => Some("2".to_string()),
+        _ => None,
+    };
+
+    let args = Resolver::new(&parser)
+        .env(env)
+        .resolve(&["--token=from-cli"])?;
+    let sel = ArgSelector::from(&args);
+
+    assert_eq!(Some("from-cli"), sel.get_value("token").map(String::as_str));
+    assert_eq!(Some(ValueSource::CommandLine), args.source("token"));
+    assert_eq!(2, sel.get_count("verbose"));
+    assert_eq!(
+        Some(ValueSource::Env("MYAPP_VERBOSE".to_string())),
+        args.source("verbose")
+    );
+
+    let args = Resolver::new(&parser).env(env).resolve(&["-v"])?;
+    let sel = ArgSelector::from(&args);
+
+    assert_eq!(Some("from-env"), sel.get_value("token").map(String::as_str));
+    assert_eq!(
+        Some(ValueSource::Env("MYAPP_TOKEN".to_string())),
+        args.source("token")
+    );
+    assert_eq!(1, sel.get_count("verbose"));
+
+    assert_eq!(
+        "Options:
+  --token <VALUE>  API token [env: MYAPP_TOKEN]
+  -v, --verbose    [env: MYAPP_VERBOSE]",
+        parser.help()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_env_var_name() {
+    assert_eq!("MYAPP_POOL_SIZE", env_var_name("MYAPP_", "pool-size"));
+    assert_eq!("X_A0", env_var_name("X_", "a0"));
+}
+
+#[test]
+fn test_parse_env_prefix() -> Result<(), ArgParserError> {
+    use super::{OptionalArg, Resolver};
+    use ParsedArg::*;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::flag("dry-run"))?
+        .env_prefix("MYAPP_")
+        .add_option(OptionalArg::required_value("token").env("API_TOKEN"))?
+        .add_option(OptionalArg::optional_value("color"))?;
+
+    let env = |var: &str| match var {
+        "MYAPP_DRY_RUN" => Some("true".to_string()),
+        "MYAPP_COLOR" => Some("never".to_string()),
+        "API_TOKEN" => Some("secret".to_string()),
+        _ => None,
+    };
+
+    let args = Resolver::new(&parser).env(env).resolve(&[])?;
+
+    assert_eq!(
+        vec![
+            OptionalValue {
+                name: "color",
+                value: Some("never".to_string())
+            },
+            Flag {
+                name: "dry-run",
+                value: true
+            },
+            RequiredValue {
+                name: "token",
+                value: "secret".to_string()
+            }
+        ],
+        *args
+    );
+    assert_eq!(
+        Some(ValueSource::Env("MYAPP_DRY_RUN".to_string())),
+        args.source("dry-run")
+    );
+    assert_eq!(
+        Some(ValueSource::Env("API_TOKEN".to_string())),
+        args.source("token")
+    );
+
+    Ok(())
+}
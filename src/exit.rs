@@ -0,0 +1,250 @@
+use super::{ArgParser, ArgParserError, InteractionMode, ParsedArgs};
+use std::{env, process, slice};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ErrorCategory {
+    Usage,
+    Validation,
+    Policy,
+    Schema,
+}
+
+// Scripts branch on these, so they are part of a tool's interface and always the same for a kind
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ExitCodes {
+    pub usage: i32,
+    pub validation: i32,
+    pub policy: i32,
+    pub schema: i32,
+}
+
+impl Default for ExitCodes {
+    fn default() -> Self {
+        Self {
+            usage: 2,
+            validation: 3,
+            policy: 4,
+            schema: 70,
+        }
+    }
+}
+
+impl ExitCodes {
+    pub fn code(&self, category: ErrorCategory) -> i32 {
+        match category {
+            ErrorCategory::Usage => self.usage,
+            ErrorCategory::Validation => self.validation,
+            ErrorCategory::Policy => self.policy,
+            ErrorCategory::Schema => self.schema,
+        }
+    }
+}
+
+impl ArgParserError {
+    // Schema errors come from setting up the parser, so they are bugs in the tool rather than in
+    // its command line. Errors such as `DuplicateOption` can come from either and count as usage.
+    pub fn category(&self) -> ErrorCategory {
+        use ArgParserError::*;
+
+        match self {
+            InvalidOption { .. }
+            | InvalidAlias { .. }
+            | DuplicateOption { .. }
+            | DuplicateAlias { .. }
+            | UnknownOption { .. }
+            | AmbiguousOption { .. }
+            | UnknownAlias { .. }
+            | InvalidCluster { .. }
+            | MissingOptionValue { .. }
+            | MissingAliasValue { .. }
+            | MissingAliasEquals { .. }
+            | MissingOptionEquals { .. }
+            | AttachedOptionValue { .. }
+            | AttachedAliasValue { .. }
+            | MissingOptionValues { .. }
+            | MissingValueTerminator { .. }
+            | TooManyOptionValues { .. }
+            | UnexpectedArg { .. }
+            | MissingRequiredOption { .. }
+            | WrongOccurrences { .. }
+            | ConflictingOptions { .. }
+            | GroupConflict { .. }
+            | MissingGroupOption { .. }
+            | NoMatchingSignature { .. }
+            | MissingArgs { .. }
+            | InvalidResponseFile { .. }
+            | InvalidCommandLine { .. }
+            | InvalidUnicode { .. } => ErrorCategory::Usage,
+            InvalidOptionValue { .. }
+            | InvalidAliasValue { .. }
+            | RejectedValue { .. }
+            | RejectedItem { .. }
+            | RejectedPositional { .. }
+            | InvalidEnvValue { .. }
+            | DuplicateKey { .. }
+            | InvalidConfig { .. }
+            | InvalidConfigValue { .. }
+            | UnknownConfigKey { .. } => ErrorCategory::Validation,
+            StaleSchema { .. }
+            | TooManyArgs { .. }
+            | CommandLineTooLong { .. }
+            | ArgumentTooLong { .. }
+            | InputTooLarge { .. }
+            | ResponseFileTooDeep { .. } => ErrorCategory::Policy,
+            InvalidConfigKey { .. }
+            | DuplicateConfigKey { .. }
+            | ReservedOption { .. }
+            | ReservedAlias { .. }
+            | DuplicateGroup { .. }
+            | InvalidRestArg
+            | InvalidOptionalArg
+            | InvalidPrefix { .. }
+            | InvalidOptionSetting { .. }
+            | InvalidPositionalSetting { .. } => ErrorCategory::Schema,
+        }
+    }
+}
+
+impl ArgParser {
+    pub fn exit_codes(&mut self, codes: ExitCodes) -> &mut Self {
+        self.exit_codes = codes;
+        self
+    }
+
+    pub fn exit_code(&self, error: &ArgParserError) -> i32 {
+        self.exit_codes.code(error.category())
+    }
+
+    pub fn parse_args_or_exit(&self) -> ParsedArgs {
+        let args = env::args().skip(1).collect::<Vec<_>>();
+
+        self.parse_or_exit(&args.iter().map(|s| &s[..]).collect::<Vec<_>>())
+    }
+
+    pub fn parse_or_exit(&self, args: &[&str]) -> ParsedArgs {
+        self.parse(args).unwrap_or_else(|error| {
+            eprintln!("{}", self.error_report(args, &error));
+            process::exit(self.exit_code(&error))
+        })
+    }
+
+    // What `parse_or_exit` prints for `error`, the first error parsing `args` gave. A terminal
+    // gets every error of the command line at once, each with the help of the options involved
+    // and any suggestion, while a script gets the first one only, worded the same whatever
+    // options happen to be close to a typo.
+    pub fn error_report(&self, args: &[&str], error: &ArgParserError) -> String {
+        use ArgParserError::*;
+
+        match self.interaction() {
+            InteractionMode::Interactive => {
+                let errors = self.parse_report(args).errors;
+                let errors = if errors.is_empty() {
+                    slice::from_ref(error)
+                } else {
+                    &errors[..]
+                };
+
+                errors
+                    .iter()
+                    .map(|error| self.explain(error, usize::MAX))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            }
+            InteractionMode::Scripted => match error {
+                UnknownOption { name, .. } => self.error_message(&UnknownOption {
+                    name: name.clone(),
+                    suggestion: None,
+                }),
+                UnknownAlias { alias, .. } => self.error_message(&UnknownAlias {
+                    alias: alias.clone(),
+                    suggestion: None,
+                }),
+                error => self.error_message(error),
+            },
+        }
+    }
+}
+
+#[test]
+fn test_exit_code() -> Result<(), ArgParserError> {
+    use super::{OptionalArg, PositionalArg};
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::required_value("port").range(1..=65535))?
+        .add_positional(PositionalArg::named())?
+        .max_args(3);
+
+    let exit_code =
+        |parser: &ArgParser, args: &[&str]| parser.exit_code(&parser.parse(args).unwrap_err());
+
+    assert_eq!(2, exit_code(&parser, &["--bogus", "x"]));
+    assert_eq!(2, exit_code(&parser, &[]));
+    assert_eq!(3, exit_code(&parser, &["--port=0", "x"]));
+    assert_eq!(4, exit_code(&parser, &["a", "b", "c", "d"]));
+    assert_eq!(
+        ErrorCategory::Schema,
+        ArgParser::default()
+            .add_positional(PositionalArg::rest())?
+            .add_positional(PositionalArg::named())
+            .map(|_| ())
+            .unwrap_err()
+            .category()
+    );
+
+    parser.exit_codes(ExitCodes {
+        usage: 64,
+        validation: 65,
+        ..ExitCodes::default()
+    });
+
+    assert_eq!(64, exit_code(&parser, &[]));
+    assert_eq!(65, exit_code(&parser, &["--port=0", "x"]));
+    assert_eq!(4, exit_code(&parser, &["a", "b", "c", "d"]));
+    assert_eq!(70, ExitCodes::default().code(ErrorCategory::Schema));
+
+    // Defined with or without the `config` feature, so matches on them don't depend on it
+    assert_eq!(
+        ErrorCategory::Validation,
+        ArgParserError::UnknownConfigKey {
+            key: "colour".to_string(),
+            path: "app.toml".to_string()
+        }
+        .category()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_error_report() -> Result<(), ArgParserError> {
+    use super::OptionalArg;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::count("verbose"))?
+        .add_option(
+            OptionalArg::required_value("port")
+                .range(1..=65535)
+                .help("Port to listen on"),
+        )?
+        .interaction_mode(InteractionMode::Scripted);
+
+    let args = ["--verbos", "--port=0"];
+    let error = parser.parse(&args).unwrap_err();
+
+    assert_eq!("--verbos is undefined", parser.error_report(&args, &error));
+
+    parser.interaction_mode(InteractionMode::Interactive);
+
+    assert_eq!(
+        "--verbos is undefined, did you mean --verbose?\n\
+         --port cannot accept '0': must be in range 1..=65535\n\
+         \n  --port <VALUE>  Port to listen on",
+        parser.error_report(&args, &error)
+    );
+
+    Ok(())
+}
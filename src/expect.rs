@@ -0,0 +1,135 @@
+// Only exported with the `testing` feature, meant for dev-dependencies, since names as short as
+// `value!` would otherwise clash with the macros of any crate using this one
+#[macro_export]
+macro_rules! parsed_eq {
+    ($result:expr, [$($arg:expr),* $(,)?]) => {
+        assert_eq!(
+            Ok(vec![$($arg),*]),
+            $result.map(::std::vec::Vec::<$crate::ParsedArg>::from)
+        )
+    };
+}
+
+#[macro_export]
+macro_rules! pos {
+    ($value:expr) => {
+        $crate::ParsedArg::Positional {
+            value: $value.to_string(),
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! flag {
+    ($name:expr) => {
+        $crate::flag!($name, true)
+    };
+    ($name:expr, $value:expr) => {
+        $crate::ParsedArg::Flag {
+            name: $name,
+            value: $value,
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! count {
+    ($name:expr, $value:expr) => {
+        $crate::ParsedArg::Count {
+            name: $name,
+            value: $value,
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! value {
+    ($name:expr, $value:expr) => {
+        $crate::ParsedArg::RequiredValue {
+            name: $name,
+            value: $value.to_string(),
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! opt_value {
+    ($name:expr) => {
+        $crate::ParsedArg::OptionalValue {
+            name: $name,
+            value: None,
+        }
+    };
+    ($name:expr, $value:expr) => {
+        $crate::ParsedArg::OptionalValue {
+            name: $name,
+            value: Some($value.to_string()),
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! values {
+    ($name:expr, [$($value:expr),* $(,)?]) => {
+        $crate::ParsedArg::Values {
+            name: $name,
+            values: vec![$($value.to_string()),*],
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! unknown {
+    ($raw:expr) => {
+        $crate::ParsedArg::Unknown {
+            raw: $raw.to_string(),
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! rest {
+    ([$($value:expr),* $(,)?]) => {
+        $crate::ParsedArg::Rest {
+            values: vec![$($value.to_string()),*],
+        }
+    };
+}
+
+#[test]
+fn test_expect() -> Result<(), super::ArgParserError> {
+    use super::{ArgParser, OptionalArg, ParsedArg};
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::flag("force").alias("f"))?
+        .add_option(OptionalArg::count("verbose").alias("v"))?
+        .add_option(OptionalArg::required_value("out"))?
+        .add_option(OptionalArg::optional_value("color"))?
+        .add_option(OptionalArg::values("files", 1..=2))?
+        .capture_rest(true);
+
+    assert_eq!(
+        ParsedArg::Flag {
+            name: "force",
+            value: true
+        },
+        flag!("force")
+    );
+
+    parsed_eq!(
+        parser.parse(&["-f", "-vv", "--out", "a", "--color", "--files", "b", "c", "d", "--", "e"]),
+        [
+            flag!("force"),
+            count!("verbose", 2),
+            value!("out", "a"),
+            opt_value!("color"),
+            values!("files", ["b", "c"]),
+            pos!("d"),
+            rest!(["e"]),
+        ]
+    );
+
+    Ok(())
+}
@@ -0,0 +1,102 @@
+use super::{ArgParser, ArgParserError, ArgSelector, ParsedArg};
+use std::{fmt::Display, str::FromStr};
+
+pub trait FromParsedArgs: Sized {
+    fn from_parsed(sel: &ArgSelector) -> Result<Self, ArgParserError>;
+}
+
+impl ArgParser {
+    pub fn parse_into<T: FromParsedArgs>(&self, args: &[&str]) -> Result<T, ArgParserError> {
+        T::from_parsed(&ArgSelector::new(&self.parse(args)?))
+    }
+}
+
+impl<P> ArgSelector<'_, P> {
+    pub fn get_parsed<T: FromStr>(&self, name: &str) -> Result<Option<T>, ArgParserError>
+    where
+        T::Err: Display,
+    {
+        use ParsedArg::*;
+
+        let value = self.args.iter().find_map(|arg| match *arg {
+            RequiredValue {
+                name: _name,
+                ref value,
+            }
+            | OptionalValue {
+                name: _name,
+                value: Some(ref value),
+            } if name == _name => Some((_name, value)),
+            _ => None,
+        });
+
+        let Some((name, value)) = value else {
+            return Ok(None);
+        };
+
+        value
+            .parse()
+            .map(Some)
+            .map_err(|err: T::Err| ArgParserError::RejectedValue {
+                name,
+                value: value.clone(),
+                reason: err.to_string(),
+            })
+    }
+}
+
+#[test]
+fn test_parse_into() -> Result<(), ArgParserError> {
+    use super::{OptionalArg, PositionalArg};
+
+    #[derive(Debug, PartialEq)]
+    struct Options {
+        port: u16,
+        verbose: bool,
+        files: Vec<String>,
+    }
+
+    impl FromParsedArgs for Options {
+        fn from_parsed(sel: &ArgSelector) -> Result<Self, ArgParserError> {
+            Ok(Self {
+                port: sel.get_parsed("port")?.unwrap_or(8080),
+                verbose: sel.get_flag("verbose", false),
+                files: sel.get_positional().into_iter().cloned().collect(),
+            })
+        }
+    }
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::required_value("port"))?
+        .add_option(OptionalArg::flag("verbose"))?
+        .add_positional(PositionalArg::rest())?;
+
+    assert_eq!(
+        Ok(Options {
+            port: 80,
+            verbose: true,
+            files: vec!["a".to_string()]
+        }),
+        parser.parse_into(&["--port=80", "--verbose", "a"])
+    );
+    assert_eq!(
+        Ok(Options {
+            port: 8080,
+            verbose: false,
+            files: vec![]
+        }),
+        parser.parse_into(&[])
+    );
+    assert_eq!(
+        Err(ArgParserError::RejectedValue {
+            name: "port",
+            value: "http".to_string(),
+            reason: "invalid digit found in string".to_string()
+        }),
+        parser.parse_into::<Options>(&["--port=http"])
+    );
+
+    Ok(())
+}
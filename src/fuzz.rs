@@ -0,0 +1,98 @@
+use super::{ArgParser, Resolver};
+use std::ffi::OsStr;
+
+impl ArgParser {
+    // Meant as the body of a `cargo fuzz` target, where the only invariant is that nothing panics:
+    // NUL bytes separate the arguments, like in `/proc/<pid>/cmdline`
+    pub fn fuzz_one(&self, data: &[u8]) {
+        let mut parser = self.clone();
+
+        // Arbitrary input must not make the parser read files or wait on stdin
+        parser.response_files = false;
+        parser.args_from_stdin = false;
+
+        let lossy = String::from_utf8_lossy(data);
+        let args = lossy.split('\0').collect::<Vec<_>>();
+
+        if let Err(error) = Resolver::new(&parser).without_env().resolve(&args) {
+            let _ = error.to_string();
+            let _ = parser.explain(&error, usize::MAX);
+        }
+
+        let _ = parser.parse_partial(&args);
+
+        for error in parser.parse_lenient(&args).1 {
+            let _ = error.to_string();
+        }
+
+        let _ = parser.parse_str(&lossy.replace('\0', " "));
+
+        let os_args = data
+            .split(|&b| b == 0)
+            .map(os_str_from_bytes)
+            .collect::<Vec<_>>();
+        let _ = parser.parse_os(&os_args.iter().map(|s| &s[..]).collect::<Vec<_>>());
+    }
+}
+
+#[cfg(unix)]
+fn os_str_from_bytes(bytes: &[u8]) -> std::ffi::OsString {
+    use std::os::unix::ffi::OsStrExt;
+
+    OsStr::from_bytes(bytes).to_os_string()
+}
+
+#[cfg(not(unix))]
+fn os_str_from_bytes(bytes: &[u8]) -> std::ffi::OsString {
+    OsStr::new(&*String::from_utf8_lossy(bytes)).to_os_string()
+}
+
+#[test]
+fn test_fuzz_one() -> Result<(), super::ArgParserError> {
+    use super::{OptionalArg, PositionalArg};
+
+    let mut parser = ArgParser::default();
+    parser
+        .add_option(OptionalArg::count("verbose").alias("v"))?
+        .add_option(OptionalArg::required_value("out").alias("o"))?
+        .add_option(
+            OptionalArg::required_value("level")
+                .choices(&["a", "b"])
+                .default_value("a"),
+        )?
+        .add_option(OptionalArg::values("exec", 1..=usize::MAX).value_terminator(";"))?
+        .add_option(OptionalArg::required_value("tag").delimiter(','))?
+        .add_positional(PositionalArg::named().name("src"))?
+        .add_positional(PositionalArg::rest())?
+        .response_files(true)
+        .args_from_stdin(true);
+
+    for data in [
+        &b""[..],
+        b"\0",
+        b"-vvv\0--out\0",
+        b"--out=\0--\0-",
+        b"-o\xff\0\xfe\xfd",
+        b"--exec\0rm\0{}\0--level=c",
+        b"@file\0-\0--tag=a,,b",
+    ] {
+        parser.fuzz_one(data);
+    }
+
+    // A cheap deterministic stand-in for the fuzzer, biased towards bytes that matter to parsing
+    let alphabet = b"-=,;@ \0vo\xffabeltxcsr";
+    let mut state = 0x2545_f491_u32;
+
+    for _ in 0..2000 {
+        let mut data = vec![];
+
+        for _ in 0..state % 24 {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            data.push(alphabet[(state >> 16) as usize % alphabet.len()]);
+        }
+
+        parser.fuzz_one(&data);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,48 @@
+use super::{ArgGroup, ArgGroupKind};
+use std::fmt;
+
+impl ArgGroup {
+    pub fn at_most_one(name: &'static str, options: &[&'static str]) -> Self {
+        Self::new(name, options, ArgGroupKind::AtMostOne)
+    }
+
+    pub fn exactly_one(name: &'static str, options: &[&'static str]) -> Self {
+        Self::new(name, options, ArgGroupKind::ExactlyOne)
+    }
+
+    pub fn at_least_one(name: &'static str, options: &[&'static str]) -> Self {
+        Self::new(name, options, ArgGroupKind::AtLeastOne)
+    }
+
+    fn new(name: &'static str, options: &[&'static str], kind: ArgGroupKind) -> Self {
+        Self {
+            name,
+            options: options.to_vec(),
+            kind,
+        }
+    }
+
+    pub(crate) fn render(&self, long_prefix: &str) -> String {
+        let options = self
+            .options
+            .iter()
+            .map(|name| format!("{}{}", long_prefix, name))
+            .collect::<Vec<_>>();
+
+        format!("({})", options.join(" | "))
+    }
+}
+
+impl fmt::Display for ArgGroup {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.render("--"))
+    }
+}
+
+#[test]
+fn test_display() {
+    assert_eq!(
+        "(--json | --yaml | --text)",
+        ArgGroup::exactly_one("format", &["json", "yaml", "text"]).to_string()
+    );
+}
@@ -0,0 +1,363 @@
+use super::{parser::Message, ArgParser, ArgParserError, OptionalArg, OptionalArgKind};
+
+impl OptionalArg {
+    // Without a parser to go by, the standard prefixes are used
+    pub fn usage(&self) -> String {
+        self.render_usage(true, &self.aliases, "--", "-")
+    }
+
+    fn render_usage(
+        &self,
+        with_name: bool,
+        aliases: &[&'static str],
+        long_prefix: &str,
+        short_prefix: &str,
+    ) -> String {
+        let placeholder = match (self.value_name, self.choices.is_empty()) {
+            (Some(name), _) => name.to_string(),
+            (None, true) => "VALUE".to_string(),
+            (None, false) => self.choices.join("|"),
+        };
+
+        let value = match self.kind {
+            OptionalArgKind::Flag | OptionalArgKind::Count => "".to_string(),
+            OptionalArgKind::RequiredValue => format!(" <{}>", placeholder),
+            OptionalArgKind::OptionalValue => format!("[=<{}>]", placeholder),
+            OptionalArgKind::Values => {
+                let (min, max) = (*self.arity.start(), *self.arity.end());
+                let optional = match max {
+                    usize::MAX => format!(" [<{}>...]", placeholder),
+                    max => format!(" [<{}>]", placeholder).repeat(max.saturating_sub(min)),
+                };
+
+                let terminator = self
+                    .value_terminator
+                    .map_or("".to_string(), |terminator| format!(" {}", terminator));
+
+                format!(" <{}>", placeholder).repeat(min) + &optional + &terminator
+            }
+        };
+
+        let negation = if self.negatable { "[no-]" } else { "" };
+
+        let (short, long): (Vec<&str>, Vec<&str>) =
+            aliases.iter().partition(|alias| alias.chars().count() == 1);
+
+        short
+            .iter()
+            .map(|alias| format!("{}{}", short_prefix, alias))
+            .chain(with_name.then(|| format!("{}{}{}", long_prefix, negation, self.name)))
+            .chain(
+                long.iter()
+                    .map(|alias| format!("{}{}{}", long_prefix, negation, alias)),
+            )
+            .collect::<Vec<_>>()
+            .join(", ")
+            + &value
+    }
+}
+
+#[test]
+fn test_usage() {
+    assert_eq!("--foo", OptionalArg::flag("foo").usage());
+    assert_eq!(
+        "-f, --[no-]foo",
+        OptionalArg::flag("foo").alias("f").negatable().usage()
+    );
+    assert_eq!(
+        "-b, --bar <VALUE>",
+        OptionalArg::required_value("bar").alias("b").usage()
+    );
+    assert_eq!(
+        "-c, --color, --colour <VALUE>",
+        OptionalArg::required_value("color")
+            .alias("colour")
+            .alias("c")
+            .usage()
+    );
+    assert_eq!(
+        "--size <VALUE> <VALUE> [<VALUE>]",
+        OptionalArg::values("size", 2..=3).usage()
+    );
+    assert_eq!(
+        "--files <VALUE> [<VALUE>...]",
+        OptionalArg::values("files", 1..=1).greedy().usage()
+    );
+    assert_eq!(
+        "--exec <VALUE> [<VALUE>...] ;",
+        OptionalArg::values("exec", 1..=usize::MAX)
+            .value_terminator(";")
+            .usage()
+    );
+    assert_eq!(
+        "-p, --port <PORT>",
+        OptionalArg::required_value("port")
+            .alias("p")
+            .value_name("PORT")
+            .usage()
+    );
+    assert_eq!(
+        "--level[=<N>]",
+        OptionalArg::optional_value("level")
+            .choices(&["1", "2"])
+            .value_name("N")
+            .usage()
+    );
+    assert_eq!(
+        "--baz[=<VALUE>]",
+        OptionalArg::optional_value("baz").usage()
+    );
+    assert_eq!(
+        "--color[=<auto|never>]",
+        OptionalArg::optional_value("color")
+            .choices(&["auto", "never"])
+            .usage()
+    );
+}
+
+impl ArgParser {
+    pub fn help(&self) -> String {
+        let mut names = self
+            .options
+            .values()
+            .filter(|option| !option.hidden && !option.env_only)
+            .map(|option| option.name)
+            .collect::<Vec<_>>();
+
+        names.sort_unstable();
+
+        format!("Options:\n{}", self.render_help_entries(&names))
+    }
+
+    pub fn explain(&self, error: &ArgParserError, limit: usize) -> String {
+        let mut names = vec![];
+
+        for name in self.error_options(error) {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+
+            for group in self
+                .groups
+                .iter()
+                .filter(|group| group.options.contains(&name))
+            {
+                for &other in &group.options {
+                    if !names.contains(&other) {
+                        names.push(other);
+                    }
+                }
+            }
+        }
+
+        names.retain(|name| !self.options[name].hidden && !self.options[name].env_only);
+        names.truncate(limit);
+
+        if names.is_empty() {
+            return self.error_message(error);
+        }
+
+        format!(
+            "{}\n\n{}",
+            self.error_message(error),
+            self.render_help_entries(&names)
+        )
+    }
+
+    // `Display` of an error always uses `--` and `-`, this uses the prefixes set on the parser, like
+    // the help does
+    pub fn error_message(&self, error: &ArgParserError) -> String {
+        Message {
+            error,
+            long: self.long_prefix,
+            short: self.short_prefix,
+        }
+        .to_string()
+    }
+
+    fn render_help_entries(&self, names: &[&'static str]) -> String {
+        let entries = names
+            .iter()
+            .flat_map(|&name| {
+                let option = &self.options[name];
+
+                let help = match (option.help, self.env_var(option)) {
+                    (Some(help), Some(var)) => format!("{} [env: {}]", help, var),
+                    (None, Some(var)) => format!("[env: {}]", var),
+                    (help, None) => help.unwrap_or("").to_string(),
+                };
+
+                // Aliases with a help of their own get separate entries, all others share one
+                let aliases = option
+                    .aliases
+                    .iter()
+                    .copied()
+                    .filter(|alias| option.alias_help.iter().all(|(other, _)| other != alias))
+                    .collect::<Vec<_>>();
+
+                let usage = |with_name, aliases: &[&'static str]| {
+                    option.render_usage(with_name, aliases, self.long_prefix, self.short_prefix)
+                };
+
+                [(usage(true, &aliases), help)].into_iter().chain(
+                    option
+                        .alias_help
+                        .iter()
+                        .map(move |&(alias, help)| (usage(false, &[alias]), help.to_string())),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let width = entries
+            .iter()
+            .map(|(usage, _)| usage.len())
+            .max()
+            .unwrap_or(0);
+
+        entries
+            .iter()
+            .map(|(usage, help)| {
+                format!("  {:width$}  {}", usage, help, width = width)
+                    .trim_end()
+                    .to_string()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub(crate) fn error_options(&self, error: &ArgParserError) -> Vec<&'static str> {
+        use ArgParserError::*;
+
+        match *error {
+            InvalidOptionValue { name, .. }
+            | MissingOptionValue { name }
+            | MissingValueTerminator { name, .. }
+            | MissingOptionEquals { name, .. }
+            | AttachedOptionValue { name, .. }
+            | RejectedValue { name, .. }
+            | RejectedItem { name, .. }
+            | WrongOccurrences { name, .. } => vec![name],
+            DuplicateOption { name } | ReservedOption { name } | DuplicateKey { name, .. } => {
+                vec![name]
+            }
+            InvalidAliasValue { alias, .. }
+            | MissingAliasValue { alias }
+            | MissingAliasEquals { alias, .. }
+            | AttachedAliasValue { alias, .. }
+            | DuplicateAlias { alias } => self.aliases.get(alias).copied().into_iter().collect(),
+            MissingRequiredOption { name, required } => vec![name, required],
+            ConflictingOptions { first, second } => vec![first, second],
+            GroupConflict { ref group, .. } | MissingGroupOption { ref group } => {
+                group.options.clone()
+            }
+            AmbiguousOption { ref candidates, .. } => candidates
+                .iter()
+                .map(|&spelling| self.aliases.get(spelling).copied().unwrap_or(spelling))
+                .collect(),
+            _ => vec![],
+        }
+        .into_iter()
+        .filter(|name| self.options.contains_key(name))
+        .collect()
+    }
+}
+
+#[test]
+fn test_help() -> Result<(), ArgParserError> {
+    use super::ArgGroup;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::flag("json").help("Print JSON"))?
+        .add_option(OptionalArg::flag("yaml").help("Print YAML"))?
+        .add_option(
+            OptionalArg::required_value("user")
+                .alias("u")
+                .help("Run as this user"),
+        )?
+        .add_option(OptionalArg::flag("verbose"))?
+        .add_option(OptionalArg::required_value("debug-dump").hidden())?
+        .add_group(ArgGroup::at_most_one("format", &["json", "yaml"]))?;
+
+    assert_eq!(
+        "Options:
+  --json              Print JSON
+  -u, --user <VALUE>  Run as this user
+  --verbose
+  --yaml              Print YAML",
+        parser.help()
+    );
+
+    let mut legacy = parser.clone();
+
+    legacy.add_option(
+        OptionalArg::flag("quiet")
+            .alias("q")
+            .alias("silent")
+            .alias_help("Deprecated, same as --quiet")
+            .help("Print nothing"),
+    )?;
+
+    assert_eq!(
+        "Options:
+  --json              Print JSON
+  -q, --quiet         Print nothing
+  --silent            Deprecated, same as --quiet
+  -u, --user <VALUE>  Run as this user
+  --verbose
+  --yaml              Print YAML",
+        legacy.help()
+    );
+    assert_eq!("-q, --quiet, --silent", legacy.options["quiet"].usage());
+    assert_eq!(
+        "invalid alias_help on --quiet: no alias() precedes it",
+        ArgParser::default()
+            .add_option(OptionalArg::flag("quiet").alias_help("Print nothing"))
+            .map(|_| ())
+            .unwrap_err()
+            .to_string()
+    );
+
+    let error = parser.parse(&["-u"]).unwrap_err();
+
+    assert_eq!(
+        "-u is missing a value
+
+  -u, --user <VALUE>  Run as this user",
+        parser.explain(&error, 3)
+    );
+
+    let error = parser.parse(&["--yaml", "--json"]).unwrap_err();
+
+    assert_eq!(
+        "--json cannot be used with --yaml, only one of (--json | --yaml) is allowed
+
+  --json  Print JSON",
+        parser.explain(&error, 1)
+    );
+
+    let error = parser.parse(&["--foo"]).unwrap_err();
+
+    assert_eq!("--foo is undefined", parser.explain(&error, 3));
+
+    let error = parser.parse(&["--debug-dump"]).unwrap_err();
+
+    assert_eq!("--debug-dump is missing a value", parser.explain(&error, 3));
+
+    parser.option_prefixes("++", "+")?;
+
+    let error = parser.parse(&["++yaml", "++json"]).unwrap_err();
+
+    assert_eq!(
+        "++json cannot be used with ++yaml, only one of (++json | ++yaml) is allowed
+
+  ++json  Print JSON",
+        parser.explain(&error, 1)
+    );
+    assert!(parser
+        .help()
+        .contains("  +u, ++user <VALUE>  Run as this user"));
+
+    Ok(())
+}
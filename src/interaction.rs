@@ -0,0 +1,48 @@
+use super::ArgParser;
+use std::io::{self, IsTerminal};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InteractionMode {
+    Interactive,
+    Scripted,
+}
+
+impl InteractionMode {
+    pub fn detect() -> Self {
+        if io::stdin().is_terminal() && io::stdout().is_terminal() {
+            Self::Interactive
+        } else {
+            Self::Scripted
+        }
+    }
+
+    pub fn is_interactive(self) -> bool {
+        self == Self::Interactive
+    }
+}
+
+impl ArgParser {
+    pub fn interaction_mode(&mut self, mode: InteractionMode) -> &mut Self {
+        self.interaction = Some(mode);
+        self
+    }
+
+    pub fn interaction(&self) -> InteractionMode {
+        self.interaction.unwrap_or_else(InteractionMode::detect)
+    }
+}
+
+#[test]
+fn test_interaction_mode() {
+    let mut parser = ArgParser::default();
+
+    assert_eq!(InteractionMode::detect(), parser.interaction());
+
+    parser.interaction_mode(InteractionMode::Interactive);
+
+    assert!(parser.interaction().is_interactive());
+
+    parser.interaction_mode(InteractionMode::Scripted);
+
+    assert!(!parser.interaction().is_interactive());
+}
@@ -0,0 +1,28 @@
+use std::{collections::BTreeSet, sync::Mutex};
+
+// Names made up at runtime, e.g. mounted options, have to be `&'static str` like every other name
+// since parse results outlive their parser. Each distinct one is stored once for the whole
+// process, so mounting again, cloning a parser or a failed call doesn't take up more memory.
+static NAMES: Mutex<BTreeSet<&'static str>> = Mutex::new(BTreeSet::new());
+
+pub(crate) fn intern(name: &str) -> &'static str {
+    let mut names = NAMES.lock().unwrap_or_else(|error| error.into_inner());
+
+    if let Some(&interned) = names.get(name) {
+        return interned;
+    }
+
+    let interned = Box::leak(name.to_string().into_boxed_str());
+
+    names.insert(interned);
+    interned
+}
+
+#[test]
+fn test_intern() {
+    let name = intern(&format!("{}-{}", "docker", "rm"));
+
+    assert_eq!("docker-rm", name);
+    assert!(std::ptr::eq(name, intern("docker-rm")));
+    assert!(!std::ptr::eq(name, intern("docker-run")));
+}
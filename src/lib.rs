@@ -1,33 +1,186 @@
-pub use parser::{ArgParser, ArgParserError, ArgParserMode, ParsedArg};
+pub use compat::{compare_schemas, CompatReport, SchemaChange};
+pub use exit::{ErrorCategory, ExitCodes};
+pub use extract::FromParsedArgs;
+pub use interaction::InteractionMode;
+pub use mapper::{ErrorContext, MappedParser};
+pub use parsed::{Choice, ParseMeta, ParsedArgs, Value, ValueSource};
+pub use parser::{ArgParser, ArgParserError, ArgParserMode, ParseState, ParsedArg};
+pub use prefs::ExecutionPrefs;
+pub use redactor::Redactor;
+pub use report::ParseReport;
+pub use resolver::Resolver;
 pub use selector::ArgSelector;
+pub use source::{
+    CachedEnv, EnvSource, FileSource, FileSystem, MemoryEnv, MemoryFiles, ProcessEnv,
+};
+pub use split::SplitStyle;
+pub use stats::ParserStats;
+pub use validator::Validator;
+pub use visitor::ArgVisitor;
 
+use std::ops::RangeInclusive;
+
+mod arena;
+mod budget;
+mod clap;
+mod cmdline;
+mod compat;
+#[cfg(feature = "config")]
+mod config;
+mod encoding;
+mod env;
+mod exit;
+#[cfg(any(test, feature = "testing"))]
+mod expect;
+mod extract;
+mod fuzz;
+mod group;
+mod help;
+mod interaction;
+mod intern;
+mod map;
+mod mapper;
+mod mount;
 mod option;
+mod os;
+mod parsed;
 mod parser;
 mod positional;
+mod prefs;
+mod redactor;
+mod report;
+mod resolver;
+mod response;
+mod schema;
 mod selector;
+mod signature;
+mod source;
+mod split;
+mod stats;
+mod suggest;
+mod validator;
+mod visitor;
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum OptionalArgKind {
     Flag,
     RequiredValue,
     OptionalValue,
+    Count,
+    Values,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct OptionalArg {
     pub name: &'static str,
-    pub alias: Option<&'static str>,
+    pub aliases: Vec<&'static str>,
     pub kind: OptionalArgKind,
     pub multiple: bool,
+    pub validator: Option<Validator>,
+    pub range: Option<RangeInclusive<i64>>,
+    pub redactor: Option<Redactor>,
+    pub default_missing: Option<&'static str>,
+    pub conditional_defaults: Vec<(&'static str, Option<&'static str>, &'static str)>,
+    pub requires: Vec<&'static str>,
+    pub conflicts: Vec<&'static str>,
+    pub allow_hyphen_values: bool,
+    pub help: Option<&'static str>,
+    pub alias_help: Vec<(&'static str, &'static str)>,
+    pub negatable: bool,
+    pub plus_negatable: bool,
+    pub hidden: bool,
+    pub arity: RangeInclusive<usize>,
+    pub env: Option<&'static str>,
+    pub env_only: bool,
+    pub config_key: Option<&'static str>,
+    pub choices: Vec<&'static str>,
+    pub or_other: bool,
+    pub default: Option<&'static str>,
+    pub encoding: Option<Encoding>,
+    pub key_values: Option<DuplicateKeys>,
+    pub hint: Option<ValueHint>,
+    pub value_style: Option<ValueStyle>,
+    pub duplicates: DuplicateOptions,
+    pub occurrences: RangeInclusive<usize>,
+    pub value_name: Option<&'static str>,
+    pub delimiter: Option<char>,
+    pub value_terminator: Option<&'static str>,
+    pub eager: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DuplicateOptions {
+    Error,
+    FirstWins,
+    LastWins,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ValueStyle {
+    Both,
+    EqualsOnly,
+    SpaceOnly,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DuplicateKeys {
+    Error,
+    FirstWins,
+    LastWins,
+    Collect,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Encoding {
+    Base64,
+    Hex,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ArgGroupKind {
+    AtMostOne,
+    ExactlyOne,
+    AtLeastOne,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ArgGroup {
+    pub name: &'static str,
+    pub options: Vec<&'static str>,
+    pub kind: ArgGroupKind,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ValueHint {
+    File,
+    Dir,
+    Glob(&'static str),
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum RestPolicy {
+    #[default]
+    Lazy,
+    Greedy,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum PositionalArgKind {
     Named,
+    Optional,
     Rest,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct PositionalArg {
     pub kind: PositionalArgKind,
+    pub validator: Option<Validator>,
+    pub name: Option<&'static str>,
+    pub hint: Option<ValueHint>,
+    pub default: Option<&'static str>,
+    pub rest_policy: RestPolicy,
+    pub range: Option<RangeInclusive<i64>>,
+    pub choices: Vec<&'static str>,
+    pub must_exist: bool,
+    pub min: usize,
 }
@@ -0,0 +1,135 @@
+use super::{ArgParser, ArgParserError, ArgSelector, DuplicateKeys, ParsedArg};
+use std::collections::{BTreeMap, HashMap};
+
+impl ArgParser {
+    pub(crate) fn apply_key_policies(
+        &self,
+        args: &mut Vec<ParsedArg>,
+    ) -> Result<(), ArgParserError> {
+        let mut seen = HashMap::new();
+        let mut dropped = vec![];
+
+        for (idx, arg) in args.iter().enumerate() {
+            let ParsedArg::RequiredValue { name, value } = arg else {
+                continue;
+            };
+
+            let Some(policy) = self.options.get(name).and_then(|option| option.key_values) else {
+                continue;
+            };
+
+            let (key, _) = value.split_once('=').unwrap_or((value, ""));
+
+            let Some(&first) = seen.get(&(*name, key)) else {
+                seen.insert((*name, key), idx);
+                continue;
+            };
+
+            match policy {
+                DuplicateKeys::Error => {
+                    return Err(ArgParserError::DuplicateKey {
+                        name,
+                        key: key.to_string(),
+                    })
+                }
+                DuplicateKeys::FirstWins => dropped.push(idx),
+                DuplicateKeys::LastWins => {
+                    dropped.push(first);
+                    seen.insert((*name, key), idx);
+                }
+                DuplicateKeys::Collect => {}
+            }
+        }
+
+        let mut idx = 0;
+
+        args.retain(|_| {
+            idx += 1;
+            !dropped.contains(&(idx - 1))
+        });
+
+        Ok(())
+    }
+}
+
+impl<'a, P> ArgSelector<'a, P> {
+    pub fn get_map(&self, name: &str) -> BTreeMap<&'a str, Vec<&'a str>> {
+        let mut map = BTreeMap::<_, Vec<_>>::new();
+
+        for arg in self.args {
+            if let ParsedArg::RequiredValue { name: _name, value } = arg {
+                if let Some((key, value)) = value.split_once('=').filter(|_| name == *_name) {
+                    map.entry(key).or_default().push(value);
+                }
+            }
+        }
+
+        map
+    }
+}
+
+#[test]
+fn test_key_values() -> Result<(), ArgParserError> {
+    use super::OptionalArg;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::required_value("label").key_value(DuplicateKeys::Error))?
+        .add_option(OptionalArg::required_value("env").key_value(DuplicateKeys::FirstWins))?
+        .add_option(OptionalArg::required_value("set").key_value(DuplicateKeys::LastWins))?
+        .add_option(OptionalArg::required_value("tag").key_value(DuplicateKeys::Collect))?;
+
+    let args = parser.parse(&[
+        "--label=a=1",
+        "--label=b=",
+        "--env=A=1",
+        "--env=A=2",
+        "--set=x=1",
+        "--set=y=2",
+        "--set=x=3",
+        "--tag=k=1",
+        "--tag=k=2",
+    ])?;
+    let sel = ArgSelector::from(&args);
+
+    assert_eq!(
+        BTreeMap::from([("a", vec!["1"]), ("b", vec![""])]),
+        sel.get_map("label")
+    );
+    assert_eq!(BTreeMap::from([("A", vec!["1"])]), sel.get_map("env"));
+    assert_eq!(
+        BTreeMap::from([("x", vec!["3"]), ("y", vec!["2"])]),
+        sel.get_map("set")
+    );
+    assert_eq!(BTreeMap::from([("k", vec!["1", "2"])]), sel.get_map("tag"));
+    assert!(sel.get_map("other").is_empty());
+    assert_eq!(7, args.len());
+
+    assert_eq!(
+        Err(ArgParserError::DuplicateKey {
+            name: "label",
+            key: "a".to_string()
+        }),
+        parser.parse(&["--label=a=1", "--label", "a=2"]).map(|_| ())
+    );
+    assert_eq!(
+        Err(ArgParserError::RejectedItem {
+            name: "label",
+            index: 1,
+            value: "a".to_string(),
+            reason: "expected KEY=VALUE".to_string()
+        }),
+        parser.parse(&["--label=a"]).map(|_| ())
+    );
+    assert_eq!(
+        "invalid key_value on --define: only required_value() options take KEY=VALUE",
+        parser
+            .add_option(OptionalArg::values("define", 1..=2).key_value(DuplicateKeys::Error))
+            .map(|_| ())
+            .unwrap_err()
+            .to_string()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,157 @@
+use super::{ArgParser, ArgParserError, ParsedArgs, Resolver};
+use std::env;
+
+type MapperFn<'a, E> = dyn Fn(ArgParserError, ErrorContext) -> E + 'a;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ErrorContext<'a> {
+    pub parser: &'a ArgParser,
+    pub args: Vec<String>,
+    pub options: Vec<&'static str>,
+}
+
+pub struct MappedParser<'a, E> {
+    parser: &'a ArgParser,
+    mapper: Box<MapperFn<'a, E>>,
+}
+
+impl ArgParser {
+    pub fn error_mapper<'a, E>(
+        &'a self,
+        mapper: impl Fn(ArgParserError, ErrorContext) -> E + 'a,
+    ) -> MappedParser<'a, E> {
+        MappedParser {
+            parser: self,
+            mapper: Box::new(mapper),
+        }
+    }
+}
+
+impl<E> MappedParser<'_, E> {
+    pub fn parse_args(&self) -> Result<ParsedArgs, E> {
+        let args = env::args().skip(1).collect::<Vec<_>>();
+        let str_args = args.iter().map(|s| &s[..]).collect::<Vec<_>>();
+
+        self.parse(&str_args)
+    }
+
+    pub fn parse(&self, args: &[&str]) -> Result<ParsedArgs, E> {
+        self.resolve(&Resolver::new(self.parser), args)
+    }
+
+    pub fn resolve(&self, resolver: &Resolver, args: &[&str]) -> Result<ParsedArgs, E> {
+        resolver
+            .resolve(args)
+            .map_err(|error| self.map(error, args))
+    }
+
+    // The args are redacted like the error itself
+    pub fn map(&self, error: ArgParserError, args: &[&str]) -> E {
+        let context = ErrorContext {
+            parser: self.parser,
+            args: self.parser.redact_args(args),
+            options: self.parser.error_options(&error),
+        };
+
+        (self.mapper)(error, context)
+    }
+}
+
+#[test]
+fn test_error_mapper() -> Result<(), ArgParserError> {
+    use super::OptionalArg;
+
+    #[derive(Debug, PartialEq)]
+    enum AppError {
+        Usage {
+            message: String,
+            options: Vec<&'static str>,
+            args: usize,
+        },
+    }
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::required_value("out"))?
+        .add_option(OptionalArg::flag("verbose"))?;
+
+    let mapped = parser.error_mapper(|error, context| AppError::Usage {
+        message: error.to_string(),
+        options: context.options,
+        args: context.args.len(),
+    });
+
+    assert_eq!(
+        Err(AppError::Usage {
+            message: "--out is missing a value".to_string(),
+            options: vec!["out"],
+            args: 2
+        }),
+        mapped.parse(&["--verbose", "--out"])
+    );
+    assert!(mapped.parse(&["--out=a"]).is_ok());
+    assert_eq!(
+        AppError::Usage {
+            message: "--verbose cannot be used with --out".to_string(),
+            options: vec!["out", "verbose"],
+            args: 0
+        },
+        mapped.map(
+            ArgParserError::ConflictingOptions {
+                first: "out",
+                second: "verbose"
+            },
+            &[]
+        )
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_error_mapper_redacted() -> Result<(), ArgParserError> {
+    use super::OptionalArg;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(
+            OptionalArg::required_value("token")
+                .alias("t")
+                .redact(|_| "***".to_string()),
+        )?
+        .add_option(OptionalArg::flag("verbose").alias("v"))?
+        .add_option(OptionalArg::required_value("user"))?;
+
+    let mapped = parser.error_mapper(|_, context| context.args);
+
+    assert_eq!(
+        vec![
+            "--token=***",
+            "-t",
+            "***",
+            "-vt***",
+            "-vt",
+            "***",
+            "--user",
+            "root",
+            "--x"
+        ],
+        mapped
+            .parse(&[
+                "--token=hunter2",
+                "-t",
+                "hunter2",
+                "-vthunter2",
+                "-vt",
+                "hunter2",
+                "--user",
+                "root",
+                "--x"
+            ])
+            .unwrap_err()
+    );
+
+    Ok(())
+}
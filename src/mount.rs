@@ -0,0 +1,353 @@
+use super::{
+    intern::intern, ArgGroup, ArgParser, ArgParserError, OptionalArg, ParsedArg, ParsedArgs,
+    ValueSource, ValueStyle,
+};
+
+// What it takes to hand a mounted option back to the parser it came from
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct MountedOption {
+    pub(crate) prefix: &'static str,
+    pub(crate) name: &'static str,
+    pub(crate) long_prefix: &'static str,
+    pub(crate) style: ValueStyle,
+}
+
+impl ArgParser {
+    // Either every option is mounted or, on the first error, none of them
+    pub fn mount(&mut self, prefix: &str, parser: &ArgParser) -> Result<&mut Self, ArgParserError> {
+        let mut names = parser.options.keys().copied().collect::<Vec<_>>();
+
+        // Keep errors deterministic regardless of the map's iteration order
+        names.sort_unstable();
+
+        let mounted_names = names
+            .iter()
+            .map(|name| format!("{}{}", prefix, name))
+            .collect::<Vec<_>>();
+
+        // Names that can't be mounted don't get interned
+        for mounted_name in &mounted_names {
+            if !OptionalArg::is_valid(mounted_name) {
+                return Err(ArgParserError::InvalidOption {
+                    name: mounted_name.clone(),
+                });
+            }
+
+            if self.options.contains_key(&mounted_name[..])
+                || self.aliases.contains_key(&mounted_name[..])
+            {
+                return Err(ArgParserError::DuplicateOption {
+                    name: intern(mounted_name),
+                });
+            }
+        }
+
+        let mut mounted = self.clone();
+        // Options only ever refer to the ones next to them, so references get the prefix too
+        let prefixed = |name: &str| intern(&format!("{}{}", prefix, name));
+        let prefix_all = |names: &[&str]| names.iter().map(|name| prefixed(name)).collect();
+
+        for (name, mounted_name) in names.into_iter().zip(mounted_names) {
+            let mounted_name = intern(&mounted_name);
+            let option = &parser.options[name];
+
+            mounted.add_option(OptionalArg {
+                name: mounted_name,
+                aliases: vec![],
+                config_key: None,
+                requires: prefix_all(&option.requires),
+                conflicts: prefix_all(&option.conflicts),
+                conditional_defaults: option
+                    .conditional_defaults
+                    .iter()
+                    .map(|&(other, value, default)| (prefixed(other), value, default))
+                    .collect(),
+                ..option.clone()
+            })?;
+
+            mounted.mounted.insert(
+                mounted_name,
+                MountedOption {
+                    prefix: &mounted_name[..prefix.len()],
+                    name,
+                    long_prefix: parser.long_prefix,
+                    style: parser.value_style_of(option),
+                },
+            );
+        }
+
+        for group in &parser.groups {
+            mounted.add_group(ArgGroup {
+                name: prefixed(group.name),
+                options: prefix_all(&group.options),
+                kind: group.kind.clone(),
+            })?;
+        }
+
+        *self = mounted;
+
+        Ok(self)
+    }
+
+    // Only what was given on the command line is forwarded: the mounted parser resolves its own
+    // environment and defaults
+    pub fn split_mounted(&self, prefix: &str, args: &ParsedArgs) -> (Vec<ParsedArg>, Vec<String>) {
+        use ParsedArg::*;
+
+        let mut own_args = vec![];
+        let mut mounted_args = vec![];
+
+        for arg in args.iter() {
+            let Some((name, mounted)) = arg
+                .name()
+                .and_then(|name| Some((name, self.mounted.get(name)?)))
+                .filter(|(_, mounted)| mounted.prefix == prefix)
+            else {
+                own_args.push(arg.clone());
+                continue;
+            };
+
+            if args.source(name) != Some(ValueSource::CommandLine) {
+                continue;
+            }
+
+            let option = format!("{}{}", mounted.long_prefix, mounted.name);
+            let attached = |value: &str| format!("{}={}", option, value);
+
+            match arg {
+                Flag { value: true, .. } | OptionalValue { value: None, .. } => {
+                    mounted_args.push(option);
+                }
+                Flag { value: false, .. } => mounted_args.push(attached("false")),
+                Count { value, .. } => mounted_args.extend(vec![option; *value]),
+                // Optional values can only ever be attached
+                OptionalValue {
+                    value: Some(value), ..
+                } => mounted_args.push(attached(value)),
+                RequiredValue { value, .. } if mounted.style == ValueStyle::SpaceOnly => {
+                    mounted_args.extend([option, value.clone()]);
+                }
+                RequiredValue { value, .. } => mounted_args.push(attached(value)),
+                ValueOs { value, .. } if mounted.style == ValueStyle::SpaceOnly => {
+                    mounted_args.extend([option, value.to_string_lossy().into_owned()]);
+                }
+                ValueOs { value, .. } => mounted_args.push(attached(&value.to_string_lossy())),
+                Values { values, .. } => {
+                    match values.split_first() {
+                        Some((first, rest)) if mounted.style == ValueStyle::EqualsOnly => {
+                            mounted_args.push(attached(first));
+                            mounted_args.extend(rest.iter().cloned());
+                        }
+                        _ => {
+                            mounted_args.push(option);
+                            mounted_args.extend(values.iter().cloned());
+                        }
+                    }
+
+                    if let Some(terminator) = self.options[name].value_terminator {
+                        mounted_args.push(terminator.to_string());
+                    }
+                }
+                // Never named, so never mounted
+                Positional { .. } | PositionalOs { .. } | Unknown { .. } | Rest { .. } => {
+                    own_args.push(arg.clone());
+                }
+            }
+        }
+
+        (own_args, mounted_args)
+    }
+}
+
+#[test]
+fn test_mount() -> Result<(), ArgParserError> {
+    use ParsedArg::*;
+
+    let mut docker_parser = ArgParser::default();
+
+    docker_parser
+        .add_option(OptionalArg::flag("rm"))?
+        .add_option(OptionalArg::required_value("name").alias("n"))?
+        .add_option(OptionalArg::optional_value("color"))?
+        .add_option(OptionalArg::count("verbose").alias("v"))?;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::flag("rm"))?
+        .mount("docker-", &docker_parser)?;
+
+    assert_eq!(
+        Err(ArgParserError::DuplicateOption {
+            name: "docker-color"
+        }),
+        parser.mount("docker-", &docker_parser).map(|_| ())
+    );
+
+    let mut clashing = ArgParser::default();
+
+    clashing.add_option(OptionalArg::flag("docker-verbose"))?;
+
+    assert_eq!(
+        Err(ArgParserError::DuplicateOption {
+            name: "docker-verbose"
+        }),
+        clashing.mount("docker-", &docker_parser).map(|_| ())
+    );
+    assert!(matches!(
+        clashing.parse(&["--docker-rm"]),
+        Err(ArgParserError::UnknownOption { .. })
+    ));
+
+    let args = parser.parse(&[
+        "--rm",
+        "--docker-rm",
+        "--docker-name",
+        "foo",
+        "--docker-color",
+        "bar",
+        "--docker-verbose",
+        "--docker-verbose",
+    ])?;
+
+    assert_eq!(
+        (
+            vec![
+                Flag {
+                    name: "rm",
+                    value: true
+                },
+                Positional {
+                    value: "bar".to_string()
+                }
+            ],
+            vec![
+                "--rm".to_string(),
+                "--name=foo".to_string(),
+                "--color".to_string(),
+                "--verbose".to_string(),
+                "--verbose".to_string()
+            ]
+        ),
+        parser.split_mounted("docker-", &args)
+    );
+    assert_eq!(
+        (args.to_vec(), vec![]),
+        parser.split_mounted("podman-", &args)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_split_mounted_reparse() -> Result<(), ArgParserError> {
+    use ParsedArg::*;
+
+    let mut find_parser = ArgParser::default();
+
+    find_parser
+        .option_prefixes("-", "+")?
+        .add_option(OptionalArg::values("exec", 1..=usize::MAX).value_terminator(";"))?
+        .add_option(OptionalArg::required_value("name").value_style(ValueStyle::SpaceOnly))?
+        .add_option(OptionalArg::required_value("type"))?
+        .add_option(OptionalArg::required_value("format").default_value("text"))?
+        .add_option(OptionalArg::flag("print"))?;
+
+    let mut parser = ArgParser::default();
+
+    parser.mount("find-", &find_parser)?;
+
+    let args = parser.parse(&[
+        "--find-exec",
+        "rm",
+        "{}",
+        ";",
+        "--find-name",
+        "*.o",
+        "--find-type=f",
+        "--find-print",
+    ])?;
+    let (own_args, mounted_args) = parser.split_mounted("find-", &args);
+
+    assert_eq!(Vec::<ParsedArg>::new(), own_args);
+    assert_eq!(
+        vec!["-exec", "rm", "{}", ";", "-name", "*.o", "-type=f", "-print"],
+        mounted_args
+    );
+
+    let mounted_args = mounted_args.iter().map(String::as_str).collect::<Vec<_>>();
+
+    assert_eq!(
+        vec![
+            Values {
+                name: "exec",
+                values: vec!["rm".to_string(), "{}".to_string()]
+            },
+            RequiredValue {
+                name: "name",
+                value: "*.o".to_string()
+            },
+            RequiredValue {
+                name: "type",
+                value: "f".to_string()
+            },
+            Flag {
+                name: "print",
+                value: true
+            },
+            RequiredValue {
+                name: "format",
+                value: "text".to_string()
+            }
+        ],
+        find_parser.parse(&mounted_args)?.to_vec()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_mount_relations() -> Result<(), ArgParserError> {
+    use super::ArgSelector;
+    use ArgParserError::*;
+
+    let mut docker_parser = ArgParser::default();
+
+    docker_parser
+        .add_option(OptionalArg::required_value("tls-cert").requires("tls-key"))?
+        .add_option(OptionalArg::required_value("tls-key"))?
+        .add_option(OptionalArg::flag("json").conflicts_with("quiet"))?
+        .add_option(OptionalArg::flag("quiet"))?
+        .add_option(OptionalArg::required_value("format").default_value_if("json", None, "json"))?
+        .add_group(ArgGroup::at_most_one("output", &["json", "quiet"]))?;
+
+    let mut parser = ArgParser::default();
+
+    parser.mount("docker-", &docker_parser)?.build()?;
+
+    assert!(parser
+        .parse(&["--docker-tls-cert=a", "--docker-tls-key=b"])
+        .is_ok());
+    assert_eq!(
+        "--docker-tls-cert requires --docker-tls-key",
+        parser
+            .parse(&["--docker-tls-cert=a"])
+            .unwrap_err()
+            .to_string()
+    );
+    assert_eq!(
+        Err(ConflictingOptions {
+            first: "docker-json",
+            second: "docker-quiet"
+        }),
+        parser.parse(&["--docker-json", "--docker-quiet"])
+    );
+
+    let args = parser.parse(&["--docker-json"])?;
+    let sel = ArgSelector::from(&args);
+
+    assert_eq!(Some(&"json".to_string()), sel.get_value("docker-format"));
+    assert_eq!(Some("docker-json"), sel.get_choice("docker-output"));
+
+    Ok(())
+}
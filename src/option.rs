@@ -1,4 +1,8 @@
-use super::{OptionalArg, OptionalArgKind};
+use super::{
+    validator, DuplicateKeys, DuplicateOptions, Encoding, OptionalArg, OptionalArgKind, ParsedArg,
+    Redactor, Validator, ValueHint, ValueStyle,
+};
+use std::ops::{RangeBounds, RangeInclusive};
 
 impl OptionalArg {
     pub fn flag(name: &'static str) -> Self {
@@ -13,22 +17,242 @@ impl OptionalArg {
         Self::new(name, OptionalArgKind::OptionalValue)
     }
 
+    pub fn count(name: &'static str) -> Self {
+        Self::new(name, OptionalArgKind::Count)
+    }
+
+    pub fn values(name: &'static str, arity: RangeInclusive<usize>) -> Self {
+        Self {
+            arity,
+            ..Self::new(name, OptionalArgKind::Values)
+        }
+    }
+
     pub fn multiple(mut self) -> Self {
         self.multiple = true;
         self
     }
 
+    pub fn help(mut self, help: &'static str) -> Self {
+        self.help = Some(help);
+        self
+    }
+
+    // Shorthand for `ValueStyle::EqualsOnly`
+    pub fn require_equals(self) -> Self {
+        self.value_style(ValueStyle::EqualsOnly)
+    }
+
+    // Values are then taken up to the next option or terminator, however many there are
+    pub fn greedy(mut self) -> Self {
+        self.arity = *self.arity.start()..=usize::MAX;
+        self
+    }
+
+    // Everything up to the terminator is a value, options included, as in `find -exec rm {} ;`, so
+    // the arity should usually be open-ended
+    pub fn value_terminator(mut self, terminator: &'static str) -> Self {
+        self.value_terminator = Some(terminator);
+        self
+    }
+
+    pub fn delimiter(mut self, delimiter: char) -> Self {
+        self.delimiter = Some(delimiter);
+        self.multiple = true;
+        self
+    }
+
+    pub fn value_name(mut self, name: &'static str) -> Self {
+        self.value_name = Some(name);
+        self
+    }
+
+    pub fn value_style(mut self, style: ValueStyle) -> Self {
+        self.value_style = Some(style);
+        self
+    }
+
+    pub fn choices(mut self, choices: &[&'static str]) -> Self {
+        self.choices = choices.to_vec();
+        self
+    }
+
+    pub fn or_other(mut self) -> Self {
+        self.or_other = true;
+        self
+    }
+
+    pub fn config_key(mut self, key: &'static str) -> Self {
+        self.config_key = Some(key);
+        self
+    }
+
+    pub fn env(mut self, var: &'static str) -> Self {
+        self.env = Some(var);
+        self
+    }
+
+    pub fn env_only(mut self, var: &'static str) -> Self {
+        self.env = Some(var);
+        self.env_only = true;
+        self
+    }
+
+    pub fn hidden(mut self) -> Self {
+        self.hidden = true;
+        self
+    }
+
+    // Like `--help`: when given, only the constraints checked after consuming the args are skipped
+    pub fn eager(mut self) -> Self {
+        self.eager = true;
+        self
+    }
+
+    pub fn negatable(mut self) -> Self {
+        self.negatable = true;
+        self
+    }
+
+    // Without a preceding alias the entry is left with an empty one, which `add_option` rejects
+    pub fn alias_help(mut self, help: &'static str) -> Self {
+        let alias = self.aliases.last().copied().unwrap_or_default();
+
+        self.alias_help.push((alias, help));
+        self
+    }
+
+    pub fn allow_hyphen_values(mut self) -> Self {
+        self.allow_hyphen_values = true;
+        self
+    }
+
+    pub fn hint(mut self, hint: ValueHint) -> Self {
+        self.hint = Some(hint);
+        self
+    }
+
+    pub fn min_occurrences(mut self, min: usize) -> Self {
+        self.occurrences = min..=*self.occurrences.end();
+        self
+    }
+
+    pub fn max_occurrences(mut self, max: usize) -> Self {
+        self.occurrences = *self.occurrences.start()..=max;
+        self
+    }
+
+    pub fn duplicates(mut self, duplicates: DuplicateOptions) -> Self {
+        self.duplicates = duplicates;
+        self
+    }
+
+    pub fn key_value(mut self, duplicates: DuplicateKeys) -> Self {
+        self.key_values = Some(duplicates);
+        self.multiple = true;
+        self
+    }
+
+    pub fn encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = Some(encoding);
+        self
+    }
+
+    pub fn plus_negatable(mut self) -> Self {
+        self.plus_negatable = true;
+        self
+    }
+
     pub fn alias(mut self, alias: &'static str) -> Self {
-        self.alias = Some(alias);
+        self.aliases.push(alias);
+        self
+    }
+
+    pub fn validator(
+        mut self,
+        f: impl Fn(&str) -> Result<(), String> + Send + Sync + 'static,
+    ) -> Self {
+        self.validator = Some(Validator::new(f));
+        self
+    }
+
+    pub fn default_missing(mut self, value: &'static str) -> Self {
+        self.default_missing = Some(value);
+        self
+    }
+
+    pub fn default_value(mut self, value: &'static str) -> Self {
+        self.default = Some(value);
+        self
+    }
+
+    pub fn default_value_if(
+        mut self,
+        other: &'static str,
+        other_value: Option<&'static str>,
+        default: &'static str,
+    ) -> Self {
+        self.conditional_defaults
+            .push((other, other_value, default));
+        self
+    }
+
+    pub fn requires(mut self, other: &'static str) -> Self {
+        self.requires.push(other);
+        self
+    }
+
+    pub fn conflicts_with(mut self, other: &'static str) -> Self {
+        self.conflicts.push(other);
+        self
+    }
+
+    pub fn redact(mut self, f: impl Fn(&str) -> String + Send + Sync + 'static) -> Self {
+        self.redactor = Some(Redactor::new(f));
+        self
+    }
+
+    pub fn range(mut self, range: impl RangeBounds<i64>) -> Self {
+        self.range = validator::inclusive_range(range);
         self
     }
 
     fn new(name: &'static str, kind: OptionalArgKind) -> Self {
         Self {
             name,
-            alias: None,
+            aliases: Vec::new(),
             kind,
             multiple: false,
+            validator: None,
+            range: None,
+            redactor: None,
+            default_missing: None,
+            conditional_defaults: Vec::new(),
+            requires: Vec::new(),
+            conflicts: Vec::new(),
+            allow_hyphen_values: false,
+            help: None,
+            alias_help: vec![],
+            negatable: false,
+            plus_negatable: false,
+            hidden: false,
+            arity: 1..=1,
+            env: None,
+            env_only: false,
+            config_key: None,
+            choices: vec![],
+            or_other: false,
+            default: None,
+            encoding: None,
+            key_values: None,
+            hint: None,
+            value_style: None,
+            duplicates: DuplicateOptions::Error,
+            occurrences: 0..=usize::MAX,
+            value_name: None,
+            delimiter: None,
+            value_terminator: None,
+            eager: false,
         }
     }
 }
@@ -42,6 +266,143 @@ impl OptionalArg {
         Self::is_valid_hyphen_seq(alias) && alias.len() == 1
     }
 
+    pub(crate) fn is_valid_config_key(key: &str) -> bool {
+        key.split('.').all(|segment| {
+            !segment.is_empty()
+                && segment
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        })
+    }
+
+    pub(crate) fn config_path(&self) -> Vec<&'static str> {
+        match self.config_key {
+            Some(key) => key.split('.').collect(),
+            None => vec![self.name],
+        }
+    }
+
+    pub(crate) fn check_value(&self, value: &str) -> Result<(), String> {
+        self.check_range(value)?;
+
+        if !self.or_other {
+            validator::check_choices(&self.choices, value)?;
+        }
+
+        if self.key_values.is_some() && value.split_once('=').is_none_or(|(key, _)| key.is_empty())
+        {
+            return Err("expected KEY=VALUE".to_string());
+        }
+
+        if let Some(encoding) = self.encoding {
+            encoding.decode(value)?;
+        }
+
+        match &self.validator {
+            Some(validator) => validator.validate(value),
+            None => Ok(()),
+        }
+    }
+
+    // A value nothing looks into can be kept as given, e.g. a path that isn't valid unicode
+    pub(crate) fn checks_values(&self) -> bool {
+        self.range.is_some()
+            || (!self.choices.is_empty() && !self.or_other)
+            || self.key_values.is_some()
+            || self.encoding.is_some()
+            || self.validator.is_some()
+            || self.delimiter.is_some()
+    }
+
+    // Only single values are split, `.values()` options already take several
+    pub(crate) fn split_value<'v>(&self, value: &'v str) -> Vec<&'v str> {
+        match (&self.kind, self.delimiter) {
+            (OptionalArgKind::RequiredValue, Some(delimiter)) => value.split(delimiter).collect(),
+            _ => vec![value],
+        }
+    }
+
+    // A default goes in as if it had been given, so it is checked and split the same way
+    pub(crate) fn parse_default(&self, value: &str) -> Result<Vec<ParsedArg>, String> {
+        self.split_value(value)
+            .into_iter()
+            .map(|value| self.parse_value(value))
+            .collect()
+    }
+
+    pub(crate) fn parse_value(&self, value: &str) -> Result<ParsedArg, String> {
+        let name = self.name;
+
+        Ok(match self.kind {
+            OptionalArgKind::Flag => ParsedArg::Flag {
+                name,
+                value: match value {
+                    "true" => true,
+                    "false" => false,
+                    _ => return Err("expected 'true' or 'false'".to_string()),
+                },
+            },
+            OptionalArgKind::RequiredValue => {
+                self.check_value(value)?;
+
+                ParsedArg::RequiredValue {
+                    name,
+                    value: value.to_string(),
+                }
+            }
+            OptionalArgKind::OptionalValue if value.is_empty() => ParsedArg::OptionalValue {
+                name,
+                value: self.default_missing.map(|s| s.to_string()),
+            },
+            OptionalArgKind::OptionalValue => {
+                self.check_value(value)?;
+
+                ParsedArg::OptionalValue {
+                    name,
+                    value: Some(value.to_string()),
+                }
+            }
+            OptionalArgKind::Count => ParsedArg::Count {
+                name,
+                value: value.parse().map_err(|_| "not a number".to_string())?,
+            },
+            OptionalArgKind::Values => {
+                self.parse_values(value.split_whitespace().map(|s| s.to_string()).collect())?
+            }
+        })
+    }
+
+    pub(crate) fn parse_values(&self, values: Vec<String>) -> Result<ParsedArg, String> {
+        if !self.arity.contains(&values.len()) {
+            return Err(match *self.arity.end() {
+                usize::MAX => format!("expected at least {} value(s)", self.arity.start()),
+                max => format!("expected {} to {} value(s)", self.arity.start(), max),
+            });
+        }
+
+        for value in &values {
+            self.check_value(value)?;
+        }
+
+        Ok(ParsedArg::Values {
+            name: self.name,
+            values,
+        })
+    }
+
+    pub(crate) fn check_range(&self, value: &str) -> Result<(), String> {
+        match &self.range {
+            Some(range) => validator::check_range(range, value),
+            None => Ok(()),
+        }
+    }
+
+    pub(crate) fn is_valid_unicode_alias(alias: &str) -> bool {
+        let mut chars = alias.chars();
+
+        matches!((chars.next(), chars.next()), (Some(ch), None) if ch.is_alphanumeric())
+    }
+
     fn is_valid_hyphen_seq(name: &str) -> bool {
         let mut allow_hyphen = false;
 
@@ -70,9 +431,71 @@ fn test_is_valid() {
     assert!(OptionalArg::is_valid("a-A-0"));
 }
 
+#[test]
+fn test_is_valid_config_key() {
+    assert!(OptionalArg::is_valid_config_key("url"));
+    assert!(OptionalArg::is_valid_config_key("database.pool_size"));
+    assert!(OptionalArg::is_valid_config_key("a.b-c.d"));
+    assert!(!OptionalArg::is_valid_config_key(""));
+    assert!(!OptionalArg::is_valid_config_key("database."));
+    assert!(!OptionalArg::is_valid_config_key("a..b"));
+    assert!(!OptionalArg::is_valid_config_key("a b"));
+}
+
 #[test]
 fn test_is_valid_alias() {
     assert!(OptionalArg::is_valid_alias("a"));
     assert!(!OptionalArg::is_valid_alias("-"));
     assert!(!OptionalArg::is_valid_alias("aA"));
+    assert!(!OptionalArg::is_valid_alias("é"));
+}
+
+#[test]
+fn test_is_valid_unicode_alias() {
+    assert!(OptionalArg::is_valid_unicode_alias("a"));
+    assert!(OptionalArg::is_valid_unicode_alias("é"));
+    assert!(OptionalArg::is_valid_unicode_alias("日"));
+    assert!(!OptionalArg::is_valid_unicode_alias(""));
+    assert!(!OptionalArg::is_valid_unicode_alias("-"));
+    assert!(!OptionalArg::is_valid_unicode_alias("💩"));
+    assert!(!OptionalArg::is_valid_unicode_alias("日本"));
+}
+
+#[test]
+fn test_check_range() {
+    let option = OptionalArg::required_value("port").range(1..=65535);
+
+    assert_eq!(Ok(()), option.check_range("1"));
+    assert_eq!(Ok(()), option.check_range("65535"));
+    assert_eq!(
+        Err("must be in range 1..=65535".to_string()),
+        option.check_range("0")
+    );
+    assert_eq!(Err("not a number".to_string()), option.check_range("80a0"));
+
+    let option = OptionalArg::required_value("jobs").range(1..);
+
+    assert_eq!(
+        Err("must be at least 1".to_string()),
+        option.check_range("0")
+    );
+
+    let option = OptionalArg::required_value("nice").range(..20);
+
+    assert_eq!(Ok(()), option.check_range("-20"));
+    assert_eq!(
+        Err("must be at most 19".to_string()),
+        option.check_range("20")
+    );
+
+    assert_eq!(
+        Ok(()),
+        OptionalArg::required_value("foo").check_range("bar")
+    );
+    assert_eq!(
+        Ok(()),
+        OptionalArg::required_value("foo")
+            .range(..)
+            .check_range("bar")
+    );
 }
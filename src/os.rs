@@ -0,0 +1,338 @@
+use super::{
+    parsed::RawArgs, parser::Tokens, ArgParser, ArgParserError, ArgSelector, OptionalArgKind,
+    ParsedArg, ParsedArgs, Resolver,
+};
+use std::{
+    env,
+    ffi::{OsStr, OsString},
+};
+
+impl ArgParser {
+    pub fn parse_args_os(&self) -> Result<ParsedArgs, ArgParserError> {
+        let args = env::args_os().skip(1).collect::<Vec<_>>();
+        let os_args = args.iter().map(|s| &s[..]).collect::<Vec<_>>();
+
+        self.parse_os(&os_args)
+    }
+
+    pub fn parse_os(&self, args: &[&OsStr]) -> Result<ParsedArgs, ArgParserError> {
+        let mut lossy_args = vec![];
+
+        for &arg in args {
+            let lossy = arg.to_string_lossy();
+
+            // Option names must be valid unicode, so the lossy conversion is only ever used as a
+            // stand-in for positional arguments and option values, attached ones included
+            if arg.to_str().is_none() && self.os_option_name(&lossy).contains('\u{fffd}') {
+                return Err(ArgParserError::InvalidUnicode {
+                    value: lossy.into_owned(),
+                });
+            }
+
+            lossy_args.push(lossy);
+        }
+
+        let resolver = Resolver {
+            os_args: Some(args),
+            ..Resolver::new(self)
+        };
+        let mut parsed =
+            resolver.resolve(&lossy_args.iter().map(|arg| &arg[..]).collect::<Vec<_>>())?;
+
+        parsed.raw_args = RawArgs::from_os(args.iter().map(|&arg| arg.to_os_string()).collect());
+
+        Ok(parsed)
+    }
+
+    // What has to be valid unicode in an option-like arg: a long option up to its value, or the
+    // aliases of a short one up to the first that takes a value, as whatever follows may be it
+    fn os_option_name<'a>(&self, arg: &'a str) -> &'a str {
+        let (prefix, short) = if arg.starts_with(self.long_prefix) {
+            (self.long_prefix, false)
+        } else if arg.starts_with(self.short_prefix) {
+            (self.short_prefix, true)
+        } else {
+            return "";
+        };
+
+        let mut end = prefix.len();
+
+        for c in arg[prefix.len()..].chars() {
+            if c == '=' || (self.colon_separator && c == ':') {
+                break;
+            }
+
+            let alias = &arg[end..end + c.len_utf8()];
+
+            end += alias.len();
+
+            if short && self.takes_value(alias) {
+                break;
+            }
+        }
+
+        &arg[..end]
+    }
+
+    fn takes_value(&self, alias: &str) -> bool {
+        self.aliases.get(alias).is_some_and(|name| {
+            !matches!(
+                self.options[name].kind,
+                OptionalArgKind::Flag | OptionalArgKind::Count
+            )
+        })
+    }
+
+    // Puts back the original of a non-unicode arg that a single step consumed, as long as nothing
+    // needs it as a string: a positional, or the value of an option that doesn't check its values
+    pub(crate) fn restore_os(
+        &self,
+        fresh: &mut [ParsedArg],
+        bumped: bool,
+        os_args: &[&OsStr],
+        tokens: &Tokens,
+    ) -> Result<(), ArgParserError> {
+        let mut invalid = tokens
+            .consumed()
+            .filter(|&idx| os_args[idx].to_str().is_none())
+            .collect::<Vec<_>>();
+
+        invalid.dedup();
+
+        let Some(&idx) = invalid.first() else {
+            return Ok(());
+        };
+
+        // The rest of a cluster such as `-vo<VALUE>` is only looked at by the next step
+        if invalid.len() == 1
+            && tokens.pushed_back()
+            && fresh
+                .iter()
+                .all(|arg| matches!(arg, ParsedArg::Flag { .. } | ParsedArg::Count { .. }))
+        {
+            return Ok(());
+        }
+
+        let original = os_args[idx];
+        let lossy = original.to_string_lossy();
+
+        match fresh {
+            [arg] if invalid.len() == 1 && !bumped => match *arg {
+                ParsedArg::Positional { ref value } if *value == lossy => {
+                    *arg = ParsedArg::PositionalOs {
+                        value: original.to_os_string(),
+                    };
+
+                    return Ok(());
+                }
+                ParsedArg::RequiredValue { name, ref value }
+                    if !self.options[name].checks_values() =>
+                {
+                    if let Some(value) = os_suffix(original, &lossy, value) {
+                        *arg = ParsedArg::ValueOs { name, value };
+
+                        return Ok(());
+                    }
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+
+        Err(ArgParserError::InvalidUnicode {
+            value: lossy.into_owned(),
+        })
+    }
+}
+
+// The original bytes of a value attached to an option, e.g. `--out=VALUE` or `-oVALUE`, whose
+// lossy conversion ends the arg's one; the part before it has to be valid unicode
+fn os_suffix(original: &OsStr, lossy: &str, value: &str) -> Option<OsString> {
+    let prefix = lossy.strip_suffix(value)?;
+
+    if prefix.contains('\u{fffd}') {
+        return None;
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+
+        Some(OsStr::from_bytes(&original.as_bytes()[prefix.len()..]).to_os_string())
+    }
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::ffi::{OsStrExt, OsStringExt};
+
+        let wide = original
+            .encode_wide()
+            .skip(prefix.encode_utf16().count())
+            .collect::<Vec<_>>();
+
+        Some(OsString::from_wide(&wide))
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    {
+        (prefix.is_empty()).then(|| original.to_os_string())
+    }
+}
+
+impl<'a> ArgSelector<'a, &'a ParsedArgs> {
+    pub fn get_positional_named_os(&self, name: &str) -> Option<&'a OsStr> {
+        match self.find_positional_named(name)? {
+            ParsedArg::Positional { value } => Some(OsStr::new(value)),
+            ParsedArg::PositionalOs { value } => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl<'a, P> ArgSelector<'a, P> {
+    pub fn get_positional_os(&self) -> Vec<&'a OsStr> {
+        self.args
+            .iter()
+            .filter_map(|arg| match arg {
+                ParsedArg::Positional { value } => Some(OsStr::new(value)),
+                ParsedArg::PositionalOs { value } => Some(&value[..]),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn get_value_os(&self, name: &str) -> Option<&'a OsStr> {
+        self.args.iter().find_map(|arg| match arg {
+            ParsedArg::RequiredValue { name: _name, value } if name == *_name => {
+                Some(OsStr::new(value))
+            }
+            ParsedArg::ValueOs { name: _name, value } if name == *_name => Some(&value[..]),
+            _ => None,
+        })
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn test_parse_os() -> Result<(), ArgParserError> {
+    use super::{OptionalArg, PositionalArg};
+    use std::os::unix::ffi::OsStrExt;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::required_value("output").alias("o"))?
+        .add_option(OptionalArg::required_value("tag").delimiter(','))?
+        .add_option(OptionalArg::values("exec", 1..=2))?
+        .add_option(OptionalArg::flag("verbose").alias("v"))?
+        .add_positional(PositionalArg::rest())?;
+
+    let bad = OsStr::from_bytes(b"caf\xe9.txt");
+
+    let args = parser.parse_os(&[
+        OsStr::new("--verbose"),
+        bad,
+        OsStr::new("--output=out"),
+        OsStr::new("ok.txt"),
+    ])?;
+    let sel = ArgSelector::from(&args);
+
+    assert_eq!(vec![bad, OsStr::new("ok.txt")], sel.get_positional_os());
+    assert_eq!(vec!["ok.txt"], sel.get_positional());
+    assert_eq!(Some(&"out".to_string()), sel.get_value("output"));
+    assert_eq!(bad, args.raw_args()[1]);
+    assert_eq!(
+        ParsedArg::PositionalOs {
+            value: bad.to_os_string()
+        },
+        args[1]
+    );
+
+    let args = parser.parse_os(&[OsStr::new("--output"), bad])?;
+
+    assert_eq!(Some(bad), ArgSelector::from(&args).get_value_os("output"));
+    assert_eq!(None, ArgSelector::from(&args).get_value("output"));
+
+    // Both are `caf\u{fffd}.txt` once converted, but each keeps its own bytes
+    let other = OsStr::from_bytes(b"caf\xff.txt");
+    let args = parser.parse_os(&[other, OsStr::new("-o"), bad, bad])?;
+    let sel = ArgSelector::from(&args);
+
+    assert_eq!(vec![other, bad], sel.get_positional_os());
+    assert_eq!(Some(bad), sel.get_value_os("output"));
+
+    for args in [[OsStr::new("--tag"), bad], [OsStr::new("--exec"), bad]] {
+        assert_eq!(
+            Err(ArgParserError::InvalidUnicode {
+                value: "caf\u{fffd}.txt".to_string()
+            }),
+            parser.parse_os(&args).map(|_| ())
+        );
+    }
+    assert_eq!(
+        "'--outp\u{fffd}t=caf.txt' is not valid unicode",
+        parser
+            .parse_os(&[OsStr::from_bytes(b"--outp\xe9t=caf.txt")])
+            .unwrap_err()
+            .to_string()
+    );
+
+    // Only the name has to be valid unicode, a value attached to it keeps its bytes
+    for attached in [
+        &b"--output=caf\xe9.txt"[..],
+        b"-ocaf\xe9.txt",
+        b"-o=caf\xe9.txt",
+        b"-vocaf\xe9.txt",
+    ] {
+        let args = parser.parse_os(&[OsStr::from_bytes(attached)])?;
+
+        assert_eq!(Some(bad), ArgSelector::from(&args).get_value_os("output"));
+    }
+    assert_eq!(
+        Err(ArgParserError::InvalidUnicode {
+            value: "-v\u{fffd}".to_string()
+        }),
+        parser.parse_os(&[OsStr::from_bytes(b"-v\xe9")]).map(|_| ())
+    );
+    assert_eq!(
+        Err(ArgParserError::InvalidUnicode {
+            value: "--tag=caf\u{fffd}.txt".to_string()
+        }),
+        parser
+            .parse_os(&[OsStr::from_bytes(b"--tag=caf\xe9.txt")])
+            .map(|_| ())
+    );
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_get_positional_named_os() -> Result<(), ArgParserError> {
+    use super::PositionalArg;
+    use std::os::unix::ffi::OsStrExt;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_positional(PositionalArg::named().name("src"))?
+        .add_positional(PositionalArg::named().name("dst"))?;
+
+    let bad = OsStr::from_bytes(b"caf\xe9.txt");
+    let args = parser.parse_os(&[bad, OsStr::new("out.txt")])?;
+    let sel = ArgSelector::from(&args);
+
+    assert_eq!(None, sel.get_positional_named("src"));
+    assert_eq!(Some(bad), sel.get_positional_named_os("src"));
+    assert_eq!(
+        Some(&"out.txt".to_string()),
+        sel.get_positional_named("dst")
+    );
+    assert_eq!(
+        Some(OsStr::new("out.txt")),
+        sel.get_positional_named_os("dst")
+    );
+    assert_eq!(None, sel.get_positional_named_os("other"));
+
+    Ok(())
+}
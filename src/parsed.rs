@@ -0,0 +1,605 @@
+use super::{ArgParser, Encoding, ParsedArg, Redactor, RestPolicy};
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    fmt, iter,
+    ops::Deref,
+    sync::{Arc, OnceLock},
+};
+
+#[derive(Default, PartialEq)]
+pub struct ParsedArgs {
+    pub(crate) args: Vec<ParsedArg>,
+    pub(crate) unused_options: Vec<&'static str>,
+    pub(crate) ignored_options: Vec<String>,
+    pub(crate) signature: Option<usize>,
+    pub(crate) sources: HashMap<&'static str, ValueSource>,
+    #[cfg(feature = "config")]
+    pub(crate) unknown_config_keys: Vec<String>,
+    pub(crate) tables: Arc<ParseTables>,
+    pub(crate) meta: ParseMeta,
+    pub(crate) raw_args: RawArgs,
+    pub(crate) remainder: Vec<String>,
+    pub(crate) defaulted_positional: Vec<usize>,
+    #[cfg(feature = "config")]
+    pub(crate) config_file: Option<String>,
+}
+
+// What the getters need to know about the parser, the same for every parse, so it is worked out
+// once and shared
+#[derive(Default, PartialEq)]
+pub(crate) struct ParseTables {
+    pub(crate) multiple_options: Vec<&'static str>,
+    pub(crate) redactors: HashMap<&'static str, Redactor>,
+    pub(crate) choices: HashMap<&'static str, Vec<&'static str>>,
+    pub(crate) encodings: HashMap<&'static str, Encoding>,
+    pub(crate) groups: HashMap<&'static str, Vec<&'static str>>,
+    pub(crate) positional_names: Vec<Option<&'static str>>,
+    pub(crate) signature_names: Vec<Vec<Option<&'static str>>>,
+}
+
+impl ParseTables {
+    pub(crate) fn redact(&self, args: &[ParsedArg]) -> Vec<ParsedArg> {
+        use ParsedArg::*;
+
+        args.iter()
+            .map(|arg| {
+                let Some(redactor) = arg.name().and_then(|name| self.redactors.get(name)) else {
+                    return arg.clone();
+                };
+
+                match *arg {
+                    RequiredValue { name, ref value } => RequiredValue {
+                        name,
+                        value: redactor.redact(value),
+                    },
+                    ValueOs { name, ref value } => ValueOs {
+                        name,
+                        value: redactor.redact(&value.to_string_lossy()).into(),
+                    },
+                    OptionalValue { name, ref value } => OptionalValue {
+                        name,
+                        value: value.as_deref().map(|s| redactor.redact(s)),
+                    },
+                    Values { name, ref values } => Values {
+                        name,
+                        values: values.iter().map(|s| redactor.redact(s)).collect(),
+                    },
+                    _ => arg.clone(),
+                }
+            })
+            .collect()
+    }
+}
+
+// Dropped whenever the parser gets a new option, positional arg, group or signature, and never
+// part of what makes two parsers equal
+#[derive(Clone, Default)]
+pub(crate) struct TablesCache(OnceLock<Arc<ParseTables>>);
+
+impl TablesCache {
+    pub(crate) fn clear(&mut self) {
+        self.0.take();
+    }
+}
+
+impl PartialEq for TablesCache {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl fmt::Debug for TablesCache {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("TablesCache")
+    }
+}
+
+impl ArgParser {
+    pub(crate) fn tables(&self) -> Arc<ParseTables> {
+        let tables = self.tables.0.get_or_init(|| {
+            Arc::new(ParseTables {
+                multiple_options: self
+                    .options
+                    .values()
+                    .filter(|option| option.multiple)
+                    .map(|option| option.name)
+                    .collect(),
+                redactors: self
+                    .options
+                    .iter()
+                    .filter_map(|(&name, option)| Some((name, option.redactor.clone()?)))
+                    .collect(),
+                choices: self
+                    .options
+                    .iter()
+                    .filter(|(_, option)| !option.choices.is_empty())
+                    .map(|(&name, option)| (name, option.choices.clone()))
+                    .collect(),
+                encodings: self
+                    .options
+                    .iter()
+                    .filter_map(|(&name, option)| Some((name, option.encoding?)))
+                    .collect(),
+                groups: self
+                    .groups
+                    .iter()
+                    .map(|group| (group.name, group.options.clone()))
+                    .collect(),
+                positional_names: self.positional.iter().map(|arg| arg.name).collect(),
+                signature_names: self
+                    .signatures
+                    .iter()
+                    .map(|signature| signature.iter().map(|arg| arg.name).collect())
+                    .collect(),
+            })
+        });
+
+        Arc::clone(tables)
+    }
+}
+
+// The args as they were given, kept in a single string until they are asked for as `OsString`s
+#[derive(Default)]
+pub(crate) struct RawArgs {
+    joined: String,
+    ends: Vec<usize>,
+    os: OnceLock<Vec<OsString>>,
+}
+
+impl RawArgs {
+    pub(crate) fn new(args: &[&str]) -> Self {
+        let mut joined = String::with_capacity(args.iter().map(|arg| arg.len()).sum());
+        let ends = args
+            .iter()
+            .map(|arg| {
+                joined.push_str(arg);
+                joined.len()
+            })
+            .collect();
+
+        Self {
+            joined,
+            ends,
+            os: OnceLock::new(),
+        }
+    }
+
+    pub(crate) fn from_os(args: Vec<OsString>) -> Self {
+        Self {
+            os: OnceLock::from(args),
+            ..Default::default()
+        }
+    }
+
+    fn get(&self) -> &[OsString] {
+        self.os.get_or_init(|| {
+            iter::once(0)
+                .chain(self.ends.iter().copied())
+                .zip(&self.ends)
+                .map(|(start, &end)| OsString::from(&self.joined[start..end]))
+                .collect()
+        })
+    }
+}
+
+impl PartialEq for RawArgs {
+    fn eq(&self, other: &Self) -> bool {
+        self.get() == other.get()
+    }
+}
+
+impl ParsedArgs {
+    pub fn unused_options(&self) -> &[&'static str] {
+        &self.unused_options
+    }
+
+    pub fn ignored_options(&self) -> &[String] {
+        &self.ignored_options
+    }
+
+    #[cfg(feature = "config")]
+    pub fn unknown_config_keys(&self) -> &[String] {
+        &self.unknown_config_keys
+    }
+
+    pub fn source(&self, name: &str) -> Option<ValueSource> {
+        if let Some(source) = self.sources.get(name) {
+            return Some(source.clone());
+        }
+
+        self.args
+            .iter()
+            .any(|arg| arg.name() == Some(name))
+            .then_some(ValueSource::CommandLine)
+    }
+
+    pub fn choice(&self, name: &str) -> Option<Choice> {
+        use ParsedArg::*;
+
+        let choices = self.tables.choices.get(name)?;
+
+        let value = self.args.iter().find_map(|arg| match arg {
+            RequiredValue { name: n, value }
+            | OptionalValue {
+                name: n,
+                value: Some(value),
+            } if *n == name => Some(value),
+            _ => None,
+        })?;
+
+        Some(match choices.iter().find(|&choice| choice == value) {
+            Some(choice) => Choice::Known(choice),
+            None => Choice::Other(value.clone()),
+        })
+    }
+
+    pub fn with_sources(&self) -> Vec<(&ParsedArg, Option<ValueSource>)> {
+        self.args
+            .iter()
+            .map(|arg| (arg, arg.name().and_then(|name| self.source(name))))
+            .collect()
+    }
+
+    #[cfg(feature = "config")]
+    pub fn config_file(&self) -> Option<&str> {
+        self.config_file.as_deref()
+    }
+
+    pub fn raw_args(&self) -> &[OsString] {
+        self.raw_args.get()
+    }
+
+    pub fn defaulted_positional(&self) -> &[usize] {
+        &self.defaulted_positional
+    }
+
+    pub fn remainder(&self) -> &[String] {
+        &self.remainder
+    }
+
+    pub fn into_parts(self) -> (Vec<ParsedArg>, Vec<String>) {
+        (self.args, self.remainder)
+    }
+
+    pub fn meta(&self) -> &ParseMeta {
+        &self.meta
+    }
+
+    pub fn signature(&self) -> Option<usize> {
+        self.signature
+    }
+
+    // With signatures, the names are the ones of the signature that matched
+    pub(crate) fn positional_names(&self) -> &[Option<&'static str>] {
+        match self.signature {
+            Some(signature) => &self.tables.signature_names[signature],
+            None => &self.tables.positional_names,
+        }
+    }
+
+    pub fn to_map(&self) -> (HashMap<String, Value>, Vec<String>) {
+        use ParsedArg::*;
+
+        let mut map = self
+            .unused_options
+            .iter()
+            .map(|&name| (name.to_string(), Value::None))
+            .collect::<HashMap<_, _>>();
+        let mut positional = vec![];
+
+        for arg in &self.args {
+            let lossy;
+            let (name, value) = match arg {
+                Positional { value } => {
+                    positional.push(value.clone());
+                    continue;
+                }
+                PositionalOs { value } => {
+                    positional.push(value.to_string_lossy().into_owned());
+                    continue;
+                }
+                Unknown { .. } | Rest { .. } => continue,
+                Flag { name, value } => {
+                    map.insert(name.to_string(), Value::Bool(*value));
+                    continue;
+                }
+                Count { name, value } => {
+                    map.insert(name.to_string(), Value::Count(*value));
+                    continue;
+                }
+                Values { name, values } => {
+                    let entry = map.entry(name.to_string()).or_insert(Value::None);
+
+                    match entry {
+                        Value::List(list) if self.tables.multiple_options.contains(name) => {
+                            list.extend(values.iter().cloned())
+                        }
+                        _ => *entry = Value::List(values.clone()),
+                    }
+
+                    continue;
+                }
+                RequiredValue { name, value } => (name, Some(value)),
+                ValueOs { name, value } => {
+                    lossy = value.to_string_lossy().into_owned();
+                    (name, Some(&lossy))
+                }
+                OptionalValue { name, value } => (name, value.as_ref()),
+            };
+
+            let entry = map.entry(name.to_string()).or_insert(Value::None);
+
+            if self.tables.multiple_options.contains(name) {
+                if !matches!(entry, Value::List(_)) {
+                    *entry = Value::List(vec![]);
+                }
+
+                if let (Value::List(values), Some(value)) = (entry, value) {
+                    values.push(value.clone());
+                }
+            } else {
+                *entry = value.map_or(Value::None, |value| Value::Str(value.clone()));
+            }
+        }
+
+        (map, positional)
+    }
+
+    pub fn redacted(&self) -> Vec<ParsedArg> {
+        self.tables.redact(&self.args)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ParseMeta {
+    pub options: usize,
+    pub positional: usize,
+    pub bytes: usize,
+    pub env: bool,
+    pub config: bool,
+    pub rest_policy: RestPolicy,
+    pub eager: Option<&'static str>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Choice {
+    Known(&'static str),
+    Other(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ValueSource {
+    CommandLine,
+    Env(String),
+    Default,
+    Config(&'static str),
+}
+
+impl fmt::Display for ValueSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ValueSource::CommandLine => write!(f, "command line"),
+            ValueSource::Env(var) => write!(f, "environment variable ${}", var),
+            ValueSource::Config(key) => write!(f, "config key '{}'", key),
+            ValueSource::Default => write!(f, "default"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    Str(String),
+    List(Vec<String>),
+    Count(usize),
+    None,
+}
+
+impl fmt::Debug for ParsedArgs {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ParsedArgs")
+            .field("args", &self.redacted())
+            .field("unused_options", &self.unused_options)
+            .field("ignored_options", &self.ignored_options)
+            .field("signature", &self.signature)
+            .finish()
+    }
+}
+
+impl Deref for ParsedArgs {
+    type Target = [ParsedArg];
+
+    fn deref(&self) -> &Self::Target {
+        &self.args
+    }
+}
+
+impl AsRef<[ParsedArg]> for ParsedArgs {
+    fn as_ref(&self) -> &[ParsedArg] {
+        &self.args
+    }
+}
+
+impl From<ParsedArgs> for Vec<ParsedArg> {
+    fn from(parsed: ParsedArgs) -> Self {
+        parsed.args
+    }
+}
+
+#[test]
+fn test_redacted() {
+    use ParsedArg::*;
+
+    let args = ParsedArgs {
+        args: vec![
+            RequiredValue {
+                name: "token",
+                value: "secret1234".to_string(),
+            },
+            RequiredValue {
+                name: "user",
+                value: "root".to_string(),
+            },
+        ],
+        tables: Arc::new(ParseTables {
+            redactors: HashMap::from([(
+                "token",
+                Redactor::new(|s| format!("***{}", &s[s.len().saturating_sub(4)..])),
+            )]),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+
+    assert_eq!(
+        vec![
+            RequiredValue {
+                name: "token",
+                value: "***1234".to_string(),
+            },
+            RequiredValue {
+                name: "user",
+                value: "root".to_string(),
+            },
+        ],
+        args.redacted()
+    );
+    assert!(!format!("{:?}", args).contains("secret"));
+    assert!(format!("{:?}", args).contains("***1234"));
+}
+
+#[test]
+fn test_redacted_values() -> Result<(), super::ArgParserError> {
+    use super::{ArgParser, ArgSelector, OptionalArg};
+
+    let mut parser = ArgParser::default();
+
+    parser.add_option(OptionalArg::values("keys", 2..=2).redact(|_| "***".to_string()))?;
+
+    let args = parser.parse(&["--keys", "secret1", "secret2"])?;
+
+    assert_eq!(
+        vec![ParsedArg::Values {
+            name: "keys",
+            values: vec!["***".to_string(), "***".to_string()],
+        }],
+        args.redacted()
+    );
+    assert!(!format!("{:?}", args).contains("secret"));
+    assert_eq!(
+        vec![["secret1", "secret2"]],
+        ArgSelector::new(&args).get_grouped_values("keys")
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_to_map() {
+    use ParsedArg::*;
+
+    let args = ParsedArgs {
+        args: vec![
+            Positional {
+                value: "foo".to_string(),
+            },
+            Flag {
+                name: "verbose",
+                value: true,
+            },
+            RequiredValue {
+                name: "user",
+                value: "root".to_string(),
+            },
+            OptionalValue {
+                name: "tag",
+                value: Some("a".to_string()),
+            },
+            OptionalValue {
+                name: "tag",
+                value: None,
+            },
+            OptionalValue {
+                name: "tag",
+                value: Some("b".to_string()),
+            },
+            OptionalValue {
+                name: "color",
+                value: None,
+            },
+            Positional {
+                value: "bar".to_string(),
+            },
+            Count {
+                name: "debug",
+                value: 2,
+            },
+        ],
+        unused_options: vec!["cache"],
+        tables: Arc::new(ParseTables {
+            multiple_options: vec!["tag"],
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+
+    assert_eq!(
+        (
+            HashMap::from([
+                ("verbose".to_string(), Value::Bool(true)),
+                ("user".to_string(), Value::Str("root".to_string())),
+                (
+                    "tag".to_string(),
+                    Value::List(vec!["a".to_string(), "b".to_string()])
+                ),
+                ("color".to_string(), Value::None),
+                ("debug".to_string(), Value::Count(2)),
+                ("cache".to_string(), Value::None),
+            ]),
+            vec!["foo".to_string(), "bar".to_string()]
+        ),
+        args.to_map()
+    );
+}
+
+#[test]
+fn test_choice() -> Result<(), super::ArgParserError> {
+    use super::{ArgParser, ArgParserError, OptionalArg};
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::required_value("format").choices(&["json", "yaml"]))?
+        .add_option(
+            OptionalArg::required_value("compression")
+                .choices(&["gzip", "zstd"])
+                .or_other(),
+        )?
+        .add_option(OptionalArg::required_value("user"))?;
+
+    let args = parser.parse(&["--format=json", "--compression=brotli", "--user=root"])?;
+
+    assert_eq!(Some(Choice::Known("json")), args.choice("format"));
+    assert_eq!(
+        Some(Choice::Other("brotli".to_string())),
+        args.choice("compression")
+    );
+    assert_eq!(None, args.choice("user"));
+
+    let args = parser.parse(&["--compression=zstd"])?;
+
+    assert_eq!(None, args.choice("format"));
+    assert_eq!(Some(Choice::Known("zstd")), args.choice("compression"));
+
+    assert_eq!(
+        Err(ArgParserError::RejectedValue {
+            name: "format",
+            value: "toml".to_string(),
+            reason: "must be one of json, yaml".to_string()
+        }),
+        parser.parse(&["--format=toml"]).map(|_| ())
+    );
+
+    Ok(())
+}
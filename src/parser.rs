@@ -1,24 +1,76 @@
-use super::{OptionalArg, OptionalArgKind, PositionalArg, PositionalArgKind};
+use super::{
+    arena::Arena,
+    intern::intern,
+    mount::MountedOption,
+    parsed::{ParseTables, RawArgs, TablesCache},
+    visitor::visit,
+    ArgGroup, ArgGroupKind, ArgVisitor, DuplicateOptions, ExitCodes, InteractionMode, OptionalArg,
+    OptionalArgKind, ParseMeta, ParsedArgs, PositionalArg, PositionalArgKind, Resolver, RestPolicy,
+    SplitStyle, ValueHint, ValueSource, ValueStyle,
+};
 use std::{
+    borrow::Cow,
     collections::{HashMap, VecDeque},
-    env, error, fmt,
+    env, error,
+    ffi::{OsStr, OsString},
+    fmt, io,
+    ops::RangeBounds,
+    sync::Arc,
+    vec,
 };
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum ArgParserMode {
     Mixed,
     OptionsFirst,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct ArgParser {
     pub(crate) mode: ArgParserMode,
     pub(crate) aliases: HashMap<&'static str, &'static str>,
     pub(crate) options: HashMap<&'static str, OptionalArg>,
     pub(crate) positional: Vec<PositionalArg>,
+    pub(crate) mounted: HashMap<&'static str, MountedOption>,
+    pub(crate) unicode_aliases: bool,
+    pub(crate) ignored_options: Vec<&'static str>,
+    pub(crate) groups: Vec<ArgGroup>,
+    pub(crate) value_style: ValueStyle,
+    pub(crate) signatures: Vec<Vec<PositionalArg>>,
+    pub(crate) env_prefix: Option<&'static str>,
+    pub(crate) reserved: Vec<&'static str>,
+    pub(crate) response_files: bool,
+    pub(crate) args_from_stdin: bool,
+    pub(crate) split_style: SplitStyle,
+    pub(crate) slash_options: bool,
+    pub(crate) colon_separator: bool,
+    pub(crate) plus_flags: bool,
+    pub(crate) abbreviations: bool,
+    pub(crate) ignore_case: bool,
+    pub(crate) allow_unknown: bool,
+    pub(crate) strict_positional: bool,
+    pub(crate) suggestion_distance: usize,
+    pub(crate) exit_codes: ExitCodes,
+    pub(crate) stop_at_unknown: bool,
+    pub(crate) capture_rest: bool,
+    pub(crate) terminator: Option<&'static str>,
+    pub(crate) keep_terminator: bool,
+    pub(crate) negative_numbers: bool,
+    pub(crate) interaction: Option<InteractionMode>,
+    pub(crate) normalize_underscores: bool,
+    pub(crate) long_prefix: &'static str,
+    pub(crate) short_prefix: &'static str,
+    pub(crate) max_args: Option<usize>,
+    pub(crate) max_input_bytes: Option<usize>,
+    pub(crate) max_response_file_depth: usize,
+    pub(crate) tables: TablesCache,
+    #[cfg(feature = "config")]
+    pub(crate) config: Option<super::config::Config>,
+    #[cfg(feature = "config")]
+    pub(crate) deny_unknown_config_keys: bool,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum ParsedArg {
     Positional {
         value: String,
@@ -35,53 +87,754 @@ pub enum ParsedArg {
         name: &'static str,
         value: Option<String>,
     },
+    Count {
+        name: &'static str,
+        value: usize,
+    },
+    Values {
+        name: &'static str,
+        values: Vec<String>,
+    },
+    PositionalOs {
+        value: OsString,
+    },
+    ValueOs {
+        name: &'static str,
+        value: OsString,
+    },
+    Unknown {
+        raw: String,
+    },
+    Rest {
+        values: Vec<String>,
+    },
+}
+
+impl ParsedArg {
+    pub fn name(&self) -> Option<&'static str> {
+        match *self {
+            Self::Positional { .. }
+            | Self::PositionalOs { .. }
+            | Self::Unknown { .. }
+            | Self::Rest { .. } => None,
+            Self::Flag { name, .. }
+            | Self::RequiredValue { name, .. }
+            | Self::ValueOs { name, .. }
+            | Self::OptionalValue { name, .. }
+            | Self::Count { name, .. }
+            | Self::Values { name, .. } => Some(name),
+        }
+    }
+
+    pub(crate) fn has_value(&self, expected: &str) -> bool {
+        match self {
+            Self::Flag { value, .. } => expected == if *value { "true" } else { "false" },
+            Self::RequiredValue { value, .. }
+            | Self::OptionalValue {
+                value: Some(value), ..
+            } => value == expected,
+            Self::Count { value, .. } => expected == value.to_string(),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct ParseState {
+    pub args: Vec<ParsedArg>,
+    pub current: String,
+    pub pending_option: Option<&'static str>,
+    pub next_positional: usize,
+    pub parse_options: bool,
+    pub(crate) tables: Arc<ParseTables>,
+    // What the current word shows in debug output, when it's a value that gets redacted
+    pub(crate) redacted_current: Option<String>,
+}
+
+impl PartialEq for ParseState {
+    fn eq(&self, other: &Self) -> bool {
+        self.args == other.args
+            && self.current == other.current
+            && self.pending_option == other.pending_option
+            && self.next_positional == other.next_positional
+            && self.parse_options == other.parse_options
+    }
+}
+
+impl fmt::Debug for ParseState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ParseState")
+            .field("args", &self.tables.redact(&self.args))
+            .field(
+                "current",
+                self.redacted_current.as_ref().unwrap_or(&self.current),
+            )
+            .field("pending_option", &self.pending_option)
+            .field("next_positional", &self.next_positional)
+            .field("parse_options", &self.parse_options)
+            .finish()
+    }
+}
+
+struct Consumed {
+    args: Vec<ParsedArg>,
+    pending_option: Option<&'static str>,
+    next_positional: usize,
+    parse_options: bool,
+    ignored_options: Vec<String>,
+    remainder: Vec<String>,
+    terminated: Option<usize>,
+    occurrences: HashMap<&'static str, usize>,
+}
+
+struct ConsumeState<'a> {
+    args: Tokens<'a>,
+    parse_options: bool,
+    parsed_options: HashMap<&'static str, ()>,
+    parsed_args: Vec<ParsedArg>,
+    parsed_positional: usize,
+    pending_option: Option<&'static str>,
+    items: HashMap<&'static str, usize>,
+    ignored_options: Vec<String>,
+    remainder: Vec<String>,
+    cluster: Option<(String, Vec<&'static str>)>,
+    // How many positional args came before the terminator
+    terminated: Option<usize>,
+    // Where the args the current one added start, and the count it bumped, for visitors
+    fresh: usize,
+    bumped: Option<usize>,
+}
+
+enum Step {
+    Next,
+    Stop,
+}
+
+// The args left to consume, along with what the current one took from them or put back in front.
+// Each one keeps the index of the input arg it came from, so `parse_os` can tell which of them
+// weren't valid unicode.
+pub(crate) struct Tokens<'a> {
+    queue: VecDeque<(usize, Cow<'a, str>)>,
+    current: Option<usize>,
+    taken: Vec<(usize, Cow<'a, str>)>,
+    pushed: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(args: &[&'a str]) -> Self {
+        Self {
+            queue: args
+                .iter()
+                .map(|&arg| Cow::Borrowed(arg))
+                .enumerate()
+                .collect(),
+            current: None,
+            taken: vec![],
+            pushed: 0,
+        }
+    }
+
+    fn next(&mut self) -> Option<Cow<'a, str>> {
+        self.taken.clear();
+        self.pushed = 0;
+
+        let (idx, arg) = self.queue.pop_front()?;
+
+        self.current = Some(idx);
+        Some(arg)
+    }
+
+    fn pop_front(&mut self) -> Option<Cow<'a, str>> {
+        let (idx, arg) = self.queue.pop_front()?;
+
+        self.taken.push((idx, arg.clone()));
+        Some(arg)
+    }
+
+    // Whether part of the current arg, e.g. the rest of a cluster, is left for the next step
+    pub(crate) fn pushed_back(&self) -> bool {
+        self.pushed > 0
+    }
+
+    // What is put back comes from the current arg, e.g. the rest of a cluster
+    fn push_front(&mut self, arg: Cow<'a, str>) {
+        self.pushed += 1;
+        self.queue
+            .push_front((self.current.unwrap_or_default(), arg));
+    }
+
+    fn drain(&mut self, range: impl RangeBounds<usize>) -> impl Iterator<Item = Cow<'a, str>> {
+        let drained = self.queue.drain(range).collect::<Vec<_>>();
+
+        self.taken.extend(drained.iter().cloned());
+        drained.into_iter().map(|(_, arg)| arg)
+    }
+
+    fn front(&self) -> Option<&str> {
+        self.queue.front().map(|(_, arg)| &arg[..])
+    }
+
+    fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    fn position(&self, arg: &str) -> Option<usize> {
+        self.queue.iter().position(|(_, s)| s == arg)
+    }
+
+    // The input args the current one and what it took came from
+    pub(crate) fn consumed(&self) -> impl Iterator<Item = usize> + '_ {
+        self.current
+            .into_iter()
+            .chain(self.taken.iter().map(|&(idx, _)| idx))
+    }
+
+    // Puts the args back the way they were before the current one
+    fn restore(&mut self) {
+        self.queue.drain(..self.pushed);
+
+        for arg in self.taken.drain(..).rev() {
+            self.queue.push_front(arg);
+        }
+
+        self.pushed = 0;
+    }
+}
+
+// Option names in the order they first appear
+fn unique_names(args: &[ParsedArg]) -> Vec<&'static str> {
+    let mut names = vec![];
+
+    for name in args.iter().filter_map(ParsedArg::name) {
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+
+    names
 }
 
 #[derive(Debug, PartialEq)]
 pub enum ArgParserError {
-    InvalidOption { name: String },
-    InvalidAlias { alias: String },
-    DuplicateOption { name: &'static str },
-    DuplicateAlias { alias: &'static str },
-    UnknownOption { name: String },
-    UnknownAlias { alias: String },
-    InvalidOptionValue { name: &'static str, value: String },
-    InvalidAliasValue { alias: &'static str, value: String },
-    MissingOptionValue { name: &'static str },
-    MissingAliasValue { alias: &'static str },
+    InvalidOption {
+        name: String,
+    },
+    InvalidAlias {
+        alias: String,
+    },
+    InvalidConfigKey {
+        name: &'static str,
+        key: &'static str,
+    },
+    DuplicateConfigKey {
+        name: &'static str,
+        other: &'static str,
+    },
+    DuplicateOption {
+        name: &'static str,
+    },
+    ReservedOption {
+        name: &'static str,
+    },
+    ReservedAlias {
+        alias: &'static str,
+    },
+    DuplicateAlias {
+        alias: &'static str,
+    },
+    UnknownOption {
+        name: String,
+        suggestion: Option<&'static str>,
+    },
+    AmbiguousOption {
+        given: String,
+        candidates: Vec<&'static str>,
+    },
+    UnknownAlias {
+        alias: String,
+        suggestion: Option<&'static str>,
+    },
+    InvalidCluster {
+        cluster: String,
+        flags: Vec<&'static str>,
+        alias: String,
+        rest: String,
+    },
+    InvalidOptionValue {
+        name: &'static str,
+        value: String,
+    },
+    InvalidAliasValue {
+        alias: &'static str,
+        value: String,
+    },
+    MissingOptionValue {
+        name: &'static str,
+    },
+    MissingAliasValue {
+        alias: &'static str,
+    },
+    MissingAliasEquals {
+        alias: &'static str,
+        value: String,
+    },
+    MissingOptionEquals {
+        name: &'static str,
+        value: String,
+    },
+    AttachedOptionValue {
+        name: &'static str,
+        value: String,
+    },
+    AttachedAliasValue {
+        alias: &'static str,
+        value: String,
+    },
+    MissingOptionValues {
+        name: &'static str,
+        expected: usize,
+        actual: usize,
+    },
+    MissingValueTerminator {
+        name: &'static str,
+        terminator: &'static str,
+    },
+    TooManyOptionValues {
+        name: &'static str,
+        expected: usize,
+        actual: usize,
+    },
+    RejectedValue {
+        name: &'static str,
+        value: String,
+        reason: String,
+    },
+    RejectedItem {
+        name: &'static str,
+        index: usize,
+        value: String,
+        reason: String,
+    },
+    RejectedPositional {
+        position: usize,
+        value: String,
+        reason: String,
+    },
+    UnexpectedArg {
+        value: String,
+    },
+    InvalidEnvValue {
+        name: &'static str,
+        var: String,
+        value: String,
+        reason: String,
+    },
+    MissingRequiredOption {
+        name: &'static str,
+        required: &'static str,
+    },
+    WrongOccurrences {
+        name: &'static str,
+        min: usize,
+        max: usize,
+        actual: usize,
+    },
+    ConflictingOptions {
+        first: &'static str,
+        second: &'static str,
+    },
+    DuplicateGroup {
+        name: &'static str,
+    },
+    GroupConflict {
+        group: ArgGroup,
+        first: &'static str,
+        second: &'static str,
+    },
+    MissingGroupOption {
+        group: ArgGroup,
+    },
     InvalidRestArg,
-    MissingArgs { actual: usize, expected: usize },
+    InvalidOptionalArg,
+    DuplicateKey {
+        name: &'static str,
+        key: String,
+    },
+    NoMatchingSignature {
+        actual: usize,
+    },
+    MissingArgs {
+        actual: usize,
+        expected: usize,
+        missing: Vec<&'static str>,
+    },
+    StaleSchema {
+        expected: String,
+        actual: String,
+    },
+    InvalidResponseFile {
+        path: String,
+        reason: String,
+    },
+    InvalidCommandLine {
+        reason: String,
+    },
+    InvalidUnicode {
+        value: String,
+    },
+    InvalidPrefix {
+        prefix: &'static str,
+    },
+    InvalidOptionSetting {
+        name: &'static str,
+        setting: &'static str,
+        reason: &'static str,
+    },
+    InvalidPositionalSetting {
+        position: usize,
+        setting: &'static str,
+        reason: &'static str,
+    },
+    TooManyArgs {
+        limit: usize,
+    },
+    CommandLineTooLong {
+        length: usize,
+        limit: usize,
+    },
+    ArgumentTooLong {
+        index: usize,
+        length: usize,
+        limit: usize,
+    },
+    InputTooLarge {
+        limit: usize,
+    },
+    ResponseFileTooDeep {
+        path: String,
+        depth: usize,
+    },
+    InvalidConfig {
+        path: String,
+        reason: String,
+    },
+    InvalidConfigValue {
+        name: &'static str,
+        key: &'static str,
+        path: String,
+        reason: String,
+    },
+    UnknownConfigKey {
+        key: String,
+        path: String,
+    },
 }
 
 impl fmt::Display for ArgParserError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Message {
+            error: self,
+            long: "--",
+            short: "-",
+        }
+        .fmt(f)
+    }
+}
+
+// An error worded with the prefixes of the parser that gave it, see `ArgParser::error_message`
+pub(crate) struct Message<'a> {
+    pub(crate) error: &'a ArgParserError,
+    pub(crate) long: &'a str,
+    pub(crate) short: &'a str,
+}
+
+impl fmt::Display for Message<'_> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         use ArgParserError::*;
 
-        match self {
-            InvalidOption { name } => write!(f, "--{} is invalid", name),
-            InvalidAlias { alias } => write!(f, "-{} is invalid", alias),
-            DuplicateOption { name } => write!(f, "cannot provide --{} again", name),
-            DuplicateAlias { alias } => write!(f, "cannot provide -{} again", alias),
-            UnknownOption { name } => write!(f, "--{} is undefined", name),
-            UnknownAlias { alias } => write!(f, "-{} is undefined", alias),
+        let Self { long, short, .. } = *self;
+        let dashes = |alias: &str| match dashes(alias) {
+            "--" => long,
+            _ => short,
+        };
+
+        match self.error {
+            InvalidOption { name } => write!(f, "{long}{} is invalid", name),
+            InvalidAlias { alias } => write!(f, "{}{} is invalid", dashes(alias), alias),
+            InvalidConfigKey { name, key } => {
+                write!(f, "config key '{}' of {long}{} is invalid", key, name)
+            }
+            DuplicateConfigKey { name, other } => write!(
+                f,
+                "config key of {long}{} overlaps with the one of {long}{}",
+                name, other
+            ),
+            DuplicateOption { name } => write!(f, "cannot provide {long}{} again", name),
+            ReservedOption { name } => write!(f, "{long}{} is reserved", name),
+            ReservedAlias { alias } => write!(f, "{}{} is reserved", dashes(alias), alias),
+            DuplicateAlias { alias } => {
+                write!(f, "cannot provide {}{} again", dashes(alias), alias)
+            }
+            UnknownOption {
+                name,
+                suggestion: None,
+            } => write!(f, "{long}{} is undefined", name),
+            UnknownOption {
+                name,
+                suggestion: Some(suggestion),
+            } => write!(
+                f,
+                "{long}{} is undefined, did you mean {long}{}?",
+                name, suggestion
+            ),
+            AmbiguousOption { given, candidates } => write!(
+                f,
+                "{long}{} is ambiguous, it could be {long}{}",
+                given,
+                candidates.join(&format!(", {}", long))
+            ),
+            UnknownAlias {
+                alias,
+                suggestion: None,
+            } => write!(f, "{short}{} is undefined", alias),
+            UnknownAlias {
+                alias,
+                suggestion: Some(suggestion),
+            } => write!(
+                f,
+                "{short}{} is undefined, did you mean {short}{}?",
+                alias, suggestion
+            ),
+            InvalidCluster {
+                cluster,
+                flags,
+                alias,
+                rest,
+            } => write!(
+                f,
+                "{} was read as a cluster of aliases {short}{} {}, but {short}{} is undefined",
+                cluster,
+                flags.join(&format!(" {}", short)),
+                rest,
+                alias
+            ),
             InvalidOptionValue { name, value } => {
-                write!(f, "--{} cannot accept '{}' as a value", name, value)
+                write!(f, "{long}{} cannot accept '{}' as a value", name, value)
             }
             InvalidAliasValue { alias, value } => {
-                write!(f, "-{} cannot accept '{}' as a value", alias, value)
+                write!(f, "{short}{} cannot accept '{}' as a value", alias, value)
+            }
+            MissingOptionValue { name } => write!(f, "{long}{} is missing a value", name),
+            MissingAliasValue { alias } => write!(f, "{short}{} is missing a value", alias),
+            MissingAliasEquals { alias, value } => write!(
+                f,
+                "{short}{} cannot accept '{}' without '=', use {short}{}={}",
+                alias, value, alias, value
+            ),
+            MissingOptionEquals { name, value } => write!(
+                f,
+                "{long}{} cannot accept '{}' without '=', use {long}{}={}",
+                name, value, name, value
+            ),
+            AttachedOptionValue { name, value } => write!(
+                f,
+                "{long}{} takes its value as a separate argument, use {long}{} {}",
+                name, name, value
+            ),
+            AttachedAliasValue { alias, value } => write!(
+                f,
+                "{short}{} takes its value as a separate argument, use {short}{} {}",
+                alias, alias, value
+            ),
+            MissingOptionValues {
+                name,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "{long}{} requires at least {} value(s), but got {}",
+                name, expected, actual
+            ),
+            MissingValueTerminator { name, terminator } => {
+                write!(
+                    f,
+                    "{long}{} is missing its terminating '{}'",
+                    name, terminator
+                )
+            }
+            TooManyOptionValues {
+                name,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "{long}{} takes at most {} value(s), but got {}",
+                name, expected, actual
+            ),
+            RejectedValue {
+                name,
+                value,
+                reason,
+            } => write!(f, "{long}{} cannot accept '{}': {}", name, value, reason),
+            RejectedItem {
+                name,
+                index,
+                value,
+                reason,
+            } => write!(
+                f,
+                "{long}{} item #{} ('{}'): {}",
+                name, index, value, reason
+            ),
+            RejectedPositional {
+                position,
+                value,
+                reason,
+            } => write!(f, "arg #{} cannot accept '{}': {}", position, value, reason),
+            UnexpectedArg { value } => write!(f, "unexpected argument '{}'", value),
+            InvalidEnvValue {
+                name,
+                var,
+                value,
+                reason,
+            } => write!(
+                f,
+                "${} cannot set {long}{} to '{}': {}",
+                var, name, value, reason
+            ),
+            MissingRequiredOption { name, required } => {
+                write!(f, "{long}{} requires {long}{}", name, required)
+            }
+            WrongOccurrences {
+                name,
+                min,
+                max,
+                actual,
+            } => {
+                write!(f, "{long}{} must be given ", name)?;
+
+                match (min, max) {
+                    (min, &usize::MAX) => write!(f, "at least {}", min)?,
+                    (0, max) => write!(f, "at most {}", max)?,
+                    (min, max) if min == max => write!(f, "exactly {}", min)?,
+                    (min, max) => write!(f, "{} to {}", min, max)?,
+                }
+
+                write!(f, " time(s), but was given {}", actual)
             }
-            MissingOptionValue { name } => write!(f, "--{} is missing a value", name),
-            MissingAliasValue { alias } => write!(f, "-{} is missing a value", alias),
+            ConflictingOptions { first, second } => {
+                write!(f, "{long}{} cannot be used with {long}{}", second, first)
+            }
+            DuplicateGroup { name } => write!(f, "cannot define group '{}' again", name),
+            GroupConflict {
+                group,
+                first,
+                second,
+            } => write!(
+                f,
+                "{long}{} cannot be used with {long}{}, only one of {} is allowed",
+                second,
+                first,
+                group.render(long)
+            ),
+            MissingGroupOption { group } => match group.kind {
+                ArgGroupKind::ExactlyOne => write!(f, "one of {} is required", group.render(long)),
+                _ => write!(f, "at least one of {} is required", group.render(long)),
+            },
             InvalidRestArg => write!(f, "'rest' positional arg must be placed last"),
-            MissingArgs { actual, expected } => {
-                write!(f, "{} arg(s) required, but got {}", expected, actual)
+            DuplicateKey { name, key } => {
+                write!(
+                    f,
+                    "{long}{} cannot accept key '{}' more than once",
+                    name, key
+                )
+            }
+            InvalidOptionalArg => {
+                write!(
+                    f,
+                    "'optional' positional args must be placed after named ones"
+                )
+            }
+            NoMatchingSignature { actual } => {
+                write!(f, "no usage accepts the given {} arg(s)", actual)
+            }
+            MissingArgs {
+                actual,
+                expected,
+                missing,
+            } => match missing.len() {
+                0 => write!(f, "{} arg(s) required, but got {}", expected, actual),
+                1 => write!(f, "missing required argument <{}>", missing[0]),
+                _ => write!(f, "missing required arguments <{}>", missing.join(">, <")),
+            },
+            StaleSchema { expected, actual } => write!(
+                f,
+                "completions were generated for schema {}, but the current one is {}",
+                actual, expected
+            ),
+            InvalidResponseFile { path, reason } => write!(f, "cannot read @{}: {}", path, reason),
+            InvalidCommandLine { reason } => write!(f, "invalid command line: {}", reason),
+            InvalidUnicode { value } => write!(f, "'{}' is not valid unicode", value),
+            CommandLineTooLong { length, limit } => write!(
+                f,
+                "the command line would be {} long, but at most {} is allowed",
+                length, limit
+            ),
+            ArgumentTooLong {
+                index,
+                length,
+                limit,
+            } => write!(
+                f,
+                "argument #{} would be {} long, but at most {} is allowed",
+                index, length, limit
+            ),
+            TooManyArgs { limit } => write!(f, "more than {} arguments were given", limit),
+            InputTooLarge { limit } => write!(f, "the arguments exceed {} bytes", limit),
+            InvalidPrefix { prefix } => {
+                write!(f, "'{}' cannot be used as an option prefix", prefix)
             }
+            InvalidOptionSetting {
+                name,
+                setting,
+                reason,
+            } => write!(f, "invalid {} on {long}{}: {}", setting, name, reason),
+            InvalidPositionalSetting {
+                position,
+                setting,
+                reason,
+            } => write!(f, "invalid {} on arg #{}: {}", setting, position, reason),
+            ResponseFileTooDeep { path, depth } => write!(
+                f,
+                "@{} is nested too deeply (depth {}) or includes itself",
+                path, depth
+            ),
+            InvalidConfig { path, reason } => write!(f, "{}: {}", path, reason),
+            InvalidConfigValue {
+                name,
+                key,
+                path,
+                reason,
+            } => write!(
+                f,
+                "{}: cannot set {long}{} from '{}': {}",
+                path, name, key, reason
+            ),
+            UnknownConfigKey { key, path } => write!(f, "{}: '{}' is undefined", path, key),
         }
     }
 }
 
 impl error::Error for ArgParserError {}
 
+pub(crate) fn dashes(alias: &str) -> &'static str {
+    if alias.chars().count() > 1 {
+        "--"
+    } else {
+        "-"
+    }
+}
+
 impl ArgParser {
     pub fn new(mode: ArgParserMode) -> Self {
         Self {
@@ -89,7 +842,149 @@ impl ArgParser {
             aliases: HashMap::new(),
             options: HashMap::new(),
             positional: Vec::new(),
+            mounted: HashMap::new(),
+            unicode_aliases: false,
+            ignored_options: Vec::new(),
+            groups: Vec::new(),
+            value_style: ValueStyle::Both,
+            signatures: Vec::new(),
+            env_prefix: None,
+            reserved: Vec::new(),
+            response_files: false,
+            args_from_stdin: false,
+            split_style: SplitStyle::Posix,
+            slash_options: false,
+            colon_separator: false,
+            plus_flags: false,
+            abbreviations: false,
+            ignore_case: false,
+            allow_unknown: false,
+            strict_positional: false,
+            suggestion_distance: 2,
+            exit_codes: ExitCodes::default(),
+            stop_at_unknown: false,
+            capture_rest: false,
+            terminator: Some("--"),
+            keep_terminator: false,
+            negative_numbers: false,
+            interaction: None,
+            normalize_underscores: false,
+            long_prefix: "--",
+            short_prefix: "-",
+            max_args: None,
+            max_input_bytes: None,
+            max_response_file_depth: 8,
+            tables: TablesCache::default(),
+            #[cfg(feature = "config")]
+            config: None,
+            #[cfg(feature = "config")]
+            deny_unknown_config_keys: false,
+        }
+    }
+
+    pub fn unicode_aliases(&mut self, allow: bool) -> &mut Self {
+        self.unicode_aliases = allow;
+        self
+    }
+
+    // Shorthand for `ValueStyle::EqualsOnly`, or back to `ValueStyle::Both`
+    pub fn require_equals(&mut self, require: bool) -> &mut Self {
+        self.value_style(match require {
+            true => ValueStyle::EqualsOnly,
+            false => ValueStyle::Both,
+        })
+    }
+
+    pub fn value_style(&mut self, style: ValueStyle) -> &mut Self {
+        self.value_style = style;
+        self
+    }
+
+    pub fn option_prefixes(
+        &mut self,
+        long: &'static str,
+        short: &'static str,
+    ) -> Result<&mut Self, ArgParserError> {
+        for prefix in [long, short] {
+            if prefix.is_empty() || prefix.chars().any(|c| c.is_alphanumeric() || c == '=') {
+                return Err(ArgParserError::InvalidPrefix { prefix });
+            }
         }
+
+        self.long_prefix = long;
+        self.short_prefix = short;
+
+        Ok(self)
+    }
+
+    pub fn allow_unknown(&mut self, allow: bool) -> &mut Self {
+        self.allow_unknown = allow;
+        self
+    }
+
+    pub fn strict_positional(&mut self, strict: bool) -> &mut Self {
+        self.strict_positional = strict;
+        self
+    }
+
+    pub fn negative_numbers(&mut self, allow: bool) -> &mut Self {
+        self.negative_numbers = allow;
+        self
+    }
+
+    pub fn terminator(&mut self, terminator: Option<&'static str>) -> &mut Self {
+        self.terminator = terminator;
+        self
+    }
+
+    pub fn keep_terminator(&mut self, keep: bool) -> &mut Self {
+        self.keep_terminator = keep;
+        self
+    }
+
+    pub fn capture_rest(&mut self, capture: bool) -> &mut Self {
+        self.capture_rest = capture;
+        self
+    }
+
+    pub fn stop_at_unknown(&mut self, stop: bool) -> &mut Self {
+        self.stop_at_unknown = stop;
+        self
+    }
+
+    pub fn ignore_case(&mut self, ignore: bool) -> &mut Self {
+        self.ignore_case = ignore;
+        self
+    }
+
+    pub fn normalize_underscores(&mut self, normalize: bool) -> &mut Self {
+        self.normalize_underscores = normalize;
+        self
+    }
+
+    pub fn allow_abbreviations(&mut self, allow: bool) -> &mut Self {
+        self.abbreviations = allow;
+        self
+    }
+
+    pub fn plus_flags(&mut self, allow: bool) -> &mut Self {
+        self.plus_flags = allow;
+        self
+    }
+
+    pub fn slash_options(&mut self, allow: bool) -> &mut Self {
+        self.slash_options = allow;
+        self
+    }
+
+    pub fn colon_separator(&mut self, allow: bool) -> &mut Self {
+        self.colon_separator = allow;
+        self
+    }
+
+    pub fn ignore_options(&mut self, options: &[&'static str]) -> &mut Self {
+        self.ignored_options.extend_from_slice(options);
+        self
     }
 }
 
@@ -103,7 +998,7 @@ impl ArgParser {
     pub fn add_option(&mut self, option: OptionalArg) -> Result<&mut Self, ArgParserError> {
         use ArgParserError::*;
 
-        let OptionalArg { name, alias, .. } = option;
+        let name = option.name;
 
         if !OptionalArg::is_valid(name) {
             return Err(InvalidOption {
@@ -111,43 +1006,163 @@ impl ArgParser {
             });
         }
 
-        if self.options.contains_key(name) {
+        if self.reserved.contains(&name) {
+            return Err(ReservedOption { name });
+        }
+
+        if self.options.contains_key(name) || self.aliases.contains_key(name) {
             return Err(DuplicateOption { name });
         }
 
-        if let Some(alias) = alias {
-            if !OptionalArg::is_valid_alias(alias) {
+        for (idx, &alias) in option.aliases.iter().enumerate() {
+            if !self.is_valid_alias(alias) && !OptionalArg::is_valid(alias) {
                 return Err(InvalidAlias {
                     alias: alias.to_string(),
                 });
             }
 
-            if self.aliases.contains_key(alias) {
+            if self.reserved.contains(&alias) {
+                return Err(ReservedAlias { alias });
+            }
+
+            if self.aliases.contains_key(alias)
+                || self.options.contains_key(alias)
+                || alias == name
+                || option.aliases[..idx].contains(&alias)
+            {
                 return Err(DuplicateAlias { alias });
             }
+        }
 
-            self.aliases.insert(alias, name);
+        if let Some(key) = option.config_key {
+            if !OptionalArg::is_valid_config_key(key) {
+                return Err(InvalidConfigKey { name, key });
+            }
         }
 
-        self.options.insert(name, option);
+        let path = option.config_path();
 
-        Ok(self)
-    }
-}
+        if let Some(other) = self
+            .options
+            .values()
+            .filter(|other| {
+                let other_path = other.config_path();
+                let len = path.len().min(other_path.len());
 
-#[test]
-fn test_add_option() {
-    use ArgParserError::*;
+                path[..len] == other_path[..len]
+            })
+            .min_by_key(|other| other.name)
+        {
+            return Err(DuplicateConfigKey {
+                name,
+                other: other.name,
+            });
+        }
 
-    let mut parser = ArgParser::default();
+        let invalid = |setting, reason| InvalidOptionSetting {
+            name,
+            setting,
+            reason,
+        };
 
-    assert_eq!(
-        Err(InvalidOption {
-            name: "--foo".to_string()
-        }),
-        parser.add_option(OptionalArg::flag("--foo"))
-    );
-    assert_eq!(
+        if option.occurrences.is_empty() {
+            return Err(invalid("min_occurrences", "it is above max_occurrences"));
+        }
+
+        if option.range.as_ref().is_some_and(|range| range.is_empty()) {
+            return Err(invalid("range", "no number is in it"));
+        }
+
+        if option.arity.is_empty() {
+            return Err(invalid("arity", "no number of values is in it"));
+        }
+
+        if *option.arity.end() == 0 {
+            return Err(invalid("arity", "the option takes no value"));
+        }
+
+        if option.alias_help.iter().any(|(alias, _)| alias.is_empty()) {
+            return Err(invalid("alias_help", "no alias() precedes it"));
+        }
+
+        match (&option.kind, option.value_style) {
+            (OptionalArgKind::Flag | OptionalArgKind::Count, Some(_)) => {
+                return Err(invalid("value_style", "the option takes no value"));
+            }
+            (OptionalArgKind::OptionalValue, Some(ValueStyle::SpaceOnly)) => {
+                return Err(invalid(
+                    "value_style",
+                    "optional values can only be attached",
+                ));
+            }
+            _ => {}
+        }
+
+        // Keys are only ever looked at in single values, see `apply_key_policies`
+        if option.key_values.is_some() && option.kind != OptionalArgKind::RequiredValue {
+            return Err(invalid(
+                "key_value",
+                "only required_value() options take KEY=VALUE",
+            ));
+        }
+
+        if option.value_terminator.is_some() && option.kind != OptionalArgKind::Values {
+            return Err(invalid(
+                "value_terminator",
+                "only values() options take several values",
+            ));
+        }
+
+        // Occurrences are only counted for options that can be given more than once
+        if !option.multiple && option.occurrences != (0..=usize::MAX) {
+            return Err(invalid(
+                match option.occurrences.start() {
+                    0 => "max_occurrences",
+                    _ => "min_occurrences",
+                },
+                "the option needs multiple()",
+            ));
+        }
+
+        for value in option.default.iter().chain(
+            option
+                .conditional_defaults
+                .iter()
+                .map(|(_, _, value)| value),
+        ) {
+            option
+                .parse_default(value)
+                .map_err(|reason| RejectedValue {
+                    name,
+                    value: value.to_string(),
+                    reason,
+                })?;
+        }
+
+        for &alias in &option.aliases {
+            self.aliases.insert(alias, name);
+        }
+
+        self.options.insert(name, option);
+        self.tables.clear();
+
+        Ok(self)
+    }
+}
+
+#[test]
+fn test_add_option() {
+    use ArgParserError::*;
+
+    let mut parser = ArgParser::default();
+
+    assert_eq!(
+        Err(InvalidOption {
+            name: "--foo".to_string()
+        }),
+        parser.add_option(OptionalArg::flag("--foo"))
+    );
+    assert_eq!(
         Err(InvalidAlias {
             alias: "?".to_string()
         }),
@@ -167,417 +1182,4944 @@ fn test_add_option() {
 }
 
 impl ArgParser {
-    pub fn add_positional(&mut self, arg: PositionalArg) -> Result<&mut Self, ArgParserError> {
-        if self.positional.last()
-            == Some(&PositionalArg {
-                kind: PositionalArgKind::Rest,
-            })
-        {
-            return Err(ArgParserError::InvalidRestArg);
-        }
-
-        self.positional.push(arg);
+    // Options may refer to ones added after them, so the references are only checked once the
+    // schema is complete: here, or at the latest when parsing
+    pub fn build(&mut self) -> Result<&mut Self, ArgParserError> {
+        self.check_references()?;
 
         Ok(self)
     }
-}
-
-#[test]
-fn test_add_positional() {
-    let mut parser = ArgParser::default();
-
-    assert!(parser.add_positional(PositionalArg::named()).is_ok());
-    assert!(parser.add_positional(PositionalArg::rest()).is_ok());
-    assert_eq!(
-        Err(ArgParserError::InvalidRestArg),
-        parser.add_positional(PositionalArg::named())
-    );
-    assert_eq!(
-        Err(ArgParserError::InvalidRestArg),
-        parser.add_positional(PositionalArg::rest())
-    );
-}
-
-impl ArgParser {
-    pub fn parse_args(&self) -> Result<Vec<ParsedArg>, ArgParserError> {
-        let args = env::args().skip(1).collect::<Vec<_>>();
-        let str_args = args.iter().map(|s| &s[..]).collect::<Vec<_>>();
-
-        self.parse(&str_args)
-    }
-
-    pub fn parse(&self, args: &[&str]) -> Result<Vec<ParsedArg>, ArgParserError> {
-        use ArgParserError::*;
-        use ParsedArg::*;
-
-        let mut args = VecDeque::from_iter(args.iter().map(|s| s.to_string()));
-        let mut parse_options = true;
-        let mut parsed_options = HashMap::new();
-        let mut parsed_args = vec![];
-
-        while let Some(arg) = args.pop_front() {
-            if arg == "--" && parse_options {
-                parse_options = false;
-                continue;
-            }
-
-            if parse_options {
-                if let Some((name_or_alias, value)) = self.parse_option(&arg)? {
-                    let (name, option, alias) = self.resolve(name_or_alias)?;
-
-                    let value = if alias.is_some() {
-                        if let Some(value) = value.strip_prefix('=') {
-                            value
-                        } else if matches!(option.kind, OptionalArgKind::Flag)
-                            && !value.is_empty()
-                            && !value.starts_with('-')
-                        {
-                            args.push_front(format!("-{}", value));
-
-                            ""
-                        } else {
-                            value
-                        }
-                    } else {
-                        value
-                    };
-
-                    match option.kind {
-                        OptionalArgKind::Flag => {
-                            if !matches!(value, "" | "true" | "false") {
-                                return Err(if let Some(alias) = alias {
-                                    InvalidAliasValue {
-                                        alias,
-                                        value: value.to_string(),
-                                    }
-                                } else {
-                                    InvalidOptionValue {
-                                        name,
-                                        value: value.to_string(),
-                                    }
-                                });
-                            }
-
-                            parsed_args.push(Flag {
-                                name,
-                                value: matches!(value, "" | "true"),
-                            });
-                        }
-                        OptionalArgKind::RequiredValue => {
-                            let value = if value.is_empty() {
-                                args.pop_front()
-                                    .and_then(|s| {
-                                        if let Ok(Some(_)) = self.parse_option(&s) {
-                                            None
-                                        } else {
-                                            Some(s)
-                                        }
-                                    })
-                                    .ok_or(if let Some(alias) = alias {
-                                        MissingAliasValue { alias }
-                                    } else {
-                                        MissingOptionValue { name }
-                                    })?
-                            } else {
-                                value.to_string()
-                            };
-
-                            parsed_args.push(RequiredValue { name, value });
-                        }
-                        OptionalArgKind::OptionalValue => {
-                            let value = if value.is_empty() {
-                                None
-                            } else {
-                                Some(value.to_string())
-                            };
 
-                            parsed_args.push(OptionalValue { name, value });
-                        }
-                    };
+    fn check_references(&self) -> Result<(), ArgParserError> {
+        let mut names = self.options.keys().copied().collect::<Vec<_>>();
 
-                    if !option.multiple {
-                        if parsed_options.contains_key(name) {
-                            return Err(if let Some(alias) = alias {
-                                DuplicateAlias { alias }
-                            } else {
-                                DuplicateOption { name }
-                            });
-                        }
+        names.sort_unstable();
 
-                        parsed_options.insert(name, ());
-                    }
+        for name in names {
+            let option = &self.options[name];
+            let references = [
+                ("requires", &option.requires),
+                ("conflicts_with", &option.conflicts),
+            ];
 
-                    continue;
+            for (setting, others) in references {
+                if others.iter().any(|other| !self.options.contains_key(other)) {
+                    return Err(ArgParserError::InvalidOptionSetting {
+                        name,
+                        setting,
+                        reason: "it names an unknown option",
+                    });
                 }
             }
-
-            parsed_args.push(Positional {
-                value: arg.to_string(),
-            });
-
-            if matches!(self.mode, ArgParserMode::OptionsFirst) {
-                parse_options = false;
-            }
-        }
-
-        let parsed_positional = parsed_args
-            .iter()
-            .filter(|arg| matches!(arg, ParsedArg::Positional { value: _ }))
-            .count();
-
-        let min_expected_positional = self
-            .positional
-            .iter()
-            .filter(|arg| arg.kind == PositionalArgKind::Named)
-            .count();
-
-        if parsed_positional < min_expected_positional {
-            return Err(MissingArgs {
-                actual: parsed_positional,
-                expected: min_expected_positional,
-            });
         }
 
-        Ok(parsed_args)
+        Ok(())
     }
 
-    fn parse_option<'a>(&self, arg: &'a str) -> Result<Option<(&'a str, &'a str)>, ArgParserError> {
+    pub fn reserve_names(&mut self, names: &[&'static str]) -> Result<&mut Self, ArgParserError> {
         use ArgParserError::*;
 
-        if let Some(name) = arg.strip_prefix("--") {
-            let (name, value) = name.split_once('=').unwrap_or((name, ""));
-
-            if !OptionalArg::is_valid(name) {
-                return Err(InvalidOption {
-                    name: name.to_string(),
-                });
+        for &name in names {
+            if let Some(&name) = self.options.get_key_value(name).map(|(name, _)| name) {
+                return Err(ReservedOption { name });
             }
 
-            return Ok(Some((name, value)));
+            if let Some((&alias, _)) = self.aliases.get_key_value(name) {
+                return Err(ReservedAlias { alias });
+            }
         }
 
-        if let Some(alias) = arg.strip_prefix('-') {
-            let (alias, value) = if alias.is_char_boundary(1) {
-                alias.split_at(1)
-            } else {
-                (alias, "")
-            };
+        self.reserved.extend_from_slice(names);
 
-            if !OptionalArg::is_valid_alias(alias) {
-                return Err(InvalidAlias {
-                    alias: alias.to_string(),
-                });
-            }
+        Ok(self)
+    }
+}
 
-            return Ok(Some((alias, value)));
-        }
+impl ArgParser {
+    pub fn with_option_default(
+        &mut self,
+        name: &str,
+        value: &'static str,
+    ) -> Result<&mut Self, ArgParserError> {
+        let name = self.aliases.get(name).copied().unwrap_or(name);
 
-        Ok(None)
+        let option = self
+            .options
+            .get_mut(name)
+            .ok_or(ArgParserError::UnknownOption {
+                name: name.to_string(),
+                suggestion: None,
+            })?;
+
+        option
+            .parse_default(value)
+            .map_err(|reason| ArgParserError::RejectedValue {
+                name: option.name,
+                value: value.to_string(),
+                reason,
+            })?;
+        option.default = Some(value);
+
+        Ok(self)
     }
 
-    fn resolve(
-        &self,
-        name_or_alias: &str,
-    ) -> Result<(&'static str, &OptionalArg, Option<&'static str>), ArgParserError> {
-        use ArgParserError::*;
+    // Values of multi-value options are checked one at a time, as they would be entered
+    pub fn validate_value(&self, name: &str, value: &str) -> Result<(), ArgParserError> {
+        let name = self.aliases.get(name).copied().unwrap_or(name);
 
-        let (name, alias) = if OptionalArg::is_valid_alias(name_or_alias) {
-            let (&alias, &name) =
-                self.aliases
-                    .get_key_value(name_or_alias)
-                    .ok_or(UnknownAlias {
-                        alias: name_or_alias.to_string(),
-                    })?;
+        let (&name, option) =
+            self.options
+                .get_key_value(name)
+                .ok_or_else(|| ArgParserError::UnknownOption {
+                    name: name.to_string(),
+                    suggestion: self.suggest_option(name),
+                })?;
 
-            (name, Some(alias))
-        } else {
-            (name_or_alias, None)
+        let checked = match option.kind {
+            OptionalArgKind::Values => option.check_value(value),
+            _ => option.parse_value(value).map(|_| ()),
         };
 
-        let (name, option) = self.options.get_key_value(name).ok_or(UnknownOption {
-            name: name.to_string(),
-        })?;
-
-        Ok((name, option, alias))
+        checked.map_err(|reason| {
+            self.redact_error(ArgParserError::RejectedValue {
+                name,
+                value: value.to_string(),
+                reason,
+            })
+        })
     }
 }
 
 #[test]
-fn test_parse() -> Result<(), ArgParserError> {
-    use ArgParserError::*;
+fn test_parse_stdio_dash() -> Result<(), ArgParserError> {
     use ParsedArg::*;
 
     let mut parser = ArgParser::default();
 
     parser
-        .add_option(OptionalArg::flag("foo").alias("f"))?
-        .add_option(OptionalArg::flag("bar").multiple().alias("b"))?
-        .add_option(OptionalArg::required_value("baz").multiple().alias("B"))?
-        .add_option(OptionalArg::optional_value("qux").multiple().alias("q"))?;
+        .add_option(OptionalArg::required_value("output").alias("o"))?
+        .add_positional(PositionalArg::rest())?;
 
     assert_eq!(
         Ok(vec![
             Positional {
-                value: "foo".to_string()
+                value: "-".to_string()
+            },
+            RequiredValue {
+                name: "output",
+                value: "-".to_string()
             },
             Positional {
-                value: "bar".to_string()
-            }
+                value: "-".to_string()
+            },
         ]),
-        parser.parse(&["foo", "bar"])
+        parser.parse(&["-", "-o", "-", "--", "-"]).map(Vec::from)
     );
     assert_eq!(
-        Err(InvalidOption {
-            name: "-foo".to_string()
-        }),
-        parser.parse(&["---foo"])
+        Ok(vec![Positional {
+            value: "+".to_string()
+        }]),
+        parser
+            .option_prefixes("--", "+")?
+            .parse(&["+"])
+            .map(Vec::from)
     );
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_negative_numbers() -> Result<(), ArgParserError> {
+    use ParsedArg::*;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::required_value("offset"))?
+        .add_option(OptionalArg::values("scale", 2..=2))?
+        .add_option(OptionalArg::count("context").alias("3"))?
+        .add_positional(PositionalArg::rest())?;
+
     assert_eq!(
-        Err(UnknownOption {
-            name: "Foo".to_string()
+        Err(ArgParserError::UnknownAlias {
+            alias: "5".to_string(),
+            suggestion: None
         }),
-        parser.parse(&["--Foo"])
-    );
-    assert_eq!(
-        Err(DuplicateOption { name: "foo" }),
-        parser.parse(&["--foo", "--foo"])
+        parser.parse(&["-5"]).map(|_| ())
     );
+
+    parser.negative_numbers(true);
+
     assert_eq!(
         Ok(vec![
-            Flag {
-                name: "foo",
-                value: true
+            RequiredValue {
+                name: "offset",
+                value: "-5".to_string()
             },
-            Positional {
-                value: "--".to_string()
+            Values {
+                name: "scale",
+                values: vec!["-0.25".to_string(), "-.5".to_string()]
             },
             Positional {
-                value: "--foo".to_string()
-            }
+                value: "-1e3".to_string()
+            },
+            Count {
+                name: "context",
+                value: 1
+            },
         ]),
-        parser.parse(&["--foo", "--", "--", "--foo"])
+        parser
+            .parse(&["--offset", "-5", "--scale", "-0.25", "-.5", "-1e3", "-3"])
+            .map(Vec::from)
     );
     assert_eq!(
-        Err(InvalidOptionValue {
-            name: "bar",
-            value: "no".to_string()
+        Err(ArgParserError::UnknownAlias {
+            alias: "i".to_string(),
+            suggestion: None
         }),
-        parser.parse(&["--bar=no"])
+        parser.parse(&["-inf"]).map(|_| ())
     );
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_allow_hyphen_values() -> Result<(), ArgParserError> {
+    use ParsedArg::*;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::required_value("offset").allow_hyphen_values())?
+        .add_option(OptionalArg::required_value("pattern").alias("e"))?
+        .add_option(OptionalArg::values("range", 2..=2).allow_hyphen_values())?
+        .add_option(OptionalArg::flag("foo").alias("f"))?;
+
     assert_eq!(
-        Err(InvalidAliasValue {
-            alias: "b",
-            value: "no".to_string()
-        }),
-        parser.parse(&["-b=no"])
+        Ok(vec![
+            RequiredValue {
+                name: "offset",
+                value: "-5".to_string()
+            },
+            RequiredValue {
+                name: "pattern",
+                value: "-foo".to_string()
+            },
+            Values {
+                name: "range",
+                values: vec!["-f".to_string(), "--foo".to_string()]
+            },
+        ]),
+        parser
+            .parse(&["--offset", "-5", "-e=-foo", "--range", "-f", "--foo"])
+            .map(Vec::from)
     );
     assert_eq!(
-        Err(UnknownAlias {
-            alias: "a".to_string()
-        }),
-        parser.parse(&["-a"])
+        Err(ArgParserError::MissingAliasValue { alias: "e" }),
+        parser.parse(&["-e", "-f"]).map(|_| ())
     );
     assert_eq!(
-        Err(DuplicateAlias { alias: "f" }),
-        parser.parse(&["-f", "-f"])
+        Err(ArgParserError::MissingOptionValues {
+            name: "range",
+            expected: 2,
+            actual: 1
+        }),
+        parser.parse(&["--range", "-1", "--"]).map(|_| ())
     );
-    assert_eq!(
-        Ok(vec![
-            Flag {
-                name: "bar",
-                value: true
-            },
-            Flag {
-                name: "bar",
-                value: false
+
+    Ok(())
+}
+
+#[test]
+fn test_validate_value() -> Result<(), ArgParserError> {
+    use ArgParserError::*;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(
+            OptionalArg::required_value("port")
+                .alias("p")
+                .range(1..=65535),
+        )?
+        .add_option(OptionalArg::values("size", 2..=2).range(0..=100))?
+        .add_option(OptionalArg::flag("verbose"))?;
+
+    assert_eq!(Ok(()), parser.validate_value("port", "80"));
+    assert_eq!(Ok(()), parser.validate_value("p", "443"));
+    assert_eq!(
+        parser.parse(&["--port=80a0"]).map(|_| ()),
+        parser.validate_value("port", "80a0")
+    );
+    assert_eq!(Ok(()), parser.validate_value("size", "50"));
+    assert!(parser.validate_value("size", "500").is_err());
+    assert_eq!(Ok(()), parser.validate_value("verbose", "false"));
+    assert_eq!(
+        Err(RejectedValue {
+            name: "verbose",
+            value: "yes".to_string(),
+            reason: "expected 'true' or 'false'".to_string()
+        }),
+        parser.validate_value("verbose", "yes")
+    );
+    assert_eq!(
+        Err(UnknownOption {
+            name: "user".to_string(),
+            suggestion: None
+        }),
+        parser.validate_value("user", "root")
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_with_option_default() -> Result<(), ArgParserError> {
+    use super::ArgSelector;
+
+    let mut base = ArgParser::default();
+
+    base.add_option(
+        OptionalArg::required_value("port")
+            .alias("p")
+            .range(1..=65535)
+            .default_value("80"),
+    )?;
+
+    let mut parser = base.clone();
+
+    parser.with_option_default("p", "1234")?;
+
+    assert_ne!(base, parser);
+    assert_eq!(
+        Some(&"80".to_string()),
+        ArgSelector::from(&base.parse(&[])?).get_value("port")
+    );
+    assert_eq!(
+        Some(&"1234".to_string()),
+        ArgSelector::from(&parser.parse(&[])?).get_value("port")
+    );
+    assert_eq!(
+        Err(ArgParserError::UnknownOption {
+            name: "host".to_string(),
+            suggestion: None
+        }),
+        parser.with_option_default("host", "localhost").map(|_| ())
+    );
+    assert_eq!(
+        Err(ArgParserError::RejectedValue {
+            name: "port",
+            value: "0".to_string(),
+            reason: "must be in range 1..=65535".to_string()
+        }),
+        parser.with_option_default("port", "0").map(|_| ())
+    );
+    assert_eq!(
+        Some(&"1234".to_string()),
+        ArgSelector::from(&parser.parse(&[])?).get_value("port")
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_reserve_names() {
+    use ArgParserError::*;
+
+    let mut parser = ArgParser::default();
+
+    assert!(parser
+        .add_option(OptionalArg::flag("verbose").alias("v"))
+        .is_ok());
+    assert_eq!(
+        Err(ReservedAlias { alias: "v" }),
+        parser.reserve_names(&["v"]).map(|_| ())
+    );
+    assert!(parser.reserve_names(&["help", "h", "version"]).is_ok());
+    assert_eq!(
+        Err(ReservedOption { name: "help" }),
+        parser.add_option(OptionalArg::flag("help")).map(|_| ())
+    );
+    assert_eq!(
+        Err(ReservedAlias { alias: "h" }),
+        parser
+            .add_option(OptionalArg::flag("host").alias("h"))
+            .map(|_| ())
+    );
+    assert_eq!(
+        "--version is reserved",
+        parser
+            .add_option(OptionalArg::required_value("debug").alias("version"))
+            .map(|_| ())
+            .unwrap_err()
+            .to_string()
+    );
+    assert_eq!(
+        Err(ReservedOption { name: "verbose" }),
+        parser.reserve_names(&["verbose"]).map(|_| ())
+    );
+}
+
+#[test]
+fn test_add_option_config_key() {
+    use ArgParserError::*;
+
+    let mut parser = ArgParser::default();
+
+    assert_eq!(
+        Err(InvalidConfigKey {
+            name: "db-url",
+            key: "database..url"
+        }),
+        parser.add_option(OptionalArg::required_value("db-url").config_key("database..url"))
+    );
+    assert!(parser
+        .add_option(OptionalArg::required_value("db-url").config_key("database.url"))
+        .is_ok());
+    assert!(parser
+        .add_option(OptionalArg::required_value("db-pool").config_key("database.pool.size"))
+        .is_ok());
+    assert_eq!(
+        Err(DuplicateConfigKey {
+            name: "database",
+            other: "db-pool"
+        }),
+        parser.add_option(OptionalArg::flag("database"))
+    );
+    assert_eq!(
+        Err(DuplicateConfigKey {
+            name: "pool",
+            other: "db-pool"
+        }),
+        parser.add_option(OptionalArg::required_value("pool").config_key("database.pool"))
+    );
+    assert!(parser
+        .add_option(OptionalArg::flag("url").config_key("url"))
+        .is_ok());
+}
+
+impl ArgParser {
+    pub fn add_group(&mut self, group: ArgGroup) -> Result<&mut Self, ArgParserError> {
+        use ArgParserError::*;
+
+        if let Some(name) = group
+            .options
+            .iter()
+            .find(|name| !self.options.contains_key(*name))
+        {
+            return Err(UnknownOption {
+                name: name.to_string(),
+                suggestion: self.suggest_option(name),
+            });
+        }
+
+        if self.groups.iter().any(|other| other.name == group.name) {
+            return Err(DuplicateGroup { name: group.name });
+        }
+
+        self.groups.push(group);
+        self.tables.clear();
+
+        Ok(self)
+    }
+}
+
+#[test]
+fn test_add_group() -> Result<(), ArgParserError> {
+    use ArgParserError::*;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::flag("json"))?
+        .add_option(OptionalArg::flag("yaml"))?;
+
+    assert_eq!(
+        Err(UnknownOption {
+            name: "text".to_string(),
+            suggestion: None
+        }),
+        parser
+            .add_group(ArgGroup::at_most_one("format", &["json", "text"]))
+            .map(|_| ())
+    );
+    assert!(parser
+        .add_group(ArgGroup::at_most_one("format", &["json", "yaml"]))
+        .is_ok());
+    assert_eq!(
+        Err(DuplicateGroup { name: "format" }),
+        parser
+            .add_group(ArgGroup::exactly_one("format", &["json"]))
+            .map(|_| ())
+    );
+
+    Ok(())
+}
+
+impl ArgParser {
+    pub fn add_positional(&mut self, arg: PositionalArg) -> Result<&mut Self, ArgParserError> {
+        if matches!(
+            self.positional.last(),
+            Some(PositionalArg {
+                kind: PositionalArgKind::Rest,
+                ..
+            })
+        ) {
+            return Err(ArgParserError::InvalidRestArg);
+        }
+
+        if arg.kind == PositionalArgKind::Named
+            && self
+                .positional
+                .iter()
+                .any(|arg| arg.kind == PositionalArgKind::Optional)
+        {
+            return Err(ArgParserError::InvalidOptionalArg);
+        }
+
+        arg.check_settings(self.positional.len())?;
+        self.positional.push(arg);
+        self.tables.clear();
+
+        Ok(self)
+    }
+}
+
+#[test]
+fn test_add_option_aliases() -> Result<(), ArgParserError> {
+    use ArgParserError::*;
+
+    let mut parser = ArgParser::default();
+
+    parser.add_option(OptionalArg::flag("color").alias("colour").alias("c"))?;
+
+    assert_eq!(
+        Err(DuplicateOption { name: "colour" }),
+        parser.add_option(OptionalArg::flag("colour")).map(|_| ())
+    );
+    assert_eq!(
+        Err(DuplicateAlias { alias: "color" }),
+        parser
+            .add_option(OptionalArg::flag("foo").alias("color"))
+            .map(|_| ())
+    );
+    assert_eq!(
+        Err(DuplicateAlias { alias: "bar" }),
+        parser
+            .add_option(OptionalArg::flag("foo").alias("bar").alias("bar"))
+            .map(|_| ())
+    );
+    assert_eq!(
+        Err(InvalidAlias {
+            alias: "-bar".to_string()
+        }),
+        parser
+            .add_option(OptionalArg::flag("foo").alias("-bar"))
+            .map(|_| ())
+    );
+    assert_eq!(
+        "cannot provide --colour again",
+        DuplicateAlias { alias: "colour" }.to_string()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_defaulted_positional() -> Result<(), ArgParserError> {
+    use super::ArgSelector;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::flag("verbose"))?
+        .add_positional(PositionalArg::named())?
+        .add_positional(PositionalArg::optional().default_value("a.out"))?
+        .add_positional(PositionalArg::optional().default_value("-"))?
+        .add_positional(PositionalArg::optional())?
+        .add_positional(PositionalArg::optional().default_value("unreachable"))?;
+
+    let args = parser.parse(&["main.c", "--verbose"])?;
+
+    assert_eq!(
+        vec!["main.c", "a.out", "-"],
+        ArgSelector::from(&args).get_positional()
+    );
+    assert_eq!(&[1, 2], args.defaulted_positional());
+
+    let args = parser.parse(&["main.c", "main"])?;
+
+    assert_eq!(
+        vec!["main.c", "main", "-"],
+        ArgSelector::from(&args).get_positional()
+    );
+    assert_eq!(&[2], args.defaulted_positional());
+
+    let args = Resolver::new(&parser)
+        .without_defaults()
+        .resolve(&["main.c"])?;
+
+    assert_eq!(vec!["main.c"], ArgSelector::from(&args).get_positional());
+    assert!(args.defaulted_positional().is_empty());
+
+    // Only the named positional arg is required
+    assert_eq!(
+        Err(ArgParserError::MissingArgs {
+            actual: 0,
+            expected: 1,
+            missing: vec![]
+        }),
+        parser.parse(&["--verbose"])
+    );
+
+    let mut parser = ArgParser::default();
+
+    assert_eq!(
+        Err(ArgParserError::RejectedPositional {
+            position: 1,
+            value: "fast".to_string(),
+            reason: "must be one of debug, release".to_string()
+        }),
+        parser
+            .add_positional(
+                PositionalArg::optional()
+                    .choices(&["debug", "release"])
+                    .default_value("fast"),
+            )
+            .map(|_| ())
+    );
+
+    parser.add_positional(
+        PositionalArg::optional()
+            .must_exist()
+            .default_value("missing.toml"),
+    )?;
+
+    assert_eq!(
+        Err(ArgParserError::RejectedPositional {
+            position: 1,
+            value: "missing.toml".to_string(),
+            reason: "no such file or directory".to_string()
+        }),
+        parser.parse(&[])
+    );
+
+    // With signatures, the matching one's defaults fill in what's missing
+    let mut parser = ArgParser::default();
+
+    parser.add_signature(vec![
+        PositionalArg::named().choices(&["build"]),
+        PositionalArg::optional().default_value("release"),
+    ])?;
+
+    assert_eq!(
+        Err(ArgParserError::RejectedPositional {
+            position: 2,
+            value: "y".to_string(),
+            reason: "must be one of x".to_string()
+        }),
+        parser
+            .add_signature(vec![
+                PositionalArg::named(),
+                PositionalArg::optional().choices(&["x"]).default_value("y"),
+            ])
+            .map(|_| ())
+    );
+
+    let args = parser.parse(&["build"])?;
+
+    assert_eq!(
+        vec!["build", "release"],
+        ArgSelector::from(&args).get_positional()
+    );
+    assert_eq!(&[1], args.defaulted_positional());
+    assert_eq!(Some(0), args.signature());
+    assert_eq!(
+        Err(ArgParserError::NoMatchingSignature { actual: 1 }),
+        parser.parse(&["run"])
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_add_positional() {
+    let mut parser = ArgParser::default();
+
+    assert!(parser.add_positional(PositionalArg::named()).is_ok());
+    assert!(parser.add_positional(PositionalArg::rest()).is_ok());
+    assert_eq!(
+        Err(ArgParserError::InvalidRestArg),
+        parser.add_positional(PositionalArg::named())
+    );
+    assert_eq!(
+        Err(ArgParserError::InvalidRestArg),
+        parser.add_positional(PositionalArg::rest())
+    );
+
+    let mut parser = ArgParser::default();
+
+    assert!(parser.add_positional(PositionalArg::optional()).is_ok());
+    assert_eq!(
+        Err(ArgParserError::InvalidOptionalArg),
+        parser.add_positional(PositionalArg::named())
+    );
+    assert!(parser.add_positional(PositionalArg::optional()).is_ok());
+    assert!(parser.add_positional(PositionalArg::rest()).is_ok());
+}
+
+impl ArgParser {
+    pub fn parse_args(&self) -> Result<ParsedArgs, ArgParserError> {
+        let args = env::args().skip(1).collect::<Vec<_>>();
+        let str_args = args.iter().map(|s| &s[..]).collect::<Vec<_>>();
+
+        self.parse(&str_args)
+    }
+
+    pub fn parse(&self, args: &[&str]) -> Result<ParsedArgs, ArgParserError> {
+        Resolver::new(self).resolve(args)
+    }
+
+    // Errors leave the parser redacted, whether they end parsing here or go to the visitor
+    pub(crate) fn parse_with(
+        &self,
+        args: &[&str],
+        resolver: &Resolver,
+        visitor: Option<&mut (dyn ArgVisitor + '_)>,
+    ) -> Result<ParsedArgs, ArgParserError> {
+        self.parse_unredacted(args, resolver, visitor)
+            .map_err(|error| self.redact_error(error))
+    }
+
+    // A visitor is told about errors and parsing goes on, otherwise the first one ends it
+    fn recover(
+        &self,
+        visitor: &mut Option<&mut (dyn ArgVisitor + '_)>,
+        error: ArgParserError,
+    ) -> Result<(), ArgParserError> {
+        match visitor {
+            Some(visitor) => {
+                visitor.on_error(self.redact_error(error));
+                Ok(())
+            }
+            None => Err(error),
+        }
+    }
+
+    fn parse_unredacted(
+        &self,
+        args: &[&str],
+        resolver: &Resolver,
+        mut visitor: Option<&mut (dyn ArgVisitor + '_)>,
+    ) -> Result<ParsedArgs, ArgParserError> {
+        let expanded = self.expand_response_files(args, &mut io::stdin())?;
+        let expanded = expanded
+            .as_ref()
+            .map(|expanded| expanded.iter().map(String::as_str).collect::<Vec<_>>());
+        let (given, args) = (args, expanded.as_deref().unwrap_or(args));
+
+        // Args in a response file have no original to go back to
+        let os_args = match resolver.os_args {
+            Some(os_args) if args[..] != *given => {
+                if let Some(arg) = os_args.iter().find(|arg| arg.to_str().is_none()) {
+                    return Err(ArgParserError::InvalidUnicode {
+                        value: arg.to_string_lossy().into_owned(),
+                    });
+                }
+
+                None
+            }
+            os_args => os_args,
+        };
+
+        self.check_references()?;
+
+        let mut state = self.consume_with(args, os_args, false, visitor.as_deref_mut())?;
+
+        if let Err(error) = self.apply_key_policies(&mut state.args) {
+            self.recover(&mut visitor, error)?;
+        }
+
+        let consumed = state.args.len();
+
+        let eager = self.find_eager_option(&state);
+        let check_constraints = eager.is_none();
+
+        if let Some(error) = self
+            .missing_args(state.next_positional)
+            .filter(|_| check_constraints)
+        {
+            self.recover(&mut visitor, error)?;
+        }
+
+        let mut meta = ParseMeta {
+            options: state.args.iter().filter(|arg| arg.name().is_some()).count(),
+            positional: state.next_positional,
+            bytes: args.iter().map(|arg| arg.len()).sum(),
+            rest_policy: self
+                .positional
+                .last()
+                .filter(|arg| arg.kind == PositionalArgKind::Rest)
+                .map_or(RestPolicy::Lazy, |arg| arg.rest_policy),
+            eager,
+            ..Default::default()
+        };
+        let mut sources = HashMap::new();
+
+        // Only the command line counts, values from the other layers are added below
+        let mut unused_options = Vec::with_capacity(self.options.len());
+
+        unused_options.extend(
+            self.options
+                .keys()
+                .filter(|&&name| !state.args.iter().any(|arg| arg.name() == Some(name))),
+        );
+
+        unused_options.sort_unstable();
+
+        if let Some(env) = &resolver.env {
+            if let Err(error) = self.apply_env(&mut state.args, &mut sources, env.as_ref()) {
+                self.recover(&mut visitor, error)?;
+            }
+        }
+
+        #[cfg(feature = "config")]
+        let unknown_config_keys = match resolver.config {
+            true => self
+                .apply_config(&mut state.args, &mut sources)
+                .or_else(|error| self.recover(&mut visitor, error).map(|_| vec![]))?,
+            false => vec![],
+        };
+
+        meta.env = sources
+            .values()
+            .any(|source| matches!(source, ValueSource::Env(_)));
+        #[cfg(feature = "config")]
+        {
+            meta.config = sources
+                .values()
+                .any(|source| matches!(source, ValueSource::Config(_)));
+        }
+
+        let mut defaulted_positional = vec![];
+        let mut defaulted_signature = None;
+
+        if resolver.defaults {
+            self.apply_defaults(&mut state.args, &mut sources);
+
+            // With signatures the defaults come from the one that matches
+            let defaults = match self.signature_defaults(&state.args) {
+                Some((signature, defaults)) => {
+                    defaulted_signature = Some(signature);
+                    defaults
+                }
+                None if self.signatures.is_empty() => {
+                    PositionalArg::defaults(&self.positional, state.next_positional)
+                }
+                None => vec![],
+            };
+
+            // Everything but `must_exist` was checked when the default was set, while the file
+            // may be gone by now
+            for (idx, value) in defaults {
+                if defaulted_signature.is_none() && self.positional[idx].must_exist {
+                    if let Err(reason) = self.positional[idx].check_value(value) {
+                        self.recover(
+                            &mut visitor,
+                            ArgParserError::RejectedPositional {
+                                position: idx + 1,
+                                value: value.to_string(),
+                                reason,
+                            },
+                        )?;
+                        break;
+                    }
+                }
+
+                state.args.push(ParsedArg::Positional {
+                    value: value.to_string(),
+                });
+                defaulted_positional.push(idx);
+            }
+        }
+
+        // Whatever one of the other layers sets counts as a single occurrence
+        for &name in sources.keys() {
+            state.occurrences.entry(name).or_insert(1);
+        }
+
+        // Values from the other layers come after the command line's
+        if let Some(visitor) = visitor.as_deref_mut() {
+            for arg in &state.args[consumed..] {
+                visit(visitor, arg);
+            }
+        }
+
+        let signature = if check_constraints {
+            for error in
+                self.relation_errors(&state.args, &state.args[..consumed], &state.occurrences)
+            {
+                self.recover(&mut visitor, error)?;
+            }
+
+            match defaulted_signature {
+                Some(signature) => Some(signature),
+                None => self
+                    .match_signature(&state.args, resolver.defaults)
+                    .or_else(|error| self.recover(&mut visitor, error).map(|_| None))?,
+            }
+        } else {
+            None
+        };
+
+        Ok(ParsedArgs {
+            args: state.args,
+            unused_options,
+            ignored_options: state.ignored_options,
+            signature,
+            sources,
+            #[cfg(feature = "config")]
+            unknown_config_keys,
+            #[cfg(feature = "config")]
+            config_file: self
+                .config
+                .as_ref()
+                .filter(|_| resolver.config)
+                .map(|config| config.path.clone()),
+            tables: self.tables(),
+            meta,
+            raw_args: RawArgs::new(given),
+            remainder: state.remainder,
+            defaulted_positional,
+        })
+    }
+
+    // Arguments that can't be taken where they are are skipped, giving back any values they took,
+    // and every constraint checked once everything is in is reported. Only a response file or
+    // input budget that can't be read or is exceeded leaves nothing to go on.
+    pub fn parse_lenient(&self, args: &[&str]) -> (ParsedArgs, Vec<ArgParserError>) {
+        let report = self.parse_report(args);
+
+        (report.args, report.errors)
+    }
+
+    pub fn parse_partial(&self, args: &[&str]) -> Result<ParseState, ArgParserError> {
+        let tables = self.tables();
+        // The current word is redacted the same way as the whole command line would be
+        let redacted_current = if tables.redactors.is_empty() {
+            None
+        } else {
+            self.redact_args(args)
+                .pop()
+                .filter(|redacted| Some(redacted.as_str()) != args.last().copied())
+        };
+        let (current, args) = args.split_last().unwrap_or((&"", &[]));
+
+        let state = self
+            .consume(args, true)
+            .map_err(|error| self.redact_error(error))?;
+
+        Ok(ParseState {
+            args: state.args,
+            current: current.to_string(),
+            pending_option: state.pending_option,
+            next_positional: state.next_positional,
+            parse_options: state.parse_options,
+            tables,
+            redacted_current,
+        })
+    }
+
+    // What the current word of a partial command line is expected to be, for completions
+    pub fn value_hint(&self, state: &ParseState) -> Option<ValueHint> {
+        if let Some(name) = state.pending_option {
+            return self.options.get(name)?.hint;
+        }
+
+        if state.parse_options && self.is_option_like(&state.current) {
+            return None;
+        }
+
+        self.positional_at(state.next_positional)?.hint
+    }
+
+    fn consume(&self, args: &[&str], partial: bool) -> Result<Consumed, ArgParserError> {
+        self.consume_with(args, None, partial, None)
+    }
+
+    // A visitor hears about every arg once it has been taken. With one, an arg that can't be taken
+    // is skipped along with whatever it did so far, and the args it took as values are given back.
+    fn consume_with(
+        &self,
+        args: &[&str],
+        os_args: Option<&[&OsStr]>,
+        partial: bool,
+        mut visitor: Option<&mut (dyn ArgVisitor + '_)>,
+    ) -> Result<Consumed, ArgParserError> {
+        let arena = Arena::default();
+        let mut state = ConsumeState {
+            args: Tokens::new(args),
+            parse_options: true,
+            parsed_options: HashMap::new(),
+            parsed_args: vec![],
+            parsed_positional: 0,
+            pending_option: None,
+            items: HashMap::new(),
+            ignored_options: vec![],
+            remainder: vec![],
+            cluster: None,
+            terminated: None,
+            fresh: 0,
+            bumped: None,
+        };
+        let mut occurrences = HashMap::new();
+
+        while let Some(arg) = state.args.next() {
+            let in_cluster = state.cluster.take();
+            let (parsed, positional) = (state.parsed_args.len(), state.parsed_positional);
+
+            state.fresh = parsed;
+            state.bumped = None;
+
+            let step = self
+                .consume_one(&mut state, arg, in_cluster, partial, &arena)
+                .and_then(|step| match os_args {
+                    Some(os_args) => self
+                        .restore_os(
+                            &mut state.parsed_args[state.fresh..],
+                            state.bumped.is_some(),
+                            os_args,
+                            &state.args,
+                        )
+                        .map(|_| step),
+                    None => Ok(step),
+                });
+
+            if step.is_ok() {
+                let bumped = state.bumped.map(|idx| &state.parsed_args[idx]);
+                let fresh = bumped.into_iter().chain(&state.parsed_args[state.fresh..]);
+
+                // A single token such as `--tag=a,b` can give several items, but it is still
+                // given once
+                let mut previous = None;
+
+                for name in fresh.clone().filter_map(ParsedArg::name) {
+                    if previous != Some(name) {
+                        *occurrences.entry(name).or_insert(0) += 1;
+                    }
+
+                    previous = Some(name);
+                }
+
+                if let Some(visitor) = visitor.as_deref_mut() {
+                    for arg in fresh {
+                        visit(visitor, arg);
+                    }
+                }
+            }
+
+            match step {
+                Ok(Step::Next) => {}
+                Ok(Step::Stop) => break,
+                Err(error) => {
+                    self.recover(&mut visitor, error)?;
+
+                    state.parsed_args.truncate(parsed);
+                    state.parsed_positional = positional;
+                    state.args.restore();
+                    state.cluster = None;
+                }
+            }
+        }
+
+        Ok(Consumed {
+            args: state.parsed_args,
+            pending_option: state.pending_option,
+            next_positional: state.parsed_positional,
+            parse_options: state.parse_options,
+            ignored_options: state.ignored_options,
+            remainder: state.remainder,
+            terminated: state.terminated,
+            occurrences,
+        })
+    }
+
+    fn consume_one<'a>(
+        &self,
+        state: &mut ConsumeState<'a>,
+        arg: Cow<'a, str>,
+        in_cluster: Option<(String, Vec<&'static str>)>,
+        partial: bool,
+        arena: &'a Arena,
+    ) -> Result<Step, ArgParserError> {
+        use ArgParserError::*;
+        use ParsedArg::*;
+
+        let ConsumeState {
+            args,
+            parse_options,
+            parsed_options,
+            parsed_args,
+            parsed_positional,
+            pending_option,
+            items,
+            ignored_options,
+            remainder,
+            cluster,
+            terminated,
+            fresh,
+            bumped,
+        } = state;
+
+        if *parse_options && self.terminator == Some(&arg) {
+            *parse_options = false;
+            *terminated = Some(*parsed_positional);
+
+            if self.capture_rest {
+                let terminator = self.keep_terminator.then_some(arg);
+
+                parsed_args.push(Rest {
+                    values: terminator
+                        .into_iter()
+                        .chain(args.drain(..))
+                        .map(Cow::into_owned)
+                        .collect(),
+                });
+                return Ok(Step::Next);
+            }
+
+            // A kept terminator is handled like any other positional argument
+            if !self.keep_terminator {
+                return Ok(Step::Next);
+            }
+        }
+
+        let translated = if *parse_options {
+            let arg = self.translate_slash_option(arg, arena);
+            let arg = self.translate_colon_separator(arg, arena);
+
+            self.translate_loose_option(arg, arena)?
+        } else {
+            arg
+        };
+        let arg = &translated[..];
+
+        if *parse_options {
+            if let Some(aliases) = self.parse_plus_flags(arg) {
+                for alias in aliases.into_iter().rev() {
+                    args.push_front(
+                        arena.alloc_fmt(format_args!("{}{}=false", self.short_prefix, alias)),
+                    );
+                }
+
+                return Ok(Step::Next);
+            }
+
+            let (key, _) = arg.split_once('=').unwrap_or((arg, ""));
+
+            if self.ignored_options.contains(&key) {
+                ignored_options.push(arg.to_string());
+                return Ok(Step::Next);
+            }
+
+            if let Some((name_or_alias, value)) = self
+                .parse_option(arg)
+                .map_err(|error| Self::cluster_error(error, arg, &in_cluster))?
+            {
+                let name_or_alias = self.expand_abbreviation(name_or_alias)?;
+                let (name_or_alias, value) = match self.resolve_negated(name_or_alias) {
+                    Some(name) if value.is_empty() => (name, "false"),
+                    Some(name) => {
+                        return Err(InvalidOptionValue {
+                            name,
+                            value: value.to_string(),
+                        })
+                    }
+                    None => (name_or_alias, value),
+                };
+
+                let resolved = self.resolve(name_or_alias);
+                // A cluster such as `-vx` with an undefined alias in it is unknown as a whole
+                let unknown = match &resolved {
+                    Err(UnknownOption { .. } | UnknownAlias { .. }) => true,
+                    Ok((_, option, Some(_))) => {
+                        in_cluster.is_none() && self.clusters_unknown_alias(option, value)
+                    }
+                    _ => false,
+                };
+
+                let (name, option, alias) = match resolved {
+                    _ if unknown && self.stop_at_unknown => {
+                        *remainder = [arg.to_string()]
+                            .into_iter()
+                            .chain(args.drain(..).map(Cow::into_owned))
+                            .collect();
+                        return Ok(Step::Stop);
+                    }
+                    _ if unknown && self.allow_unknown => {
+                        parsed_args.push(Unknown {
+                            raw: arg.to_string(),
+                        });
+                        return Ok(Step::Next);
+                    }
+                    resolved => {
+                        resolved.map_err(|error| Self::cluster_error(error, arg, &in_cluster))?
+                    }
+                };
+
+                let unattached = alias.is_some() && !value.is_empty() && !value.starts_with('=');
+
+                let value = if alias.is_some() {
+                    if let Some(value) = value.strip_prefix('=') {
+                        value
+                    } else if matches!(option.kind, OptionalArgKind::Flag | OptionalArgKind::Count)
+                        && !value.is_empty()
+                        && !value.starts_with(self.short_prefix)
+                    {
+                        args.push_front(
+                            arena.alloc_fmt(format_args!("{}{}", self.short_prefix, value)),
+                        );
+
+                        *cluster = Some(match in_cluster {
+                            Some((cluster, mut flags)) => {
+                                flags.extend(alias);
+                                (cluster, flags)
+                            }
+                            None => (arg.to_string(), alias.into_iter().collect()),
+                        });
+
+                        ""
+                    } else {
+                        value
+                    }
+                } else {
+                    value
+                };
+
+                // Flags and counts take no value, and an optional one can only ever be attached
+                let style = match (&option.kind, self.value_style_of(option)) {
+                    (OptionalArgKind::Flag | OptionalArgKind::Count, _)
+                    | (OptionalArgKind::OptionalValue, ValueStyle::SpaceOnly) => ValueStyle::Both,
+                    (_, style) => style,
+                };
+                // Equals-only options never take their first value from the next arg, which is
+                // only an error when there has to be one
+                let separate = match option.kind {
+                    OptionalArgKind::RequiredValue => value.is_empty(),
+                    OptionalArgKind::Values => value.is_empty() && *option.arity.start() > 0,
+                    _ => false,
+                };
+
+                match style {
+                    ValueStyle::EqualsOnly if unattached => {
+                        return Err(if let Some(alias) = alias {
+                            MissingAliasEquals {
+                                alias,
+                                value: value.to_string(),
+                            }
+                        } else {
+                            MissingOptionEquals {
+                                name,
+                                value: value.to_string(),
+                            }
+                        });
+                    }
+                    ValueStyle::EqualsOnly if separate && args.front().is_some() => {
+                        let value = args.front().unwrap_or_default().to_string();
+
+                        return Err(if let Some(alias) = alias {
+                            MissingAliasEquals { alias, value }
+                        } else {
+                            MissingOptionEquals { name, value }
+                        });
+                    }
+                    ValueStyle::SpaceOnly if !value.is_empty() => {
+                        let value = value.to_string();
+
+                        return Err(if let Some(alias) = alias {
+                            AttachedAliasValue { alias, value }
+                        } else {
+                            AttachedOptionValue { name, value }
+                        });
+                    }
+                    _ => {}
+                }
+
+                match option.kind {
+                    OptionalArgKind::Flag => {
+                        if !matches!(value, "" | "true" | "false") {
+                            return Err(if let Some(alias) = alias {
+                                InvalidAliasValue {
+                                    alias,
+                                    value: value.to_string(),
+                                }
+                            } else {
+                                InvalidOptionValue {
+                                    name,
+                                    value: value.to_string(),
+                                }
+                            });
+                        }
+
+                        parsed_args.push(Flag {
+                            name,
+                            value: matches!(value, "" | "true"),
+                        });
+                    }
+                    OptionalArgKind::Count => {
+                        if !value.is_empty() {
+                            return Err(if let Some(alias) = alias {
+                                InvalidAliasValue {
+                                    alias,
+                                    value: value.to_string(),
+                                }
+                            } else {
+                                InvalidOptionValue {
+                                    name,
+                                    value: value.to_string(),
+                                }
+                            });
+                        }
+
+                        if let Some(idx) = parsed_args.iter().position(
+                            |arg| matches!(arg, Count { name: _name, .. } if *_name == name),
+                        ) {
+                            if let Count { value, .. } = &mut parsed_args[idx] {
+                                *value += 1;
+                            }
+
+                            *bumped = Some(idx);
+                        } else {
+                            parsed_args.push(Count { name, value: 1 });
+                        }
+                    }
+                    OptionalArgKind::RequiredValue => {
+                        if partial && value.is_empty() && args.is_empty() {
+                            *pending_option = Some(name);
+                            return Ok(Step::Stop);
+                        }
+
+                        let value = if value.is_empty() {
+                            args.pop_front()
+                                .and_then(|s| {
+                                    if let (false, Ok(Some(_))) =
+                                        (option.allow_hyphen_values, self.parse_option(&s))
+                                    {
+                                        None
+                                    } else {
+                                        Some(s)
+                                    }
+                                })
+                                .ok_or(if let Some(alias) = alias {
+                                    MissingAliasValue { alias }
+                                } else {
+                                    MissingOptionValue { name }
+                                })?
+                        } else {
+                            Cow::Borrowed(value)
+                        };
+
+                        for value in option.split_value(&value) {
+                            Self::validate(name, option, value, items)?;
+
+                            parsed_args.push(RequiredValue {
+                                name,
+                                value: value.to_string(),
+                            });
+                        }
+                    }
+                    OptionalArgKind::Values => {
+                        let mut values = vec![];
+
+                        if !value.is_empty() {
+                            values.push(value.to_string());
+                        }
+
+                        if let Some(terminator) = option.value_terminator {
+                            match args.position(terminator) {
+                                Some(end) => {
+                                    values.extend(args.drain(..end).map(String::from));
+                                    args.pop_front();
+                                }
+                                None if partial => {
+                                    *pending_option = Some(name);
+                                    return Ok(Step::Stop);
+                                }
+                                None => return Err(MissingValueTerminator { name, terminator }),
+                            }
+
+                            if values.len() > *option.arity.end() {
+                                return Err(TooManyOptionValues {
+                                    name,
+                                    expected: *option.arity.end(),
+                                    actual: values.len(),
+                                });
+                            }
+                        }
+
+                        while values.len() < *option.arity.end()
+                            && option.value_terminator.is_none()
+                            && !(style == ValueStyle::EqualsOnly && values.is_empty())
+                        {
+                            match args.front() {
+                                Some(s)
+                                    if self.terminator != Some(s)
+                                        && (option.allow_hyphen_values
+                                            || !matches!(self.parse_option(s), Ok(Some(_)))) =>
+                                {
+                                    values.extend(args.pop_front().map(String::from));
+                                }
+                                _ => break,
+                            }
+                        }
+
+                        if values.len() < *option.arity.start() {
+                            if partial && args.is_empty() {
+                                *pending_option = Some(name);
+                                return Ok(Step::Stop);
+                            }
+
+                            return Err(MissingOptionValues {
+                                name,
+                                expected: *option.arity.start(),
+                                actual: values.len(),
+                            });
+                        }
+
+                        for value in &values {
+                            Self::validate(name, option, value, items)?;
+                        }
+
+                        parsed_args.push(Values { name, values });
+                    }
+                    OptionalArgKind::OptionalValue => {
+                        let value = if value.is_empty() {
+                            option.default_missing.map(|s| s.to_string())
+                        } else {
+                            Self::validate(name, option, value, items)?;
+
+                            Some(value.to_string())
+                        };
+
+                        parsed_args.push(OptionalValue { name, value });
+                    }
+                };
+
+                if !option.multiple && option.kind != OptionalArgKind::Count {
+                    if parsed_options.contains_key(name) {
+                        match option.duplicates {
+                            DuplicateOptions::Error => {
+                                return Err(if let Some(alias) = alias {
+                                    DuplicateAlias { alias }
+                                } else {
+                                    DuplicateOption { name }
+                                })
+                            }
+                            DuplicateOptions::FirstWins => {
+                                parsed_args.pop();
+                            }
+                            DuplicateOptions::LastWins => {
+                                if let Some(idx) =
+                                    parsed_args.iter().position(|arg| arg.name() == Some(name))
+                                {
+                                    parsed_args.remove(idx);
+                                    *fresh -= 1;
+                                }
+                            }
+                        }
+                    }
+
+                    parsed_options.insert(name, ());
+                }
+
+                return Ok(Step::Next);
+            }
+        }
+
+        *parsed_positional += 1;
+
+        // Signatures already tell which counts are fine
+        if self.strict_positional
+            && self.signatures.is_empty()
+            && self.positional_at(*parsed_positional - 1).is_none()
+        {
+            return Err(UnexpectedArg {
+                value: arg.to_string(),
+            });
+        }
+
+        if let Some(positional) = self.positional_at(*parsed_positional - 1) {
+            positional
+                .check_value(arg)
+                .map_err(|reason| RejectedPositional {
+                    position: *parsed_positional,
+                    value: arg.to_string(),
+                    reason,
+                })?;
+        }
+
+        parsed_args.push(Positional {
+            value: arg.to_string(),
+        });
+
+        if matches!(self.mode, ArgParserMode::OptionsFirst) {
+            *parse_options = false;
+        }
+
+        // A greedy rest arg takes everything as soon as the positional args before it have their
+        // values, while with a lazy one they are satisfied first and options are parsed throughout
+        if let Some(PositionalArg {
+            kind: PositionalArgKind::Rest,
+            rest_policy: RestPolicy::Greedy,
+            ..
+        }) = self
+            .positional
+            .get(*parsed_positional)
+            .or(self.positional.get(*parsed_positional - 1))
+        {
+            *parse_options = false;
+        }
+
+        Ok(Step::Next)
+    }
+
+    fn apply_defaults(
+        &self,
+        args: &mut Vec<ParsedArg>,
+        sources: &mut HashMap<&'static str, ValueSource>,
+    ) {
+        let mut names = self.options.keys().copied().collect::<Vec<_>>();
+
+        names.sort_unstable();
+
+        // Static defaults go first, so that conditions can depend on them whatever the names
+        for conditional in [false, true] {
+            for &name in &names {
+                let option = &self.options[name];
+
+                if option.conditional_defaults.is_empty() == conditional
+                    || args.iter().any(|arg| arg.name() == Some(name))
+                {
+                    continue;
+                }
+
+                let default = option
+                    .conditional_defaults
+                    .iter()
+                    .find(|&&(other, other_value, _)| {
+                        args.iter().any(|arg| {
+                            arg.name() == Some(other)
+                                && other_value.is_none_or(|v| arg.has_value(v))
+                        })
+                    })
+                    .map(|&(_, _, default)| default)
+                    .or(option.default);
+
+                // Already checked when the default was set
+                if let Some(Ok(parsed)) = default.map(|default| option.parse_default(default)) {
+                    sources.insert(name, ValueSource::Default);
+                    args.extend(parsed);
+                }
+            }
+        }
+    }
+
+    // Besides the consumed options, the positional and remaining args are searched, since with
+    // `OptionsFirst` or `stop_at_unknown` a subcommand's `--help` ends up there. Option values and
+    // whatever comes after a terminator never count.
+    fn find_eager_option(&self, state: &Consumed) -> Option<&'static str> {
+        let consumed = state
+            .args
+            .iter()
+            .filter_map(ParsedArg::name)
+            .find(|name| self.options.get(name).is_some_and(|option| option.eager));
+
+        consumed.or_else(|| {
+            state
+                .args
+                .iter()
+                .filter_map(|arg| match arg {
+                    ParsedArg::Positional { value } => Some(value.as_str()),
+                    _ => None,
+                })
+                .take(state.terminated.unwrap_or(usize::MAX))
+                .chain(state.remainder.iter().map(String::as_str))
+                .take_while(|&arg| self.terminator != Some(arg))
+                .find_map(|arg| {
+                    let (name_or_alias, _) = self.parse_option(arg).ok()??;
+                    let (name, option, _) = self.resolve(name_or_alias).ok()?;
+
+                    option.eager.then_some(name)
+                })
+        })
+    }
+
+    fn missing_args(&self, given: usize) -> Option<ArgParserError> {
+        let named = self
+            .positional
+            .iter()
+            .filter(|arg| arg.kind == PositionalArgKind::Named)
+            .count();
+
+        // Positional args are filled in order, so the rest arg only gets values once every
+        // optional one before it has been given
+        let min_rest = match self.positional.last() {
+            Some(arg) if arg.kind == PositionalArgKind::Rest && arg.min > 0 => {
+                Some((self.positional.len() - 1 + arg.min, arg.name))
+            }
+            _ => None,
+        };
+
+        let expected = min_rest.map_or(named, |(min, _)| min);
+
+        if given >= expected {
+            return None;
+        }
+
+        // Naming only some of the missing args would read as if the others were given, so
+        // without every name the counts are reported instead
+        let missing = self
+            .positional
+            .iter()
+            .filter(|arg| arg.kind == PositionalArgKind::Named)
+            .skip(given)
+            .map(|arg| arg.name)
+            .chain(min_rest.map(|(_, name)| name))
+            .collect::<Option<Vec<_>>>();
+
+        Some(ArgParserError::MissingArgs {
+            actual: given,
+            expected,
+            missing: missing.unwrap_or_default(),
+        })
+    }
+
+    // All of these are independent, so unlike the others they can be reported at once
+    fn relation_errors(
+        &self,
+        args: &[ParsedArg],
+        command_line: &[ParsedArg],
+        occurrences: &HashMap<&'static str, usize>,
+    ) -> Vec<ArgParserError> {
+        let mut errors = vec![];
+        let names = unique_names(args);
+        // Values from the other layers can't be dropped by the user, so they never conflict
+        let given = unique_names(command_line);
+
+        for (idx, &second) in given.iter().enumerate() {
+            if let Some(&first) = given[..idx].iter().find(|&&first| {
+                self.options[first].conflicts.contains(&second)
+                    || self.options[second].conflicts.contains(&first)
+            }) {
+                errors.push(ArgParserError::ConflictingOptions { first, second });
+            }
+        }
+
+        // Only what the user passed needs anything, but any layer can provide what's needed
+        for &name in &given {
+            if let Some(&required) = self.options[name]
+                .requires
+                .iter()
+                .find(|required| !names.contains(required))
+            {
+                errors.push(ArgParserError::MissingRequiredOption { name, required });
+            }
+        }
+
+        let mut multiple = self
+            .options
+            .values()
+            .filter(|option| option.multiple)
+            .collect::<Vec<_>>();
+
+        multiple.sort_unstable_by_key(|option| option.name);
+
+        for option in multiple {
+            let actual = occurrences.get(option.name).copied().unwrap_or(0);
+
+            if !option.occurrences.contains(&actual) {
+                errors.push(ArgParserError::WrongOccurrences {
+                    name: option.name,
+                    min: *option.occurrences.start(),
+                    max: *option.occurrences.end(),
+                    actual,
+                });
+            }
+        }
+
+        for group in &self.groups {
+            let present = given
+                .iter()
+                .filter(|name| group.options.contains(name))
+                .collect::<Vec<_>>();
+
+            match (&group.kind, &present[..]) {
+                (ArgGroupKind::AtMostOne | ArgGroupKind::ExactlyOne, [&first, &second, ..]) => {
+                    errors.push(ArgParserError::GroupConflict {
+                        group: group.clone(),
+                        first,
+                        second,
+                    });
+                }
+                (ArgGroupKind::ExactlyOne | ArgGroupKind::AtLeastOne, []) => {
+                    errors.push(ArgParserError::MissingGroupOption {
+                        group: group.clone(),
+                    });
+                }
+                _ => {}
+            };
+        }
+
+        errors
+    }
+
+    fn translate_slash_option<'a>(&self, arg: Cow<'a, str>, arena: &'a Arena) -> Cow<'a, str> {
+        let Some(rest) = arg.strip_prefix('/').filter(|_| self.slash_options) else {
+            return arg;
+        };
+
+        let (name, value) = rest.split_once(':').unwrap_or((rest, ""));
+
+        // Anything else, e.g. an absolute path, is left alone
+        if !self.options.contains_key(name) && !self.aliases.contains_key(name) {
+            return arg;
+        }
+
+        let prefix = if self.is_valid_alias(name) {
+            self.short_prefix
+        } else {
+            self.long_prefix
+        };
+
+        if value.is_empty() && !rest.contains(':') {
+            arena.alloc_fmt(format_args!("{}{}", prefix, name))
+        } else {
+            arena.alloc_fmt(format_args!("{}{}={}", prefix, name, value))
+        }
+    }
+
+    // Only a defined name may precede the `:`, so values like `--out=c:\dir` are left alone
+    fn translate_colon_separator<'a>(&self, arg: Cow<'a, str>, arena: &'a Arena) -> Cow<'a, str> {
+        if !self.colon_separator {
+            return arg;
+        }
+
+        let Some((prefix, rest)) = [self.long_prefix, self.short_prefix]
+            .into_iter()
+            .find_map(|prefix| Some((prefix, arg.strip_prefix(prefix)?)))
+        else {
+            return arg;
+        };
+
+        match rest.split_once(':') {
+            Some((name, value))
+                if self.options.contains_key(name)
+                    || self.aliases.contains_key(name)
+                    || self.resolve_negated(name).is_some() =>
+            {
+                arena.alloc_fmt(format_args!("{}{}={}", prefix, name, value))
+            }
+            _ => arg,
+        }
+    }
+
+    // Only long options are matched loosely, `-v` and `-V` are usually different things
+    fn translate_loose_option<'a>(
+        &self,
+        arg: Cow<'a, str>,
+        arena: &'a Arena,
+    ) -> Result<Cow<'a, str>, ArgParserError> {
+        if !self.ignore_case && !self.normalize_underscores {
+            return Ok(arg);
+        }
+
+        let Some(rest) = arg
+            .strip_prefix(self.long_prefix)
+            .filter(|rest| self.long_prefix != self.short_prefix || self.is_long_option(rest))
+        else {
+            return Ok(arg);
+        };
+
+        let (name, value) = rest.split_at(rest.find('=').unwrap_or(rest.len()));
+
+        if self.options.contains_key(name)
+            || self.aliases.contains_key(name)
+            || self.resolve_negated(name).is_some()
+        {
+            return Ok(arg);
+        }
+
+        let fold = |name: &str| {
+            let name = match self.normalize_underscores {
+                true => name.replace('_', "-"),
+                false => name.to_string(),
+            };
+
+            match self.ignore_case {
+                true => name.to_lowercase(),
+                false => name,
+            }
+        };
+
+        let given = fold(name);
+
+        let mut spellings = self
+            .options
+            .keys()
+            .chain(
+                self.aliases
+                    .keys()
+                    .filter(|alias| !self.is_valid_alias(alias)),
+            )
+            .copied()
+            .collect::<Vec<_>>();
+
+        spellings.sort_unstable();
+
+        let found = spellings
+            .iter()
+            .filter_map(|&spelling| {
+                if fold(spelling) == given {
+                    return Some((spelling, false));
+                }
+
+                given
+                    .strip_prefix("no-")
+                    .filter(|&negated| fold(spelling) == negated)
+                    .and_then(|_| self.resolve_negated(&format!("no-{}", spelling)))
+                    .map(|_| (spelling, true))
+            })
+            .collect::<Vec<_>>();
+
+        // Spellings that fold the same are only fine when they are the same option
+        let mut names = found
+            .iter()
+            .map(|&(spelling, negated)| {
+                let name = self.aliases.get(spelling).copied().unwrap_or(spelling);
+
+                (name, negated)
+            })
+            .collect::<Vec<_>>();
+
+        names.sort_unstable();
+        names.dedup();
+
+        match (&found[..], names.len()) {
+            ([], _) => Ok(arg),
+            ([(spelling, negated), ..], 1) => Ok(arena.alloc_fmt(format_args!(
+                "{}{}{}{}",
+                self.long_prefix,
+                if *negated { "no-" } else { "" },
+                spelling,
+                value
+            ))),
+            _ => Err(ArgParserError::AmbiguousOption {
+                given: name.to_string(),
+                candidates: found
+                    .iter()
+                    .map(|&(spelling, negated)| match negated {
+                        true => intern(&format!("no-{}", spelling)),
+                        false => spelling,
+                    })
+                    .collect(),
+            }),
+        }
+    }
+
+    pub(crate) fn parse_option<'a>(
+        &self,
+        arg: &'a str,
+    ) -> Result<Option<(&'a str, &'a str)>, ArgParserError> {
+        use ArgParserError::*;
+
+        // A lone `-` usually stands for stdin or stdout, and a lone `--` that doesn't terminate the
+        // options (see `terminator`) is just as ordinary
+        if arg == self.short_prefix
+            || arg == self.long_prefix
+            || (self.negative_numbers && self.is_negative_number(arg))
+        {
+            return Ok(None);
+        }
+
+        if let Some(name) = arg
+            .strip_prefix(self.long_prefix)
+            .filter(|name| self.long_prefix != self.short_prefix || self.is_long_option(name))
+        {
+            let (name, value) = name.split_once('=').unwrap_or((name, ""));
+
+            if !OptionalArg::is_valid(name) {
+                return Err(InvalidOption {
+                    name: name.to_string(),
+                });
+            }
+
+            return Ok(Some((name, value)));
+        }
+
+        if let Some(alias) = arg.strip_prefix(self.short_prefix) {
+            let (alias, value) = alias.split_at(alias.chars().next().map_or(0, char::len_utf8));
+
+            if !self.is_valid_alias(alias) {
+                return Err(InvalidAlias {
+                    alias: alias.to_string(),
+                });
+            }
+
+            return Ok(Some((alias, value)));
+        }
+
+        Ok(None)
+    }
+
+    // Follows the aliases clustered after a flag the way they are consumed, until one takes a value
+    fn clusters_unknown_alias<'a>(&'a self, mut option: &'a OptionalArg, mut rest: &str) -> bool {
+        while matches!(option.kind, OptionalArgKind::Flag | OptionalArgKind::Count)
+            && !rest.is_empty()
+            && !rest.starts_with('=')
+            && !rest.starts_with(self.short_prefix)
+        {
+            let (alias, tail) = rest.split_at(rest.chars().next().map_or(0, char::len_utf8));
+
+            if !self.is_valid_alias(alias) {
+                return false;
+            }
+
+            match self.resolve(alias) {
+                Ok((_, next, _)) => (option, rest) = (next, tail),
+                Err(error) => return matches!(error, ArgParserError::UnknownAlias { .. }),
+            }
+        }
+
+        false
+    }
+
+    // Aliases split off a cluster like `-fbx=1` are reported along with the flags before them
+    fn cluster_error(
+        error: ArgParserError,
+        arg: &str,
+        cluster: &Option<(String, Vec<&'static str>)>,
+    ) -> ArgParserError {
+        match (error, cluster) {
+            (
+                ArgParserError::UnknownAlias { alias, .. } | ArgParserError::InvalidAlias { alias },
+                Some((cluster, flags)),
+            ) => ArgParserError::InvalidCluster {
+                cluster: cluster.clone(),
+                flags: flags.clone(),
+                alias,
+                rest: arg.to_string(),
+            },
+            (error, _) => error,
+        }
+    }
+
+    fn validate(
+        name: &'static str,
+        option: &OptionalArg,
+        value: &str,
+        items: &mut HashMap<&'static str, usize>,
+    ) -> Result<(), ArgParserError> {
+        let index = items.entry(name).and_modify(|n| *n += 1).or_insert(1);
+
+        option.check_value(value).map_err(|reason| {
+            if option.multiple {
+                ArgParserError::RejectedItem {
+                    name,
+                    index: *index,
+                    value: value.to_string(),
+                    reason,
+                }
+            } else {
+                ArgParserError::RejectedValue {
+                    name,
+                    value: value.to_string(),
+                    reason,
+                }
+            }
+        })
+    }
+
+    pub(crate) fn is_valid_alias(&self, alias: &str) -> bool {
+        if self.unicode_aliases {
+            OptionalArg::is_valid_unicode_alias(alias)
+        } else {
+            OptionalArg::is_valid_alias(alias)
+        }
+    }
+
+    // An option's own style takes precedence over the parser's
+    pub(crate) fn value_style_of(&self, option: &OptionalArg) -> ValueStyle {
+        option.value_style.unwrap_or(self.value_style)
+    }
+
+    fn positional_at(&self, idx: usize) -> Option<&PositionalArg> {
+        self.positional.get(idx).or_else(|| {
+            self.positional
+                .last()
+                .filter(|arg| arg.kind == PositionalArgKind::Rest)
+        })
+    }
+
+    // `+xy` is only taken for disabled flags if every alias in it allows that, anything else is
+    // left for positional arguments
+    fn parse_plus_flags<'a>(&self, arg: &'a str) -> Option<Vec<&'a str>> {
+        let aliases = arg.strip_prefix('+').filter(|_| self.short_prefix != "+")?;
+
+        let aliases = aliases
+            .char_indices()
+            .map(|(idx, c)| &aliases[idx..idx + c.len_utf8()])
+            .collect::<Vec<_>>();
+
+        let all_flags = aliases.iter().all(|&alias| {
+            self.is_valid_alias(alias)
+                && self
+                    .aliases
+                    .get(alias)
+                    .and_then(|name| self.options.get(name))
+                    .is_some_and(|option| {
+                        option.kind == OptionalArgKind::Flag
+                            && (option.plus_negatable || self.plus_flags)
+                    })
+        });
+
+        (!aliases.is_empty() && all_flags).then_some(aliases)
+    }
+
+    // Digits that are defined as aliases keep working, so `-5` can still be e.g. `head -5`
+    fn is_negative_number(&self, arg: &str) -> bool {
+        let Some(number) = arg.strip_prefix(self.short_prefix) else {
+            return false;
+        };
+
+        number.starts_with(|c: char| c.is_ascii_digit() || c == '.')
+            && number.parse::<f64>().is_ok()
+            && !self.aliases.contains_key(&number[..1])
+    }
+
+    pub(crate) fn is_option_like(&self, arg: &str) -> bool {
+        arg.starts_with(self.long_prefix) || arg.starts_with(self.short_prefix)
+    }
+
+    // With a shared prefix, e.g. `-name` in find-like tools, anything that isn't a defined short
+    // alias is taken for a long option
+    fn is_long_option(&self, arg: &str) -> bool {
+        let (name, _) = arg.split_once('=').unwrap_or((arg, ""));
+        let (alias, _) = name.split_at(name.chars().next().map_or(0, char::len_utf8));
+
+        self.options.contains_key(name)
+            || self.resolve_negated(name).is_some()
+            || (!self.is_valid_alias(name) && self.aliases.contains_key(name))
+            || !self.aliases.contains_key(alias)
+    }
+
+    fn expand_abbreviation<'a>(&self, given: &'a str) -> Result<&'a str, ArgParserError> {
+        if !self.abbreviations
+            || self.is_valid_alias(given)
+            || self.options.contains_key(given)
+            || self.aliases.contains_key(given)
+            || self.resolve_negated(given).is_some()
+        {
+            return Ok(given);
+        }
+
+        let mut candidates = self
+            .options
+            .keys()
+            .chain(
+                self.aliases
+                    .keys()
+                    .filter(|alias| !self.is_valid_alias(alias)),
+            )
+            .filter(|spelling| spelling.starts_with(given))
+            .copied()
+            .collect::<Vec<_>>();
+
+        candidates.sort_unstable();
+
+        let mut names = candidates
+            .iter()
+            .map(|&spelling| self.aliases.get(spelling).copied().unwrap_or(spelling))
+            .collect::<Vec<_>>();
+
+        names.sort_unstable();
+        names.dedup();
+
+        match names[..] {
+            [] => Ok(given),
+            [name] => Ok(name),
+            _ => Err(ArgParserError::AmbiguousOption {
+                given: given.to_string(),
+                candidates,
+            }),
+        }
+    }
+
+    fn resolve_negated(&self, name: &str) -> Option<&'static str> {
+        if self.options.contains_key(name) {
+            return None;
+        }
+
+        let name = name.strip_prefix("no-")?;
+
+        self.options
+            .get_key_value(self.aliases.get(name).copied().unwrap_or(name))
+            .filter(|(_, option)| option.negatable && option.kind == OptionalArgKind::Flag)
+            .map(|(&name, _)| name)
+    }
+
+    pub(crate) fn resolve(
+        &self,
+        name_or_alias: &str,
+    ) -> Result<(&'static str, &OptionalArg, Option<&'static str>), ArgParserError> {
+        use ArgParserError::*;
+
+        let (name, alias) = if self.is_valid_alias(name_or_alias) {
+            let (&alias, &name) =
+                self.aliases
+                    .get_key_value(name_or_alias)
+                    .ok_or_else(|| UnknownAlias {
+                        alias: name_or_alias.to_string(),
+                        suggestion: self.suggest_alias(name_or_alias),
+                    })?;
+
+            (name, Some(alias))
+        } else {
+            (
+                self.aliases
+                    .get(name_or_alias)
+                    .copied()
+                    .unwrap_or(name_or_alias),
+                None,
+            )
+        };
+
+        let (name, option) = self
+            .options
+            .get_key_value(name)
+            .filter(|(_, option)| !option.env_only)
+            .ok_or_else(|| UnknownOption {
+                name: name.to_string(),
+                suggestion: self.suggest_option(name),
+            })?;
+
+        Ok((name, option, alias))
+    }
+}
+
+#[test]
+fn test_parse() -> Result<(), ArgParserError> {
+    use ArgParserError::*;
+    use ParsedArg::*;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::flag("foo").alias("f"))?
+        .add_option(OptionalArg::flag("bar").multiple().alias("b"))?
+        .add_option(OptionalArg::required_value("baz").multiple().alias("B"))?
+        .add_option(OptionalArg::optional_value("qux").multiple().alias("q"))?;
+
+    assert_eq!(
+        Ok(vec![
+            Positional {
+                value: "foo".to_string()
+            },
+            Positional {
+                value: "bar".to_string()
+            }
+        ]),
+        parser.parse(&["foo", "bar"]).map(Vec::from)
+    );
+    assert_eq!(
+        Err(InvalidOption {
+            name: "-foo".to_string()
+        }),
+        parser.parse(&["---foo"])
+    );
+    assert_eq!(
+        Err(UnknownOption {
+            name: "Foo".to_string(),
+            suggestion: Some("foo")
+        }),
+        parser.parse(&["--Foo"])
+    );
+    assert_eq!(
+        Err(DuplicateOption { name: "foo" }),
+        parser.parse(&["--foo", "--foo"])
+    );
+    assert_eq!(
+        Ok(vec![
+            Flag {
+                name: "foo",
+                value: true
+            },
+            Positional {
+                value: "--".to_string()
+            },
+            Positional {
+                value: "--foo".to_string()
+            }
+        ]),
+        parser.parse(&["--foo", "--", "--", "--foo"]).map(Vec::from)
+    );
+    assert_eq!(
+        Err(InvalidOptionValue {
+            name: "bar",
+            value: "no".to_string()
+        }),
+        parser.parse(&["--bar=no"])
+    );
+    assert_eq!(
+        Err(InvalidAliasValue {
+            alias: "b",
+            value: "no".to_string()
+        }),
+        parser.parse(&["-b=no"])
+    );
+    assert_eq!(
+        Err(UnknownAlias {
+            alias: "a".to_string(),
+            suggestion: None
+        }),
+        parser.parse(&["-a"])
+    );
+    assert_eq!(
+        Err(DuplicateAlias { alias: "f" }),
+        parser.parse(&["-f", "-f"])
+    );
+    assert_eq!(
+        Ok(vec![
+            Flag {
+                name: "bar",
+                value: true
+            },
+            Flag {
+                name: "bar",
+                value: false
+            },
+            Flag {
+                name: "bar",
+                value: true,
+            },
+            Positional {
+                value: "false".to_string()
+            }
+        ]),
+        parser
+            .parse(&["--bar=true", "-b=false", "-b", "false"])
+            .map(Vec::from)
+    );
+    assert_eq!(
+        Err(MissingOptionValue { name: "baz" }),
+        parser.parse(&["--baz"]),
+    );
+    assert_eq!(
+        Err(MissingAliasValue { alias: "B" }),
+        parser.parse(&["-B", "--foo"])
+    );
+    assert_eq!(
+        Ok(vec![
+            RequiredValue {
+                name: "baz",
+                value: "123".to_string()
+            },
+            RequiredValue {
+                name: "baz",
+                value: "456".to_string()
+            }
+        ]),
+        parser.parse(&["--baz=123", "-B", "456"]).map(Vec::from)
+    );
+    assert_eq!(
+        Ok(vec![
+            OptionalValue {
+                name: "qux",
+                value: None
+            },
+            Positional {
+                value: "foo".to_string()
+            },
+            OptionalValue {
+                name: "qux",
+                value: Some("bar".to_string())
+            }
+        ]),
+        parser.parse(&["--qux", "foo", "--qux=bar"]).map(Vec::from)
+    );
+    assert_eq!(
+        Err(InvalidCluster {
+            cluster: "-btrue".to_string(),
+            flags: vec!["b"],
+            alias: "t".to_string(),
+            rest: "-true".to_string()
+        }),
+        parser.parse(&["-btrue"])
+    );
+    assert_eq!(
+        Err(InvalidAliasValue {
+            alias: "b",
+            value: "-foo".to_string()
+        }),
+        parser.parse(&["-b-foo"])
+    );
+    assert_eq!(
+        Ok(vec![
+            Flag {
+                name: "bar",
+                value: true
+            },
+            RequiredValue {
+                name: "baz",
+                value: "q=123".to_string()
+            },
+            Flag {
+                name: "bar",
+                value: true
+            },
+            OptionalValue {
+                name: "qux",
+                value: Some("123".to_string())
+            }
+        ]),
+        parser.parse(&["-bBq=123", "-bq=123"]).map(Vec::from)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_options_first() -> Result<(), ArgParserError> {
+    use ArgParserError::*;
+    use ParsedArg::*;
+
+    let mut parser = ArgParser::new(ArgParserMode::OptionsFirst);
+
+    parser
+        .add_positional(PositionalArg::named())?
+        .add_positional(PositionalArg::named())?
+        .add_positional(PositionalArg::rest())?
+        .add_option(OptionalArg::flag("foo"))?;
+
+    assert_eq!(
+        Err(MissingArgs {
+            actual: 1,
+            expected: 2,
+            missing: vec![]
+        }),
+        parser.parse(&["--foo", "foo"])
+    );
+    assert_eq!(
+        Ok(vec![
+            Flag {
+                name: "foo",
+                value: true
+            },
+            Positional {
+                value: "foo".to_string()
+            },
+            Positional {
+                value: "--foo".to_string()
+            }
+        ]),
+        parser.parse(&["--foo", "foo", "--foo"]).map(Vec::from)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_rest_policy() -> Result<(), ArgParserError> {
+    use crate::{flag, parsed_eq, pos};
+
+    let build = |policy| {
+        let mut parser = ArgParser::default();
+
+        parser
+            .add_option(OptionalArg::flag("verbose").alias("v"))?
+            .add_positional(PositionalArg::named())?
+            .add_positional(PositionalArg::rest().rest_policy(policy))?;
+
+        Ok::<_, ArgParserError>(parser)
+    };
+    let lazy = build(RestPolicy::Lazy)?;
+    let greedy = build(RestPolicy::Greedy)?;
+
+    let args = ["script.py", "a", "-v", "--", "b"];
+
+    parsed_eq!(
+        lazy.parse(&args),
+        [pos!("script.py"), pos!("a"), flag!("verbose"), pos!("b")]
+    );
+    parsed_eq!(
+        greedy.parse(&args),
+        [
+            pos!("script.py"),
+            pos!("a"),
+            pos!("-v"),
+            pos!("--"),
+            pos!("b")
+        ]
+    );
+    parsed_eq!(
+        greedy.parse(&["-v", "script.py", "x", "-v"]),
+        [flag!("verbose"), pos!("script.py"), pos!("x"), pos!("-v")]
+    );
+
+    // Once the named arg has its value, the greedy rest takes what follows straight away
+    parsed_eq!(
+        greedy.parse(&["script.py", "-v"]),
+        [pos!("script.py"), pos!("-v")]
+    );
+    parsed_eq!(
+        lazy.parse(&["script.py", "-v"]),
+        [pos!("script.py"), flag!("verbose")]
+    );
+    assert_eq!(RestPolicy::Lazy, lazy.parse(&["x"])?.meta().rest_policy);
+    assert_eq!(RestPolicy::Greedy, greedy.parse(&["x"])?.meta().rest_policy);
+    assert_eq!(
+        Err(ArgParserError::InvalidPositionalSetting {
+            position: 2,
+            setting: "rest_policy",
+            reason: "only a rest arg has one"
+        }),
+        ArgParser::default()
+            .add_positional(PositionalArg::named())?
+            .add_positional(PositionalArg::named().rest_policy(RestPolicy::Greedy))
+            .map(|_| ())
+    );
+    assert_eq!(
+        "invalid rest_policy on arg #1: only a rest arg has one",
+        ArgParser::default()
+            .add_signature(vec![
+                PositionalArg::optional().rest_policy(RestPolicy::Greedy)
+            ])
+            .unwrap_err()
+            .to_string()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_strict_positional() -> Result<(), ArgParserError> {
+    use ArgParserError::*;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::flag("force"))?
+        .add_positional(PositionalArg::named())?
+        .add_positional(PositionalArg::optional())?;
+
+    assert_eq!(3, parser.parse(&["a", "b", "c"])?.meta().positional);
+
+    parser.strict_positional(true);
+
+    assert_eq!(2, parser.parse(&["a", "--force", "b"])?.meta().positional);
+    assert_eq!(
+        Err(UnexpectedArg {
+            value: "c".to_string()
+        }),
+        parser.parse(&["a", "b", "c"])
+    );
+    assert_eq!(
+        "unexpected argument 'c'",
+        parser.parse(&["a", "b", "c"]).unwrap_err().to_string()
+    );
+
+    parser.add_positional(PositionalArg::rest())?;
+
+    assert_eq!(4, parser.parse(&["a", "b", "c", "d"])?.meta().positional);
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_eager() -> Result<(), ArgParserError> {
+    use super::ArgSelector;
+
+    let mut parser = ArgParser::new(ArgParserMode::OptionsFirst);
+
+    parser
+        .add_option(OptionalArg::flag("help").alias("h").eager())?
+        .add_option(OptionalArg::required_value("token"))?
+        .add_positional(PositionalArg::named().name("command"))?
+        .add_positional(PositionalArg::rest())?
+        .add_group(ArgGroup::at_least_one("auth", &["token"]))?;
+
+    let mut sub = ArgParser::default();
+
+    sub.add_option(OptionalArg::flag("help").alias("h").eager())?
+        .add_option(OptionalArg::flag("verbose").alias("v"))?
+        .add_option(OptionalArg::required_value("message").allow_hyphen_values())?
+        .add_positional(PositionalArg::named().name("path"))?;
+
+    assert_eq!(Some("help"), parser.parse(&["-h"])?.meta().eager);
+
+    let args = parser.parse(&["sub", "--help"])?;
+
+    assert_eq!(Some("help"), args.meta().eager);
+    assert_eq!(
+        vec!["sub", "--help"],
+        ArgSelector::from(&args).get_positional()
+    );
+    assert_eq!(Some("help"), sub.parse(&["--help"])?.meta().eager);
+    assert_eq!(Some("help"), sub.parse(&["-vh"])?.meta().eager);
+    assert!(matches!(
+        sub.parse(&["--message", "--help"]),
+        Err(ArgParserError::MissingArgs { .. })
+    ));
+
+    assert_eq!(
+        Err(ArgParserError::MissingArgs {
+            actual: 0,
+            expected: 1,
+            missing: vec!["path"]
+        }),
+        sub.parse(&[])
+    );
+    assert!(matches!(
+        parser.parse(&["sub", "x"]),
+        Err(ArgParserError::MissingGroupOption { .. })
+    ));
+    assert!(parser.parse(&["--", "--help"]).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_rest_min() -> Result<(), ArgParserError> {
+    use ArgParserError::*;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::flag("force"))?
+        .add_positional(PositionalArg::rest().name("files").min(1))?;
+
+    assert_eq!(2, parser.parse(&["a", "b"])?.meta().positional);
+    assert_eq!(
+        Err(MissingArgs {
+            actual: 0,
+            expected: 1,
+            missing: vec!["files"]
+        }),
+        parser.parse(&["--force"])
+    );
+    assert_eq!(
+        "missing required argument <files>",
+        parser.parse(&[]).unwrap_err().to_string()
+    );
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_positional(PositionalArg::named().name("dest"))?
+        .add_positional(PositionalArg::optional())?
+        .add_positional(PositionalArg::rest().name("srcs").min(2))?;
+
+    assert_eq!(4, parser.parse(&["d", "o", "a", "b"])?.meta().positional);
+    assert_eq!(
+        Err(MissingArgs {
+            actual: 3,
+            expected: 4,
+            missing: vec!["srcs"]
+        }),
+        parser.parse(&["d", "o", "a"])
+    );
+    assert_eq!(
+        Err(MissingArgs {
+            actual: 0,
+            expected: 4,
+            missing: vec!["dest", "srcs"]
+        }),
+        parser.parse(&[])
+    );
+
+    let invalid = |position| {
+        Err(InvalidPositionalSetting {
+            position,
+            setting: "min",
+            reason: "only a rest arg takes several values",
+        })
+    };
+
+    assert_eq!(
+        invalid(1),
+        ArgParser::default()
+            .add_positional(PositionalArg::named().min(3))
+            .map(|_| ())
+    );
+    assert_eq!(
+        invalid(2),
+        ArgParser::default()
+            .add_signature(vec![
+                PositionalArg::named(),
+                PositionalArg::optional().min(3)
+            ])
+            .map(|_| ())
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_missing_args() -> Result<(), ArgParserError> {
+    use ArgParserError::*;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_positional(PositionalArg::named().name("input"))?
+        .add_positional(PositionalArg::named())?
+        .add_positional(PositionalArg::named().name("output"))?;
+
+    assert_eq!(
+        Err(MissingArgs {
+            actual: 1,
+            expected: 3,
+            missing: vec![]
+        }),
+        parser.parse(&["foo"]).map(|_| ())
+    );
+    assert_eq!(
+        "missing required argument <output>",
+        parser.parse(&["foo", "bar"]).unwrap_err().to_string()
+    );
+    assert_eq!(
+        "3 arg(s) required, but got 0",
+        parser.parse(&[]).unwrap_err().to_string()
+    );
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_positional(PositionalArg::named().name("input"))?
+        .add_positional(PositionalArg::named().name("mode"))?
+        .add_positional(PositionalArg::named().name("output"))?;
+
+    assert_eq!(
+        "missing required arguments <mode>, <output>",
+        parser.parse(&["foo"]).unwrap_err().to_string()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_validator() -> Result<(), ArgParserError> {
+    use ArgParserError::*;
+    use ParsedArg::*;
+
+    let users = vec!["root", "guest"];
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_positional(PositionalArg::named().validator(|s| {
+            if s.starts_with('/') {
+                Ok(())
+            } else {
+                Err("must be an absolute path".to_string())
+            }
+        }))?
+        .add_positional(PositionalArg::rest().validator(|s| {
+            if s.is_empty() {
+                Err("must not be empty".to_string())
+            } else {
+                Ok(())
+            }
+        }))?
+        .add_option(OptionalArg::required_value("user").validator(move |s| {
+            if users.contains(&s) {
+                Ok(())
+            } else {
+                Err(format!("unknown user, expected one of {:?}", users))
+            }
+        }))?
+        .add_option(
+            OptionalArg::optional_value("color")
+                .alias("c")
+                .validator(|s| match s {
+                    "always" | "never" => Ok(()),
+                    _ => Err("expected 'always' or 'never'".to_string()),
+                }),
+        )?;
+
+    assert_eq!(
+        Ok(vec![
+            RequiredValue {
+                name: "user",
+                value: "root".to_string()
+            },
+            Positional {
+                value: "/tmp".to_string()
+            },
+            OptionalValue {
+                name: "color",
+                value: None
+            },
+            Positional {
+                value: "foo".to_string()
+            }
+        ]),
+        parser
+            .parse(&["--user", "root", "/tmp", "--color", "foo"])
+            .map(Vec::from)
+    );
+    assert_eq!(
+        Err(RejectedValue {
+            name: "user",
+            value: "admin".to_string(),
+            reason: "unknown user, expected one of [\"root\", \"guest\"]".to_string()
+        }),
+        parser.parse(&["--user=admin", "/tmp"])
+    );
+    assert_eq!(
+        Err(RejectedValue {
+            name: "color",
+            value: "auto".to_string(),
+            reason: "expected 'always' or 'never'".to_string()
+        }),
+        parser.parse(&["-cauto", "/tmp"])
+    );
+    assert_eq!(
+        Err(RejectedPositional {
+            position: 1,
+            value: "tmp".to_string(),
+            reason: "must be an absolute path".to_string()
+        }),
+        parser.parse(&["tmp"])
+    );
+    assert_eq!(
+        Err(RejectedPositional {
+            position: 3,
+            value: "".to_string(),
+            reason: "must not be empty".to_string()
+        }),
+        parser.parse(&["/tmp", "foo", ""])
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_typed_positional() -> Result<(), ArgParserError> {
+    use ArgParserError::*;
+
+    let manifest = concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml");
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_positional(PositionalArg::named().must_exist())?
+        .add_positional(PositionalArg::named().choices(&["debug", "release"]))?
+        .add_positional(PositionalArg::rest().range(1..=8))?;
+
+    assert_eq!(
+        Ok(4),
+        parser
+            .parse(&[manifest, "release", "1", "8"])
+            .map(|args| Vec::from(args).len())
+    );
+    assert_eq!(
+        Err(RejectedPositional {
+            position: 1,
+            value: "missing.toml".to_string(),
+            reason: "no such file or directory".to_string()
+        }),
+        parser.parse(&["missing.toml", "debug"])
+    );
+    assert_eq!(
+        Err(RejectedPositional {
+            position: 2,
+            value: "fast".to_string(),
+            reason: "must be one of debug, release".to_string()
+        }),
+        parser.parse(&[manifest, "fast"])
+    );
+    assert_eq!(
+        Err(RejectedPositional {
+            position: 4,
+            value: "9".to_string(),
+            reason: "must be in range 1..=8".to_string()
+        }),
+        parser.parse(&[manifest, "debug", "2", "9"])
+    );
+    assert_eq!(
+        Err(RejectedPositional {
+            position: 3,
+            value: "two".to_string(),
+            reason: "not a number".to_string()
+        }),
+        parser.parse(&[manifest, "debug", "two"])
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_range() -> Result<(), ArgParserError> {
+    use ArgParserError::*;
+    use ParsedArg::*;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::required_value("port").range(1..=65535))?
+        .add_option(OptionalArg::optional_value("level").range(0..10))?;
+
+    assert_eq!(
+        Ok(vec![
+            RequiredValue {
+                name: "port",
+                value: "8080".to_string()
+            },
+            OptionalValue {
+                name: "level",
+                value: Some("9".to_string())
+            }
+        ]),
+        parser
+            .parse(&["--port", "8080", "--level=9"])
+            .map(Vec::from)
+    );
+    assert_eq!(
+        Err(RejectedValue {
+            name: "port",
+            value: "70000".to_string(),
+            reason: "must be in range 1..=65535".to_string()
+        }),
+        parser.parse(&["--port=70000"])
+    );
+    assert_eq!(
+        Err(RejectedValue {
+            name: "port",
+            value: "http".to_string(),
+            reason: "not a number".to_string()
+        }),
+        parser.parse(&["--port", "http"])
+    );
+    assert_eq!(
+        Err(RejectedValue {
+            name: "level",
+            value: "10".to_string(),
+            reason: "must be in range 0..=9".to_string()
+        }),
+        parser.parse(&["--level=10"])
+    );
+
+    let (min, max) = (5, 1);
+
+    assert_eq!(
+        Err(InvalidOptionSetting {
+            name: "jobs",
+            setting: "range",
+            reason: "no number is in it"
+        }),
+        parser
+            .add_option(OptionalArg::required_value("jobs").range(min..=max))
+            .map(|_| ())
+    );
+    assert_eq!(
+        "invalid range on arg #1: no number is in it",
+        parser
+            .add_positional(PositionalArg::named().range(min..min))
+            .map(|_| ())
+            .unwrap_err()
+            .to_string()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_aliases() -> Result<(), ArgParserError> {
+    use ArgParserError::*;
+    use ParsedArg::*;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(
+            OptionalArg::required_value("color")
+                .alias("colour")
+                .alias("c")
+                .alias("C"),
+        )?
+        .add_option(OptionalArg::flag("cache").alias("caching").negatable())?;
+
+    assert_eq!(
+        Ok(vec![
+            RequiredValue {
+                name: "color",
+                value: "red".to_string()
+            },
+            Flag {
+                name: "cache",
+                value: false
+            }
+        ]),
+        parser
+            .parse(&["--colour=red", "--no-caching"])
+            .map(Vec::from)
+    );
+    assert_eq!(
+        Err(DuplicateOption { name: "color" }),
+        parser.parse(&["--color=red", "--colour=blue"])
+    );
+    assert_eq!(
+        Err(DuplicateAlias { alias: "C" }),
+        parser.parse(&["-cred", "-Cblue"])
+    );
+    assert_eq!(
+        Err(MissingOptionValue { name: "color" }),
+        parser.parse(&["--colour"])
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_duplicates() -> Result<(), ArgParserError> {
+    use crate::{flag, parsed_eq, pos, value};
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(
+            OptionalArg::required_value("color")
+                .alias("c")
+                .duplicates(DuplicateOptions::LastWins),
+        )?
+        .add_option(
+            OptionalArg::flag("force")
+                .negatable()
+                .duplicates(DuplicateOptions::LastWins),
+        )?
+        .add_option(OptionalArg::required_value("out").duplicates(DuplicateOptions::FirstWins))?;
+
+    parsed_eq!(
+        parser.parse(&["--color=red", "a", "-cblue", "--force", "--no-force"]),
+        [pos!("a"), value!("color", "blue"), flag!("force", false)]
+    );
+    parsed_eq!(
+        parser.parse(&["--out=a.txt", "--out=b.txt", "--out", "c.txt"]),
+        [value!("out", "a.txt")]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_clusters() -> Result<(), ArgParserError> {
+    use ArgParserError::*;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::flag("force").alias("f"))?
+        .add_option(OptionalArg::flag("bare").alias("b"))?
+        .add_option(OptionalArg::count("verbose").alias("v"))?;
+
+    crate::parsed_eq!(
+        parser.parse(&["-fb=false"]),
+        [crate::flag!("force"), crate::flag!("bare", false)]
+    );
+    assert_eq!(
+        Err(InvalidCluster {
+            cluster: "-fvbx=1".to_string(),
+            flags: vec!["f", "v", "b"],
+            alias: "x".to_string(),
+            rest: "-x=1".to_string()
+        }),
+        parser.parse(&["-fvbx=1"])
+    );
+    assert_eq!(
+        "-fx was read as a cluster of aliases -f -x, but -x is undefined",
+        parser.parse(&["-fx"]).unwrap_err().to_string()
+    );
+    assert_eq!(
+        Err(UnknownAlias {
+            alias: "x".to_string(),
+            suggestion: None
+        }),
+        parser.parse(&["-xf"])
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_values() -> Result<(), ArgParserError> {
+    use ArgParserError::*;
+    use ParsedArg::*;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::values("size", 2..=3).alias("s").range(0..))?
+        .add_option(OptionalArg::flag("foo"))?;
+
+    assert_eq!(
+        Ok(vec![
+            Values {
+                name: "size",
+                values: vec!["1".to_string(), "2".to_string()]
+            },
+            Flag {
+                name: "foo",
+                value: true
+            }
+        ]),
+        parser.parse(&["--size", "1", "2", "--foo"]).map(Vec::from)
+    );
+    assert_eq!(
+        Ok(vec![
+            Values {
+                name: "size",
+                values: vec!["1".to_string(), "2".to_string(), "3".to_string()]
+            },
+            Positional {
+                value: "4".to_string()
+            }
+        ]),
+        parser.parse(&["-s1", "2", "3", "4"]).map(Vec::from)
+    );
+    assert_eq!(
+        Ok(vec![
+            Values {
+                name: "size",
+                values: vec!["1".to_string(), "2".to_string()]
+            },
+            Positional {
+                value: "3".to_string()
+            }
+        ]),
+        parser.parse(&["--size=1", "2", "--", "3"]).map(Vec::from)
+    );
+    assert_eq!(
+        Err(MissingOptionValues {
+            name: "size",
+            expected: 2,
+            actual: 1
+        }),
+        parser.parse(&["--size", "1", "--foo"])
+    );
+    assert_eq!(
+        Err(RejectedValue {
+            name: "size",
+            value: "x".to_string(),
+            reason: "not a number".to_string()
+        }),
+        parser.parse(&["--size", "1", "x"])
+    );
+
+    // An occurrence has to be able to take at least one value
+    let (min, max) = (3, 1);
+
+    for (arity, reason) in [
+        (min..=max, "no number of values is in it"),
+        (0..=0, "the option takes no value"),
+    ] {
+        assert_eq!(
+            Err(InvalidOptionSetting {
+                name: "scale",
+                setting: "arity",
+                reason
+            }),
+            parser.add_option(OptionalArg::values("scale", arity))
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_greedy_values() -> Result<(), ArgParserError> {
+    use crate::{flag, parsed_eq, pos, values};
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::values("files", 1..=1).alias("f").greedy())?
+        .add_option(OptionalArg::flag("verbose"))?;
+
+    parsed_eq!(
+        parser.parse(&["--files", "a", "b", "c", "--verbose", "d"]),
+        [
+            values!("files", ["a", "b", "c"]),
+            flag!("verbose"),
+            pos!("d")
+        ]
+    );
+    parsed_eq!(
+        parser.parse(&["-fa", "b", "--", "c"]),
+        [values!("files", ["a", "b"]), pos!("c")]
+    );
+    assert_eq!(
+        Err(ArgParserError::MissingOptionValues {
+            name: "files",
+            expected: 1,
+            actual: 0
+        }),
+        parser.parse(&["--files", "--verbose"])
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_value_terminator() -> Result<(), ArgParserError> {
+    use crate::{flag, parsed_eq, pos, values};
+    use ArgParserError::*;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(
+            OptionalArg::values("exec", 1..=usize::MAX)
+                .multiple()
+                .value_terminator(";"),
+        )?
+        .add_option(OptionalArg::values("pair", 2..=2).value_terminator("+"))?
+        .add_option(OptionalArg::flag("verbose"))?;
+
+    parsed_eq!(
+        parser.parse(&[
+            "--exec",
+            "rm",
+            "-rf",
+            "{}",
+            ";",
+            "--verbose",
+            "--exec=ls",
+            ";",
+            "x"
+        ]),
+        [
+            values!("exec", ["rm", "-rf", "{}"]),
+            flag!("verbose"),
+            values!("exec", ["ls"]),
+            pos!("x")
+        ]
+    );
+    assert_eq!(
+        Err(MissingValueTerminator {
+            name: "exec",
+            terminator: ";"
+        }),
+        parser.parse(&["--exec", "rm", "{}"])
+    );
+    assert_eq!(
+        Err(MissingOptionValues {
+            name: "exec",
+            expected: 1,
+            actual: 0
+        }),
+        parser.parse(&["--exec", ";"])
+    );
+    assert_eq!(
+        Some("exec"),
+        parser.parse_partial(&["--exec", "rm", ""])?.pending_option
+    );
+
+    // The terminator doesn't lift the declared arity
+    parsed_eq!(
+        parser.parse(&["--pair", "a", "b", "+"]),
+        [values!("pair", ["a", "b"])]
+    );
+    assert_eq!(
+        Err(TooManyOptionValues {
+            name: "pair",
+            expected: 2,
+            actual: 3
+        }),
+        parser.parse(&["--pair", "a", "b", "c", "+"])
+    );
+    assert_eq!(
+        "invalid value_terminator on --cmd: only values() options take several values",
+        parser
+            .add_option(OptionalArg::required_value("cmd").value_terminator(";"))
+            .unwrap_err()
+            .to_string()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_count() -> Result<(), ArgParserError> {
+    use ArgParserError::*;
+    use ParsedArg::*;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::count("verbose").alias("v"))?
+        .add_option(OptionalArg::flag("force").alias("f"))?;
+
+    assert_eq!(
+        Ok(vec![Count {
+            name: "verbose",
+            value: 3
+        }]),
+        parser.parse(&["-vvv"]).map(Vec::from)
+    );
+    assert_eq!(
+        Ok(vec![
+            Count {
+                name: "verbose",
+                value: 4
+            },
+            Flag {
+                name: "force",
+                value: true
+            }
+        ]),
+        parser.parse(&["-v", "-vfv", "--verbose"]).map(Vec::from)
+    );
+    assert_eq!(
+        Err(InvalidOptionValue {
+            name: "verbose",
+            value: "3".to_string()
+        }),
+        parser.parse(&["--verbose=3"])
+    );
+    assert_eq!(
+        Err(InvalidAliasValue {
+            alias: "v",
+            value: "true".to_string()
+        }),
+        parser.parse(&["-v=true"])
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_negatable() -> Result<(), ArgParserError> {
+    use ArgParserError::*;
+    use ParsedArg::*;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::flag("cache").negatable())?
+        .add_option(OptionalArg::flag("color"))?
+        .add_option(OptionalArg::flag("pager").negatable())?
+        .add_option(OptionalArg::flag("no-pager"))?;
+
+    assert_eq!(
+        Ok(vec![
+            Flag {
+                name: "cache",
+                value: false
+            },
+            Flag {
+                name: "no-pager",
+                value: true
+            }
+        ]),
+        parser.parse(&["--no-cache", "--no-pager"]).map(Vec::from)
+    );
+    assert_eq!(
+        Err(DuplicateOption { name: "cache" }),
+        parser.parse(&["--cache", "--no-cache"])
+    );
+    assert_eq!(
+        Err(InvalidOptionValue {
+            name: "cache",
+            value: "true".to_string()
+        }),
+        parser.parse(&["--no-cache=true"])
+    );
+    assert_eq!(
+        Err(UnknownOption {
+            name: "no-color".to_string(),
+            suggestion: None
+        }),
+        parser.parse(&["--no-color"])
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_option_prefixes() -> Result<(), ArgParserError> {
+    use ParsedArg::*;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::required_value("name"))?
+        .add_option(OptionalArg::flag("verbose").alias("v").negatable())?
+        .add_option(OptionalArg::count("depth").alias("d"))?
+        .option_prefixes("-", "-")?;
+
+    assert_eq!(
+        Ok(vec![
+            RequiredValue {
+                name: "name",
+                value: "*.rs".to_string()
+            },
+            Flag {
+                name: "verbose",
+                value: false
+            },
+            Count {
+                name: "depth",
+                value: 2
+            },
+        ]),
+        parser
+            .parse(&["-name", "*.rs", "-no-verbose", "-dd"])
+            .map(Vec::from)
+    );
+    assert_eq!(
+        Err(ArgParserError::UnknownOption {
+            name: "type".to_string(),
+            suggestion: None
+        }),
+        parser.parse(&["-type", "f"]).map(|_| ())
+    );
+
+    parser.option_prefixes("++", "+")?;
+
+    assert_eq!(
+        Ok(vec![
+            Flag {
+                name: "verbose",
+                value: true
+            },
+            Count {
+                name: "depth",
+                value: 1
+            },
+            Positional {
+                value: "-v".to_string()
+            },
+        ]),
+        parser.parse(&["++verbose", "+d", "-v"]).map(Vec::from)
+    );
+    assert_eq!(
+        Err(ArgParserError::InvalidAliasValue {
+            alias: "d",
+            value: "+v".to_string()
+        }),
+        parser.parse(&["+d+v"]).map(|_| ())
+    );
+
+    // Messages rendered by the parser use its prefixes, while `Display` keeps the standard ones
+    let error = parser.parse(&["++nme", "x"]).unwrap_err();
+
+    assert_eq!(
+        "--nme is undefined, did you mean --name?",
+        error.to_string()
+    );
+    assert_eq!(
+        "++nme is undefined, did you mean ++name?",
+        parser.error_message(&error)
+    );
+    assert_eq!(
+        "+d cannot accept '+v' as a value",
+        parser.explain(&parser.parse(&["+d+v"]).unwrap_err(), 0)
+    );
+    assert_eq!(
+        "+dvx was read as a cluster of aliases +d +v +x, but +x is undefined",
+        parser.error_message(&parser.parse(&["+dvx"]).unwrap_err())
+    );
+
+    parser.negative_numbers(true);
+
+    assert_eq!(
+        Ok(vec![Positional {
+            value: "+5".to_string()
+        }]),
+        parser.parse(&["+5"]).map(Vec::from)
+    );
+    assert_eq!(
+        Err(ArgParserError::InvalidPrefix { prefix: "" }),
+        parser.option_prefixes("--", "").map(|_| ())
+    );
+    assert_eq!(
+        Err(ArgParserError::InvalidPrefix { prefix: "x-" }),
+        parser.option_prefixes("x-", "-").map(|_| ())
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_allow_unknown() -> Result<(), ArgParserError> {
+    use ParsedArg::*;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::flag("verbose").alias("v"))?
+        .add_positional(PositionalArg::rest())?;
+
+    let args = ["--verbose", "--jobs=4", "-x", "-vx", "--", "--other"];
+
+    assert_eq!(
+        Err(ArgParserError::UnknownOption {
+            name: "jobs".to_string(),
+            suggestion: None
+        }),
+        parser.parse(&args).map(|_| ())
+    );
+
+    parser.allow_unknown(true);
+
+    assert_eq!(
+        Ok(vec![
+            Flag {
+                name: "verbose",
+                value: true
+            },
+            Unknown {
+                raw: "--jobs=4".to_string()
+            },
+            Unknown {
+                raw: "-x".to_string()
+            },
+            Unknown {
+                raw: "-vx".to_string()
+            },
+            Positional {
+                value: "--other".to_string()
+            },
+        ]),
+        parser.parse(&args).map(Vec::from)
+    );
+
+    parser
+        .add_option(OptionalArg::count("quiet").alias("q"))?
+        .add_option(OptionalArg::required_value("out").alias("o"))?;
+
+    assert_eq!(
+        Ok(vec![
+            Count {
+                name: "quiet",
+                value: 2
+            },
+            Flag {
+                name: "verbose",
+                value: true
+            },
+            RequiredValue {
+                name: "out",
+                value: "x".to_string()
+            },
+        ]),
+        parser.parse(&["-qv", "-qox"]).map(Vec::from)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_terminator() -> Result<(), ArgParserError> {
+    use ParsedArg::*;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::flag("verbose"))?
+        .add_positional(PositionalArg::rest())?
+        .terminator(Some(";"));
+
+    let positional = |values: &[&str]| {
+        values
+            .iter()
+            .map(|value| Positional {
+                value: value.to_string(),
+            })
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        Ok(positional(&["--", "--verbose"])),
+        parser.parse(&[";", "--", "--verbose"]).map(Vec::from)
+    );
+
+    parser.keep_terminator(true);
+
+    assert_eq!(
+        Ok(positional(&[";", "--verbose", ";"])),
+        parser.parse(&[";", "--verbose", ";"]).map(Vec::from)
+    );
+
+    parser.capture_rest(true);
+
+    assert_eq!(
+        Ok(vec![Rest {
+            values: vec![";".to_string(), "a".to_string()]
+        }]),
+        parser.parse(&[";", "a"]).map(Vec::from)
+    );
+
+    parser.capture_rest(false).terminator(None);
+
+    assert_eq!(
+        Ok(positional(&["--", "a", "--"])),
+        parser.parse(&["--", "a", "--"]).map(Vec::from)
+    );
+
+    // Nor is it the terminator when another one is set
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::required_value("out"))?
+        .terminator(Some(";"));
+
+    assert_eq!(
+        Ok(vec![RequiredValue {
+            name: "out",
+            value: "--".to_string()
+        }]),
+        parser.parse(&["--out", "--"]).map(Vec::from)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_capture_rest() -> Result<(), ArgParserError> {
+    use super::ArgSelector;
+    use ParsedArg::*;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::flag("verbose"))?
+        .add_positional(PositionalArg::rest())?
+        .capture_rest(true);
+
+    let args = parser.parse(&["a", "--verbose", "--", "b", "--verbose", "--"])?;
+    let sel = ArgSelector::from(&args);
+
+    assert_eq!(
+        Some(&Rest {
+            values: vec!["b".to_string(), "--verbose".to_string(), "--".to_string()]
+        }),
+        args.last()
+    );
+    assert_eq!(vec!["a"], sel.get_positional());
+    assert_eq!(vec!["b", "--verbose", "--"], sel.get_rest());
+    assert_eq!(
+        Some(&Rest { values: vec![] }),
+        parser.parse(&["--"])?.last()
+    );
+    assert!(ArgSelector::from(&parser.parse(&["a"])?)
+        .get_rest()
+        .is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_stop_at_unknown() -> Result<(), ArgParserError> {
+    use ParsedArg::*;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::flag("release").alias("r"))?
+        .add_option(OptionalArg::required_value("bin"))?
+        .add_positional(PositionalArg::rest())?
+        .stop_at_unknown(true);
+
+    let (args, remainder) = parser
+        .parse(&[
+            "--release",
+            "a",
+            "--bin",
+            "app",
+            "--port=80",
+            "--release",
+            "b",
+        ])?
+        .into_parts();
+
+    assert_eq!(
+        vec![
+            Flag {
+                name: "release",
+                value: true
+            },
+            Positional {
+                value: "a".to_string()
+            },
+            RequiredValue {
+                name: "bin",
+                value: "app".to_string()
+            },
+        ],
+        args
+    );
+    assert_eq!(vec!["--port=80", "--release", "b"], remainder);
+    assert!(parser.parse(&["--release"])?.remainder().is_empty());
+
+    let (args, remainder) = parser.parse(&["a", "-rp", "80"])?.into_parts();
+
+    assert_eq!(
+        vec![Positional {
+            value: "a".to_string()
+        }],
+        args
+    );
+    assert_eq!(vec!["-rp", "80"], remainder);
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_loose_names() -> Result<(), ArgParserError> {
+    use ParsedArg::*;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::required_value("log-level").alias("L"))?
+        .add_option(OptionalArg::flag("color").alias("colour").negatable())?
+        .add_option(OptionalArg::flag("verbose").alias("v"))?;
+
+    assert_eq!(
+        Err(ArgParserError::InvalidOption {
+            name: "log_level".to_string()
+        }),
+        parser.parse(&["--log_level=debug"]).map(|_| ())
+    );
+
+    parser.normalize_underscores(true);
+
+    assert_eq!(
+        Ok(vec![RequiredValue {
+            name: "log-level",
+            value: "debug".to_string()
+        }]),
+        parser.parse(&["--log_level=debug"]).map(Vec::from)
+    );
+    assert_eq!(
+        Err(ArgParserError::UnknownOption {
+            name: "Verbose".to_string(),
+            suggestion: Some("verbose")
+        }),
+        parser.parse(&["--Verbose"]).map(|_| ())
+    );
+
+    parser.ignore_case(true);
+
+    assert_eq!(
+        Ok(vec![
+            RequiredValue {
+                name: "log-level",
+                value: "info".to_string()
+            },
+            Flag {
+                name: "color",
+                value: false
+            },
+            Flag {
+                name: "verbose",
+                value: true
+            },
+        ]),
+        parser
+            .parse(&["--LOG_Level", "info", "--No-Colour", "--Verbose"])
+            .map(Vec::from)
+    );
+    assert_eq!(
+        Err(ArgParserError::UnknownAlias {
+            alias: "V".to_string(),
+            suggestion: Some("v")
+        }),
+        parser.parse(&["-V"]).map(|_| ())
+    );
+
+    parser.add_option(OptionalArg::flag("Verbose"))?;
+
+    assert_eq!(
+        Err(ArgParserError::AmbiguousOption {
+            given: "VERBOSE".to_string(),
+            candidates: vec!["Verbose", "verbose"]
+        }),
+        parser.parse(&["--VERBOSE"]).map(|_| ())
+    );
+    assert_eq!(
+        Ok(vec![Flag {
+            name: "Verbose",
+            value: true
+        }]),
+        parser.parse(&["--Verbose"]).map(Vec::from)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_abbreviations() -> Result<(), ArgParserError> {
+    use ParsedArg::*;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::flag("verbose").alias("loud"))?
+        .add_option(OptionalArg::flag("version").alias("V"))?
+        .add_option(OptionalArg::required_value("output").alias("out"))?;
+
+    assert_eq!(
+        Err(ArgParserError::UnknownOption {
+            name: "verb".to_string(),
+            suggestion: None
+        }),
+        parser.parse(&["--verb"]).map(|_| ())
+    );
+
+    parser.allow_abbreviations(true);
+
+    assert_eq!(
+        Ok(vec![
+            Flag {
+                name: "verbose",
+                value: true
+            },
+            RequiredValue {
+                name: "output",
+                value: "a.txt".to_string()
+            },
+            Flag {
+                name: "version",
+                value: true
+            },
+        ]),
+        parser
+            .parse(&["--verb", "--ou=a.txt", "--vers"])
+            .map(Vec::from)
+    );
+    assert_eq!(
+        Err(ArgParserError::AmbiguousOption {
+            given: "ver".to_string(),
+            candidates: vec!["verbose", "version"]
+        }),
+        parser.parse(&["--ver"]).map(|_| ())
+    );
+    assert_eq!(
+        "--ver is ambiguous, it could be --verbose, --version",
+        parser.parse(&["--ver"]).unwrap_err().to_string()
+    );
+    assert_eq!(
+        Err(ArgParserError::UnknownOption {
+            name: "quiet".to_string(),
+            suggestion: None
+        }),
+        parser.parse(&["--quiet"]).map(|_| ())
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_plus_flags() -> Result<(), ArgParserError> {
+    use ParsedArg::*;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::flag("errexit").alias("e").plus_negatable())?
+        .add_option(OptionalArg::flag("xtrace").alias("x"))?
+        .add_option(OptionalArg::required_value("output").alias("o"))?;
+
+    assert_eq!(
+        Ok(vec![
+            Flag {
+                name: "errexit",
+                value: false
+            },
+            Positional {
+                value: "+x".to_string()
+            },
+            Positional {
+                value: "+o".to_string()
+            },
+            Positional {
+                value: "+".to_string()
+            },
+        ]),
+        parser.parse(&["+e", "+x", "+o", "+"]).map(Vec::from)
+    );
+
+    parser.plus_flags(true);
+
+    assert_eq!(
+        Ok(vec![
+            Flag {
+                name: "errexit",
+                value: false
+            },
+            Flag {
+                name: "xtrace",
+                value: false
+            },
+            Positional {
+                value: "+xo".to_string()
+            },
+            Positional {
+                value: "+x".to_string()
+            },
+        ]),
+        parser.parse(&["+ex", "+xo", "--", "+x"]).map(Vec::from)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_colon_separator() -> Result<(), ArgParserError> {
+    use ParsedArg::*;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::required_value("include").alias("I").multiple())?
+        .add_option(OptionalArg::required_value("define"))?
+        .add_option(OptionalArg::required_value("out").alias("o"))?;
+
+    assert_eq!(
+        Err(ArgParserError::InvalidOption {
+            name: "define:NDEBUG".to_string()
+        }),
+        parser.parse(&["--define:NDEBUG"])
+    );
+
+    parser.colon_separator(true);
+
+    assert_eq!(
+        Ok(vec![
+            RequiredValue {
+                name: "include",
+                value: "src".to_string()
+            },
+            RequiredValue {
+                name: "define",
+                value: "NDEBUG=1".to_string()
+            },
+            RequiredValue {
+                name: "include",
+                value: "lib".to_string()
+            },
+            RequiredValue {
+                name: "out",
+                value: "c:\\build".to_string()
+            }
+        ]),
+        parser
+            .parse(&[
+                "-I:src",
+                "--define:NDEBUG=1",
+                "--include",
+                "lib",
+                "-o=c:\\build"
+            ])
+            .map(Vec::from)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_slash_options() -> Result<(), ArgParserError> {
+    use ParsedArg::*;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::flag("verbose").alias("v"))?
+        .add_option(OptionalArg::required_value("out").alias("o"))?
+        .add_option(OptionalArg::optional_value("color"))?;
+
+    let args = ["/verbose", "/out:a.txt", "/color", "/usr/bin"];
+
+    assert_eq!(
+        Ok(vec![
+            Positional {
+                value: "/verbose".to_string()
+            },
+            Positional {
+                value: "/v".to_string()
+            }
+        ]),
+        parser.parse(&["/verbose", "/v"]).map(Vec::from)
+    );
+
+    parser.slash_options(true);
+
+    assert_eq!(
+        Ok(vec![
+            Flag {
+                name: "verbose",
+                value: true
+            },
+            RequiredValue {
+                name: "out",
+                value: "a.txt".to_string()
+            },
+            OptionalValue {
+                name: "color",
+                value: None
+            },
+            Positional {
+                value: "/usr/bin".to_string()
+            }
+        ]),
+        parser.parse(&args).map(Vec::from)
+    );
+    assert_eq!(
+        Ok(vec![
+            Flag {
+                name: "verbose",
+                value: false
+            },
+            RequiredValue {
+                name: "out",
+                value: "b.txt".to_string()
+            },
+            Positional {
+                value: "/v".to_string()
+            }
+        ]),
+        parser
+            .parse(&["/v:false", "/o:b.txt", "--", "/v"])
+            .map(Vec::from)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_require_equals() -> Result<(), ArgParserError> {
+    use ArgParserError::*;
+    use ParsedArg::*;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(
+            OptionalArg::optional_value("color")
+                .alias("c")
+                .require_equals(),
+        )?
+        .add_option(OptionalArg::optional_value("tag").alias("t"))?;
+
+    assert_eq!(
+        Ok(vec![
+            OptionalValue {
+                name: "color",
+                value: None
+            },
+            Positional {
+                value: "always".to_string()
+            },
+            OptionalValue {
+                name: "tag",
+                value: Some("foo".to_string())
+            }
+        ]),
+        parser.parse(&["-c", "always", "-tfoo"]).map(Vec::from)
+    );
+    assert_eq!(
+        Ok(vec![OptionalValue {
+            name: "color",
+            value: Some("always".to_string())
+        }]),
+        parser.parse(&["-c=always"]).map(Vec::from)
+    );
+    assert_eq!(
+        Err(MissingAliasEquals {
+            alias: "c",
+            value: "always".to_string()
+        }),
+        parser.parse(&["-calways"])
+    );
+
+    parser.require_equals(true);
+
+    assert_eq!(
+        "-t cannot accept 'foo' without '=', use -t=foo",
+        parser.parse(&["-tfoo"]).unwrap_err().to_string()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_value_style() -> Result<(), ArgParserError> {
+    use ArgParserError::*;
+    use ParsedArg::*;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::required_value("out").alias("o"))?
+        .add_option(OptionalArg::required_value("log").value_style(ValueStyle::SpaceOnly))?
+        .add_option(OptionalArg::values("files", 1..=2))?
+        .value_style(ValueStyle::EqualsOnly);
+
+    assert_eq!(
+        Ok(vec![RequiredValue {
+            name: "out",
+            value: "a.txt".to_string()
+        }]),
+        parser.parse(&["--out=a.txt"]).map(Vec::from)
+    );
+    assert_eq!(
+        Err(MissingOptionEquals {
+            name: "out",
+            value: "a.txt".to_string()
+        }),
+        parser.parse(&["--out", "a.txt"])
+    );
+    assert_eq!(
+        Err(MissingAliasEquals {
+            alias: "o",
+            value: "a.txt".to_string()
+        }),
+        parser.parse(&["-oa.txt"])
+    );
+    assert_eq!(
+        Err(MissingOptionValue { name: "out" }),
+        parser.parse(&["--out"])
+    );
+    assert_eq!(
+        Ok(vec![RequiredValue {
+            name: "log",
+            value: "b.log".to_string()
+        }]),
+        parser.parse(&["--log", "b.log"]).map(Vec::from)
+    );
+    assert_eq!(
+        "--log takes its value as a separate argument, use --log b.log",
+        parser.parse(&["--log=b.log"]).unwrap_err().to_string()
+    );
+    assert_eq!(
+        Ok(vec![Values {
+            name: "files",
+            values: vec!["a".to_string(), "b".to_string()]
+        }]),
+        parser.parse(&["--files=a", "b"]).map(Vec::from)
+    );
+    assert_eq!(
+        Err(MissingOptionEquals {
+            name: "files",
+            value: "a".to_string()
+        }),
+        parser.parse(&["--files", "a", "b"])
+    );
+
+    parser.value_style(ValueStyle::SpaceOnly);
+
+    assert_eq!(
+        Err(AttachedAliasValue {
+            alias: "o",
+            value: "a.txt".to_string()
+        }),
+        parser.parse(&["-o=a.txt"])
+    );
+    assert_eq!(
+        Err(AttachedOptionValue {
+            name: "files",
+            value: "a".to_string()
+        }),
+        parser.parse(&["--files=a", "b"])
+    );
+    assert_eq!(
+        Err(InvalidOptionSetting {
+            name: "force",
+            setting: "value_style",
+            reason: "the option takes no value"
+        }),
+        parser
+            .add_option(OptionalArg::flag("force").require_equals())
+            .map(|_| ())
+    );
+    assert_eq!(
+        Err(InvalidOptionSetting {
+            name: "color",
+            setting: "value_style",
+            reason: "optional values can only be attached"
+        }),
+        parser
+            .add_option(OptionalArg::optional_value("color").value_style(ValueStyle::SpaceOnly))
+            .map(|_| ())
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_unicode_aliases() -> Result<(), ArgParserError> {
+    use ArgParserError::*;
+    use ParsedArg::*;
+
+    let mut parser = ArgParser::default();
+
+    parser.add_option(OptionalArg::flag("foo").alias("f"))?;
+
+    assert_eq!(
+        Err(InvalidAlias {
+            alias: "é".to_string()
+        }),
+        parser
+            .add_option(OptionalArg::flag("bar").alias("é"))
+            .map(|_| ())
+    );
+    assert_eq!(
+        Err(InvalidAlias {
+            alias: "💩".to_string()
+        }),
+        parser.parse(&["-💩x"])
+    );
+    assert_eq!(
+        Err(InvalidAlias {
+            alias: "日".to_string()
+        }),
+        parser.parse(&["-日本"])
+    );
+    assert_eq!(
+        Err(InvalidAliasValue {
+            alias: "f",
+            value: "-日".to_string()
+        }),
+        parser.parse(&["-f-日"])
+    );
+
+    parser
+        .unicode_aliases(true)
+        .add_option(OptionalArg::flag("bar").alias("é"))?
+        .add_option(OptionalArg::required_value("baz").alias("日"))?;
+
+    assert_eq!(
+        Ok(vec![
+            Flag {
+                name: "bar",
+                value: true
+            },
+            Flag {
+                name: "foo",
+                value: true
+            },
+            RequiredValue {
+                name: "baz",
+                value: "本".to_string()
+            }
+        ]),
+        parser.parse(&["-éf", "-日本"]).map(Vec::from)
+    );
+    assert_eq!(
+        Err(InvalidAlias {
+            alias: "💩".to_string()
+        }),
+        parser.parse(&["-💩"])
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_default_missing() -> Result<(), ArgParserError> {
+    use ParsedArg::*;
+
+    let mut parser = ArgParser::default();
+
+    parser.add_option(
+        OptionalArg::optional_value("color")
+            .multiple()
+            .default_missing("auto"),
+    )?;
+
+    assert_eq!(
+        Ok(vec![
+            OptionalValue {
+                name: "color",
+                value: Some("auto".to_string())
+            },
+            OptionalValue {
+                name: "color",
+                value: Some("never".to_string())
+            }
+        ]),
+        parser.parse(&["--color", "--color=never"]).map(Vec::from)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_conditional_defaults() -> Result<(), ArgParserError> {
+    use ParsedArg::*;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::flag("compile"))?
+        .add_option(OptionalArg::required_value("target"))?
+        .add_option(
+            OptionalArg::required_value("output")
+                .default_value_if("target", Some("wasm"), "a.wasm")
+                .default_value_if("compile", Some("true"), "a.out"),
+        )?;
+
+    assert_eq!(Ok(vec![]), parser.parse(&[]).map(Vec::from));
+    assert_eq!(
+        Ok(vec![
+            Flag {
+                name: "compile",
+                value: true
+            },
+            RequiredValue {
+                name: "output",
+                value: "a.out".to_string()
+            }
+        ]),
+        parser.parse(&["--compile"]).map(Vec::from)
+    );
+    assert_eq!(
+        Ok(vec![Flag {
+            name: "compile",
+            value: false
+        }]),
+        parser.parse(&["--compile=false"]).map(Vec::from)
+    );
+    assert_eq!(
+        Ok(vec![
+            Flag {
+                name: "compile",
+                value: true
+            },
+            RequiredValue {
+                name: "target",
+                value: "wasm".to_string()
+            },
+            RequiredValue {
+                name: "output",
+                value: "a.wasm".to_string()
+            }
+        ]),
+        parser.parse(&["--compile", "--target=wasm"]).map(Vec::from)
+    );
+    assert_eq!(
+        Ok(vec![
+            RequiredValue {
+                name: "output",
+                value: "b.out".to_string()
+            },
+            Flag {
+                name: "compile",
+                value: true
+            }
+        ]),
+        parser
+            .parse(&["--output=b.out", "--compile"])
+            .map(Vec::from)
+    );
+
+    // Static defaults are in place before any condition is looked at, whatever the names
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::required_value("backend").default_value_if(
+            "target",
+            Some("wasm"),
+            "wasmtime",
+        ))?
+        .add_option(OptionalArg::required_value("target").default_value("wasm"))?;
+
+    assert_eq!(
+        Ok(vec![
+            RequiredValue {
+                name: "target",
+                value: "wasm".to_string()
             },
+            RequiredValue {
+                name: "backend",
+                value: "wasmtime".to_string()
+            }
+        ]),
+        parser.parse(&[]).map(Vec::from)
+    );
+    assert_eq!(
+        Err(ArgParserError::RejectedValue {
+            name: "level",
+            value: "c".to_string(),
+            reason: "must be one of a, b".to_string()
+        }),
+        parser
+            .add_option(
+                OptionalArg::required_value("level")
+                    .choices(&["a", "b"])
+                    .default_value("a")
+                    .default_value_if("target", None, "c")
+            )
+            .map(|_| ())
+    );
+    assert_eq!(
+        Err(ArgParserError::RejectedValue {
+            name: "jobs",
+            value: "many".to_string(),
+            reason: "not a number".to_string()
+        }),
+        parser
+            .add_option(OptionalArg::count("jobs").default_value("many"))
+            .map(|_| ())
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_delimiter() -> Result<(), ArgParserError> {
+    use crate::{parsed_eq, value, ArgSelector, Resolver};
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(
+            OptionalArg::required_value("features")
+                .delimiter(',')
+                .choices(&["a", "b", "c"])
+                .env("FEATURES"),
+        )?
+        .add_option(
+            OptionalArg::required_value("path")
+                .delimiter(':')
+                .default_value("/bin:/usr/bin"),
+        )?;
+
+    parsed_eq!(
+        parser.parse(&["--features", "a,b", "--features=c"]),
+        [
+            value!("features", "a"),
+            value!("features", "b"),
+            value!("features", "c"),
+            value!("path", "/bin"),
+            value!("path", "/usr/bin"),
+        ]
+    );
+    assert_eq!(
+        Err(ArgParserError::RejectedItem {
+            name: "features",
+            index: 2,
+            value: "d".to_string(),
+            reason: "must be one of a, b, c".to_string()
+        }),
+        parser.parse(&["--features=a,d"])
+    );
+
+    let env = |var: &str| (var == "FEATURES").then(|| "b,c".to_string());
+    let args = Resolver::new(&parser).env(env).resolve(&[])?;
+    let sel = ArgSelector::from(&args);
+
+    assert_eq!(vec!["b", "c"], sel.get_values("features"));
+    assert_eq!(vec!["/bin", "/usr/bin"], sel.get_values("path"));
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_occurrences() -> Result<(), ArgParserError> {
+    use crate::{parsed_eq, value};
+    use ArgParserError::*;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(
+            OptionalArg::required_value("tag")
+                .multiple()
+                .min_occurrences(1)
+                .max_occurrences(2),
+        )?
+        .add_option(
+            OptionalArg::required_value("peer")
+                .multiple()
+                .max_occurrences(1)
+                .delimiter(','),
+        )?;
+
+    parsed_eq!(
+        parser.parse(&["--tag=a", "--tag=b"]),
+        [value!("tag", "a"), value!("tag", "b")]
+    );
+    parsed_eq!(
+        parser.parse(&["--tag=a", "--peer=x,y"]),
+        [value!("tag", "a"), value!("peer", "x"), value!("peer", "y")]
+    );
+    assert_eq!(
+        Err(WrongOccurrences {
+            name: "tag",
+            min: 1,
+            max: 2,
+            actual: 3
+        }),
+        parser.parse(&["--tag=a", "--tag=b", "--tag=c"])
+    );
+    assert_eq!(
+        "--tag must be given 1 to 2 time(s), but was given 0",
+        parser.parse(&[]).unwrap_err().to_string()
+    );
+    assert_eq!(
+        "--peer must be given at most 1 time(s), but was given 2",
+        parser
+            .parse(&["--tag=a", "--peer=x", "--peer=y"])
+            .unwrap_err()
+            .to_string()
+    );
+    assert_eq!(
+        Err(InvalidOptionSetting {
+            name: "retry",
+            setting: "min_occurrences",
+            reason: "it is above max_occurrences"
+        }),
+        parser
+            .add_option(
+                OptionalArg::required_value("retry")
+                    .multiple()
+                    .min_occurrences(3)
+                    .max_occurrences(1)
+            )
+            .map(|_| ())
+    );
+    assert_eq!(
+        "invalid min_occurrences on --host: the option needs multiple()",
+        parser
+            .add_option(OptionalArg::required_value("host").min_occurrences(1))
+            .unwrap_err()
+            .to_string()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_requires() -> Result<(), ArgParserError> {
+    use ArgParserError::*;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::required_value("tls-cert").requires("tls-key"))?
+        .add_option(OptionalArg::required_value("tls-key").requires("tls-cert"))?
+        .add_option(OptionalArg::flag("compile"))?
+        .add_option(
+            OptionalArg::required_value("output")
+                .requires("compile")
+                .default_value_if("compile", None, "a.out"),
+        )?;
+
+    assert!(parser.parse(&[]).is_ok());
+    assert!(parser
+        .parse(&["--tls-cert=foo.crt", "--tls-key=foo.key"])
+        .is_ok());
+    assert!(parser.parse(&["--compile"]).is_ok());
+    assert_eq!(
+        Err(MissingRequiredOption {
+            name: "tls-cert",
+            required: "tls-key"
+        }),
+        parser.parse(&["--tls-cert=foo.crt"])
+    );
+    assert_eq!(
+        Err(MissingRequiredOption {
+            name: "output",
+            required: "compile"
+        }),
+        parser.parse(&["--output=a.out"])
+    );
+    assert_eq!(
+        "--tls-key requires --tls-cert",
+        parser
+            .parse(&["--tls-key=foo.key"])
+            .unwrap_err()
+            .to_string()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_requires_defaulted() -> Result<(), ArgParserError> {
+    use ArgParserError::*;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(
+            OptionalArg::required_value("cert")
+                .requires("key")
+                .default_value("a.crt"),
+        )?
+        .add_option(OptionalArg::required_value("key").requires("cert"))?
+        .build()?;
+
+    assert!(parser.parse(&[]).is_ok());
+    assert!(parser.parse(&["--key=a.key"]).is_ok());
+    assert_eq!(
+        Err(MissingRequiredOption {
+            name: "cert",
+            required: "key"
+        }),
+        parser.parse(&["--cert=b.crt"])
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_build() -> Result<(), ArgParserError> {
+    use ArgParserError::*;
+
+    let mut parser = ArgParser::default();
+
+    parser.add_option(OptionalArg::flag("aa").requires("bb"))?;
+
+    // Not yet known, but may come later
+    assert_eq!(
+        Err(InvalidOptionSetting {
+            name: "aa",
+            setting: "requires",
+            reason: "it names an unknown option"
+        }),
+        parser.build().map(|_| ())
+    );
+
+    parser.add_option(OptionalArg::flag("bb").conflicts_with("nope"))?;
+
+    assert_eq!(
+        "invalid conflicts_with on --bb: it names an unknown option",
+        parser.parse(&[]).unwrap_err().to_string()
+    );
+
+    parser.add_option(OptionalArg::flag("nope"))?.build()?;
+
+    assert!(parser.parse(&["--aa", "--bb"]).is_ok());
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_conflicts() -> Result<(), ArgParserError> {
+    use ArgParserError::*;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::flag("json").conflicts_with("quiet"))?
+        .add_option(OptionalArg::flag("quiet").alias("q"))?
+        .add_option(OptionalArg::flag("verbose"))?;
+
+    assert!(parser.parse(&["--json", "--verbose"]).is_ok());
+    assert!(parser.parse(&["-q", "--verbose"]).is_ok());
+    assert_eq!(
+        Err(ConflictingOptions {
+            first: "json",
+            second: "quiet"
+        }),
+        parser.parse(&["--json", "--verbose", "-q"])
+    );
+    assert_eq!(
+        Err(ConflictingOptions {
+            first: "quiet",
+            second: "json"
+        }),
+        parser.parse(&["--quiet", "--json"])
+    );
+    assert_eq!(
+        "--json cannot be used with --quiet",
+        parser
+            .parse(&["--quiet", "--json"])
+            .unwrap_err()
+            .to_string()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_conflicts_defaulted() -> Result<(), ArgParserError> {
+    use ArgParserError::*;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::flag("json").conflicts_with("format"))?
+        .add_option(OptionalArg::required_value("format").default_value("text"))?;
+
+    assert!(parser.parse(&["--json"]).is_ok());
+    assert_eq!(
+        Err(ConflictingOptions {
+            first: "format",
+            second: "json"
+        }),
+        parser.parse(&["--format=yaml", "--json"])
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_groups() -> Result<(), ArgParserError> {
+    use super::ArgSelector;
+    use ArgParserError::*;
+
+    let format = ArgGroup::exactly_one("format", &["json", "yaml", "text"]);
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::flag("json"))?
+        .add_option(OptionalArg::flag("yaml"))?
+        .add_option(OptionalArg::flag("text"))?
+        .add_option(OptionalArg::flag("color"))?
+        .add_option(OptionalArg::flag("pager"))?
+        .add_group(format.clone())?
+        .add_group(ArgGroup::at_most_one("output", &["color", "pager"]))?;
+
+    assert!(parser.parse(&["--yaml"]).is_ok());
+    assert!(parser.parse(&["--text", "--pager"]).is_ok());
+
+    let args = parser.parse(&["--color", "--text"])?;
+    let sel = ArgSelector::from(&args);
+
+    assert_eq!(Some("text"), sel.get_choice("format"));
+    assert_eq!(Some("color"), sel.get_choice("output"));
+    assert_eq!(None, sel.get_choice("color"));
+    assert_eq!(
+        Err(MissingGroupOption {
+            group: format.clone()
+        }),
+        parser.parse(&["--color"])
+    );
+    assert_eq!(
+        Err(GroupConflict {
+            group: format.clone(),
+            first: "text",
+            second: "json"
+        }),
+        parser.parse(&["--text", "--color", "--json"])
+    );
+    assert_eq!(
+        "one of (--json | --yaml | --text) is required",
+        parser.parse(&[]).unwrap_err().to_string()
+    );
+    assert_eq!(
+        "--pager cannot be used with --color, only one of (--color | --pager) is allowed",
+        parser
+            .parse(&["--json", "--color", "--pager"])
+            .unwrap_err()
+            .to_string()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_groups_defaulted() -> Result<(), ArgParserError> {
+    use ArgParserError::*;
+
+    let format = ArgGroup::exactly_one("format", &["json", "style"]);
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::flag("json"))?
+        .add_option(OptionalArg::required_value("style").default_value("text"))?
+        .add_group(format.clone())?;
+
+    assert!(parser.parse(&["--json"]).is_ok());
+    assert!(parser.parse(&["--style=yaml"]).is_ok());
+    assert_eq!(Err(MissingGroupOption { group: format }), parser.parse(&[]));
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_ignored_options() -> Result<(), ArgParserError> {
+    use ArgParserError::*;
+    use ParsedArg::*;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .ignore_options(&["--legacy-noop", "-L"])
+        .add_option(OptionalArg::flag("foo"))?;
+
+    let args = parser.parse(&["--legacy-noop", "--foo", "-L=1", "--", "--legacy-noop"])?;
+
+    assert_eq!(
+        vec![
             Flag {
-                name: "bar",
-                value: true,
+                name: "foo",
+                value: true
             },
             Positional {
-                value: "false".to_string()
+                value: "--legacy-noop".to_string()
             }
-        ]),
-        parser.parse(&["--bar=true", "-b=false", "-b", "false"])
+        ],
+        *args
     );
+    assert_eq!(vec!["--legacy-noop", "-L=1"], args.ignored_options());
     assert_eq!(
-        Err(MissingOptionValue { name: "baz" }),
-        parser.parse(&["--baz"]),
+        Err(UnknownOption {
+            name: "legacy".to_string(),
+            suggestion: None
+        }),
+        parser.parse(&["--legacy"])
     );
+
+    Ok(())
+}
+
+#[test]
+fn test_unused_options() -> Result<(), ArgParserError> {
+    use super::MemoryEnv;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::flag("verbose").alias("v"))?
+        .add_option(OptionalArg::flag("cache"))?
+        .add_option(OptionalArg::required_value("user").env("MYAPP_USER"))?;
+
     assert_eq!(
-        Err(MissingAliasValue { alias: "B" }),
-        parser.parse(&["-B", "--foo"])
+        vec!["cache", "user", "verbose"],
+        parser.parse(&["foo"])?.unused_options()
     );
     assert_eq!(
-        Ok(vec![
-            RequiredValue {
-                name: "baz",
-                value: "123".to_string()
-            },
-            RequiredValue {
-                name: "baz",
-                value: "456".to_string()
-            }
-        ]),
-        parser.parse(&["--baz=123", "-B", "456"])
+        vec!["cache"],
+        parser.parse(&["-v", "--user", "foo"])?.unused_options()
     );
+
+    // Options that only the environment sets weren't used on the command line
+    let env = MemoryEnv::new().set("MYAPP_USER", "admin");
+
     assert_eq!(
-        Ok(vec![
-            OptionalValue {
-                name: "qux",
-                value: None
-            },
-            Positional {
-                value: "foo".to_string()
-            },
-            OptionalValue {
-                name: "qux",
-                value: Some("bar".to_string())
-            }
-        ]),
-        parser.parse(&["--qux", "foo", "--qux=bar"])
+        vec!["cache", "user"],
+        Resolver::new(&parser)
+            .env_source(env)
+            .resolve(&["-v"])?
+            .unused_options()
     );
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_rejected_item() -> Result<(), ArgParserError> {
+    use ArgParserError::*;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::required_value("ids").multiple().range(0..))?
+        .add_option(
+            OptionalArg::optional_value("tag")
+                .multiple()
+                .validator(|s| {
+                    if s.contains(' ') {
+                        Err("must not contain spaces".to_string())
+                    } else {
+                        Ok(())
+                    }
+                }),
+        )?;
+
     assert_eq!(
-        Err(UnknownAlias {
-            alias: "t".to_string()
+        Err(RejectedItem {
+            name: "ids",
+            index: 3,
+            value: "abc".to_string(),
+            reason: "not a number".to_string()
         }),
-        parser.parse(&["-btrue"])
+        parser.parse(&["--ids=1", "--tag=a", "--ids", "2", "--ids=abc"])
     );
     assert_eq!(
-        Err(InvalidAliasValue {
-            alias: "b",
-            value: "-foo".to_string()
+        Err(RejectedItem {
+            name: "tag",
+            index: 2,
+            value: "b c".to_string(),
+            reason: "must not contain spaces".to_string()
         }),
-        parser.parse(&["-b-foo"])
+        parser.parse(&["--tag=a", "--ids=1", "--tag=b c"])
     );
     assert_eq!(
-        Ok(vec![
-            Flag {
-                name: "bar",
-                value: true
+        "--ids item #3 ('abc'): not a number",
+        parser
+            .parse(&["--ids=1", "--ids=2", "--ids=abc"])
+            .unwrap_err()
+            .to_string()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_value_hint() -> Result<(), ArgParserError> {
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(
+            OptionalArg::required_value("output")
+                .alias("o")
+                .hint(ValueHint::Dir),
+        )?
+        .add_option(OptionalArg::required_value("user"))?
+        .add_positional(PositionalArg::named().hint(ValueHint::Glob("*.rs")))?
+        .add_positional(PositionalArg::rest().hint(ValueHint::File))?;
+
+    let hint = |args: &[&str]| parser.value_hint(&parser.parse_partial(args).unwrap());
+
+    assert_eq!(Some(ValueHint::Glob("*.rs")), hint(&["ma"]));
+    assert_eq!(Some(ValueHint::File), hint(&["main.rs", "a", ""]));
+    assert_eq!(Some(ValueHint::Dir), hint(&["-o", "ta"]));
+    assert_eq!(None, hint(&["--user", ""]));
+    assert_eq!(None, hint(&["main.rs", "--ou"]));
+    assert_eq!(Some(ValueHint::File), hint(&["main.rs", "--", "--ou"]));
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_lenient() -> Result<(), ArgParserError> {
+    use crate::{flag, pos, ArgSelector};
+    use ArgParserError::*;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::flag("verbose").alias("v"))?
+        .add_option(OptionalArg::required_value("out"))?
+        .add_option(OptionalArg::count("retries").default_value("3"))?
+        .add_positional(PositionalArg::named())?;
+
+    let (args, errors) = parser.parse_lenient(&["--bogus", "a.txt", "-v", "--verbose", "--out"]);
+
+    assert_eq!(
+        vec![
+            pos!("a.txt"),
+            flag!("verbose"),
+            ParsedArg::Count {
+                name: "retries",
+                value: 3
+            }
+        ],
+        Vec::from(args)
+    );
+    assert_eq!(
+        vec![
+            UnknownOption {
+                name: "bogus".to_string(),
+                suggestion: None
             },
-            RequiredValue {
-                name: "baz",
-                value: "q=123".to_string()
+            DuplicateOption { name: "verbose" },
+            MissingOptionValue { name: "out" }
+        ],
+        errors
+    );
+
+    let (args, errors) = parser.parse_lenient(&["--out", "-v", "b.txt"]);
+
+    assert_eq!(
+        vec![
+            flag!("verbose"),
+            pos!("b.txt"),
+            ParsedArg::Count {
+                name: "retries",
+                value: 3
+            }
+        ],
+        Vec::from(args)
+    );
+    assert_eq!(vec![MissingOptionValue { name: "out" }], errors);
+
+    let (args, errors) = parser.parse_lenient(&["c.txt"]);
+
+    assert_eq!(3, ArgSelector::from(&args).get_count("retries"));
+    assert!(errors.is_empty());
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::flag("json").conflicts_with("yaml"))?
+        .add_option(OptionalArg::flag("yaml"))?
+        .add_option(OptionalArg::required_value("key").requires("cert"))?
+        .add_option(OptionalArg::required_value("cert"))?
+        .add_option(OptionalArg::required_value("user").default_value("root"))?
+        .add_option(OptionalArg::required_value("token"))?
+        .add_positional(PositionalArg::named().name("host"))?
+        .add_group(ArgGroup::exactly_one("auth", &["key", "token"]))?;
+
+    let (args, errors) = parser.parse_lenient(&["--json", "--yaml", "--bogus", "--key=k"]);
+
+    assert_eq!(
+        Some("root"),
+        ArgSelector::from(&args)
+            .get_value("user")
+            .map(String::as_str)
+    );
+    assert_eq!(
+        vec![
+            UnknownOption {
+                name: "bogus".to_string(),
+                suggestion: None
             },
-            Flag {
-                name: "bar",
-                value: true
+            MissingArgs {
+                actual: 0,
+                expected: 1,
+                missing: vec!["host"]
             },
-            OptionalValue {
-                name: "qux",
-                value: Some("123".to_string())
+            ConflictingOptions {
+                first: "json",
+                second: "yaml"
+            },
+            MissingRequiredOption {
+                name: "key",
+                required: "cert"
             }
-        ]),
-        parser.parse(&["-bBq=123", "-bq=123"])
+        ],
+        errors
+    );
+    assert_eq!(
+        Some(&errors[1]),
+        parser
+            .parse(&["--json", "--yaml", "--key=k"])
+            .err()
+            .as_ref()
     );
 
+    // Once through the args, however many of them go wrong
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::required_value("out"))?
+        .add_positional(PositionalArg::rest())?;
+
+    let args = (0..10_000)
+        .map(|n| if n % 100 == 0 { "--bogus" } else { "x" })
+        .chain(["--out"])
+        .collect::<Vec<_>>();
+    let (parsed, errors) = parser.parse_lenient(&args);
+
+    assert_eq!(9_900, parsed.meta().positional);
+    assert_eq!(101, errors.len());
+    assert_eq!(Some(&MissingOptionValue { name: "out" }), errors.last());
+
     Ok(())
 }
 
 #[test]
-fn test_parse_options_first() -> Result<(), ArgParserError> {
+fn test_parse_partial() -> Result<(), ArgParserError> {
     use ArgParserError::*;
     use ParsedArg::*;
 
@@ -586,31 +6128,100 @@ fn test_parse_options_first() -> Result<(), ArgParserError> {
     parser
         .add_positional(PositionalArg::named())?
         .add_positional(PositionalArg::named())?
-        .add_positional(PositionalArg::rest())?
-        .add_option(OptionalArg::flag("foo"))?;
+        .add_option(OptionalArg::flag("foo"))?
+        .add_option(OptionalArg::required_value("bar").alias("b"))?;
 
     assert_eq!(
-        Err(MissingArgs {
-            actual: 1,
-            expected: 2
+        Ok(ParseState {
+            args: vec![],
+            current: "".to_string(),
+            pending_option: None,
+            next_positional: 0,
+            parse_options: true,
+            ..Default::default()
         }),
-        parser.parse(&["--foo", "foo"])
+        parser.parse_partial(&[])
     );
     assert_eq!(
-        Ok(vec![
-            Flag {
+        Ok(ParseState {
+            args: vec![Flag {
                 name: "foo",
                 value: true
-            },
-            Positional {
+            }],
+            current: "--ba".to_string(),
+            pending_option: None,
+            next_positional: 0,
+            parse_options: true,
+            ..Default::default()
+        }),
+        parser.parse_partial(&["--foo", "--ba"])
+    );
+    assert_eq!(
+        Ok(ParseState {
+            args: vec![],
+            current: "".to_string(),
+            pending_option: Some("bar"),
+            next_positional: 0,
+            parse_options: true,
+            ..Default::default()
+        }),
+        parser.parse_partial(&["-b", ""])
+    );
+    assert_eq!(
+        Ok(ParseState {
+            args: vec![Positional {
                 value: "foo".to_string()
-            },
-            Positional {
-                value: "--foo".to_string()
-            }
-        ]),
-        parser.parse(&["--foo", "foo", "--foo"])
+            }],
+            current: "--f".to_string(),
+            pending_option: None,
+            next_positional: 1,
+            parse_options: false,
+            ..Default::default()
+        }),
+        parser.parse_partial(&["foo", "--f"])
+    );
+    assert_eq!(
+        Err(UnknownOption {
+            name: "ba".to_string(),
+            suggestion: Some("bar")
+        }),
+        parser.parse_partial(&["--ba", "foo"])
     );
 
     Ok(())
 }
+
+#[test]
+fn test_parse_partial_redacted() -> Result<(), ArgParserError> {
+    let mut parser = ArgParser::default();
+
+    parser.add_option(
+        OptionalArg::required_value("token")
+            .alias("t")
+            .redact(|_| "***".to_string()),
+    )?;
+
+    let state = parser.parse_partial(&["--token", "secret1234", "-t", "secret5678"])?;
+
+    assert_eq!("secret5678", state.current);
+    assert!(!format!("{:?}", state).contains("secret"));
+
+    let state = parser.parse_partial(&["--token=secret1234"])?;
+
+    assert_eq!("--token=secret1234", state.current);
+    assert!(format!("{:?}", state).contains("--token=***"));
+
+    let state = parser.parse_partial(&["--tok"])?;
+
+    assert!(format!("{:?}", state).contains("--tok"));
+
+    Ok(())
+}
+
+#[test]
+fn test_send_sync() {
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    assert_send_sync::<ArgParser>();
+    assert_send_sync::<ParsedArgs>();
+}
@@ -1,15 +1,157 @@
-use super::{PositionalArg, PositionalArgKind};
+use super::{
+    validator, ArgParserError, PositionalArg, PositionalArgKind, RestPolicy, Validator, ValueHint,
+};
+use std::{ops::RangeBounds, path::Path};
 
 impl PositionalArg {
     pub fn named() -> Self {
-        Self {
-            kind: PositionalArgKind::Named,
-        }
+        Self::new(PositionalArgKind::Named)
+    }
+
+    pub fn optional() -> Self {
+        Self::new(PositionalArgKind::Optional)
     }
 
     pub fn rest() -> Self {
+        Self::new(PositionalArgKind::Rest)
+    }
+
+    pub fn name(mut self, name: &'static str) -> Self {
+        self.name = Some(name);
+        self
+    }
+
+    pub fn default_value(mut self, value: &'static str) -> Self {
+        self.default = Some(value);
+        self
+    }
+
+    pub fn rest_policy(mut self, policy: RestPolicy) -> Self {
+        self.rest_policy = policy;
+        self
+    }
+
+    pub fn hint(mut self, hint: ValueHint) -> Self {
+        self.hint = Some(hint);
+        self
+    }
+
+    pub fn validator(
+        mut self,
+        f: impl Fn(&str) -> Result<(), String> + Send + Sync + 'static,
+    ) -> Self {
+        self.validator = Some(Validator::new(f));
+        self
+    }
+
+    pub fn range(mut self, range: impl RangeBounds<i64>) -> Self {
+        self.range = validator::inclusive_range(range);
+        self
+    }
+
+    pub fn choices(mut self, choices: &[&'static str]) -> Self {
+        self.choices = choices.to_vec();
+        self
+    }
+
+    // Only rest args take several values, so `add_positional` rejects a minimum on any other one
+    pub fn min(mut self, min: usize) -> Self {
+        self.min = min;
+        self
+    }
+
+    pub fn must_exist(mut self) -> Self {
+        self.must_exist = true;
+        self
+    }
+
+    pub(crate) fn check_value(&self, value: &str) -> Result<(), String> {
+        self.check(value, self.must_exist)
+    }
+
+    // Same order as for options: the built-in checks first, the custom validator last
+    fn check(&self, value: &str, must_exist: bool) -> Result<(), String> {
+        if let Some(range) = &self.range {
+            validator::check_range(range, value)?;
+        }
+
+        validator::check_choices(&self.choices, value)?;
+
+        if must_exist && !Path::new(value).exists() {
+            return Err("no such file or directory".to_string());
+        }
+
+        match &self.validator {
+            Some(validator) => validator.validate(value),
+            None => Ok(()),
+        }
+    }
+
+    // Checked by `add_positional` and `add_signature`, with `idx` being where the arg goes
+    pub(crate) fn check_settings(&self, idx: usize) -> Result<(), ArgParserError> {
+        if self.kind != PositionalArgKind::Rest && self.rest_policy != RestPolicy::Lazy {
+            return Err(ArgParserError::InvalidPositionalSetting {
+                position: idx + 1,
+                setting: "rest_policy",
+                reason: "only a rest arg has one",
+            });
+        }
+
+        if self.kind != PositionalArgKind::Rest && self.min != 0 {
+            return Err(ArgParserError::InvalidPositionalSetting {
+                position: idx + 1,
+                setting: "min",
+                reason: "only a rest arg takes several values",
+            });
+        }
+
+        if self.range.as_ref().is_some_and(|range| range.is_empty()) {
+            return Err(ArgParserError::InvalidPositionalSetting {
+                position: idx + 1,
+                setting: "range",
+                reason: "no number is in it",
+            });
+        }
+
+        // Whether a file exists can change by the time the default is used, so that is left to
+        // the parse
+        if let Some(value) = self.default {
+            self.check(value, false)
+                .map_err(|reason| ArgParserError::RejectedPositional {
+                    position: idx + 1,
+                    value: value.to_string(),
+                    reason,
+                })?;
+        }
+
+        Ok(())
+    }
+
+    // Can't skip over an absent optional positional arg without a default
+    pub(crate) fn defaults(layout: &[Self], given: usize) -> Vec<(usize, &'static str)> {
+        layout
+            .iter()
+            .enumerate()
+            .skip(given)
+            .map_while(|(idx, arg)| match (&arg.kind, arg.default) {
+                (PositionalArgKind::Optional, Some(value)) => Some((idx, value)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn new(kind: PositionalArgKind) -> Self {
         Self {
-            kind: PositionalArgKind::Rest,
+            kind,
+            validator: None,
+            name: None,
+            hint: None,
+            default: None,
+            rest_policy: RestPolicy::Lazy,
+            range: None,
+            choices: Vec::new(),
+            must_exist: false,
+            min: 0,
         }
     }
 }
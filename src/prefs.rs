@@ -0,0 +1,72 @@
+use super::{ArgParser, ArgParserError, ArgSelector, OptionalArg};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ExecutionPrefs {
+    pub dry_run: bool,
+    pub assume_yes: bool,
+}
+
+impl ExecutionPrefs {
+    pub fn should_mutate(&self) -> bool {
+        !self.dry_run
+    }
+
+    // `ask` is only called when the answer isn't given on the command line already
+    pub fn confirm(&self, ask: impl FnOnce() -> bool) -> bool {
+        self.assume_yes || ask()
+    }
+}
+
+impl ArgParser {
+    pub fn add_execution_options(&mut self) -> Result<&mut Self, ArgParserError> {
+        self.add_option(
+            OptionalArg::flag("dry-run").help("Show what would be done without changing anything"),
+        )?
+        .add_option(
+            OptionalArg::flag("yes")
+                .alias("y")
+                .help("Answer yes to all confirmations"),
+        )
+    }
+}
+
+impl<P> ArgSelector<'_, P> {
+    pub fn get_execution_prefs(&self) -> ExecutionPrefs {
+        ExecutionPrefs {
+            dry_run: self.get_flag("dry-run", false),
+            assume_yes: self.get_flag("yes", false),
+        }
+    }
+}
+
+#[test]
+fn test_execution_prefs() -> Result<(), ArgParserError> {
+    let mut parser = ArgParser::default();
+
+    parser.add_execution_options()?;
+
+    let args = parser.parse(&["--dry-run"])?;
+    let prefs = ArgSelector::from(&args).get_execution_prefs();
+
+    assert_eq!(
+        ExecutionPrefs {
+            dry_run: true,
+            assume_yes: false
+        },
+        prefs
+    );
+    assert!(!prefs.should_mutate());
+    assert!(!prefs.confirm(|| false));
+
+    let args = parser.parse(&["-y"])?;
+    let prefs = ArgSelector::from(&args).get_execution_prefs();
+
+    assert!(prefs.should_mutate());
+    assert!(prefs.confirm(|| panic!("must not ask")));
+    assert_eq!(
+        ExecutionPrefs::default(),
+        ArgSelector::from(&parser.parse(&[])?).get_execution_prefs()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,225 @@
+use super::{ArgParser, ArgParserError, OptionalArg, OptionalArgKind};
+use std::{fmt, sync::Arc};
+
+// Shared between threads along with the parser and its results, so it has to be thread-safe
+type RedactorFn = dyn Fn(&str) -> String + Send + Sync;
+
+#[derive(Clone)]
+pub struct Redactor(Arc<RedactorFn>);
+
+impl Redactor {
+    pub fn new(f: impl Fn(&str) -> String + Send + Sync + 'static) -> Self {
+        Self(Arc::new(f))
+    }
+
+    pub fn redact(&self, value: &str) -> String {
+        (self.0)(value)
+    }
+}
+
+impl fmt::Debug for Redactor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Redactor")
+    }
+}
+
+impl PartialEq for Redactor {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl ArgParser {
+    // Values in errors are redacted like in `ParsedArgs`, since error messages end up in logs too
+    pub(crate) fn redact_error(&self, mut error: ArgParserError) -> ArgParserError {
+        use ArgParserError::*;
+
+        let (name, value) = match &mut error {
+            InvalidOptionValue { name, value }
+            | MissingOptionEquals { name, value }
+            | AttachedOptionValue { name, value }
+            | RejectedValue { name, value, .. }
+            | RejectedItem { name, value, .. }
+            | InvalidEnvValue { name, value, .. } => (*name, value),
+            InvalidAliasValue { alias, value }
+            | MissingAliasEquals { alias, value }
+            | AttachedAliasValue { alias, value } => {
+                (self.aliases.get(alias).copied().unwrap_or(alias), value)
+            }
+            _ => return error,
+        };
+
+        if let Some(redactor) = self
+            .options
+            .get(name)
+            .and_then(|option| option.redactor.as_ref())
+        {
+            *value = redactor.redact(value);
+        }
+
+        error
+    }
+
+    // The args as given, with the values of redacted options replaced, for when they are handed
+    // over along with an error. Up to the terminator, options are found the way the parse finds
+    // them, but without normalizing their names.
+    pub(crate) fn redact_args(&self, args: &[&str]) -> Vec<String> {
+        let mut redacted = Vec::with_capacity(args.len());
+        // The option whose values the next args are, and how many it may still take
+        let mut pending: Option<(&OptionalArg, &Redactor, usize)> = None;
+        let mut args = args.iter();
+
+        while let Some(&arg) = args.next() {
+            if self.terminator == Some(arg) {
+                redacted.push(arg.to_string());
+                redacted.extend(args.map(|arg| arg.to_string()));
+                break;
+            }
+
+            let parsed = self.parse_option(arg).ok().flatten();
+
+            if let Some((option, redactor, left)) = pending.take().filter(|_| parsed.is_none()) {
+                if option.value_terminator == Some(arg) {
+                    redacted.push(arg.to_string());
+                } else {
+                    redacted.push(redactor.redact(arg));
+                    pending = Some((option, redactor, left - 1)).filter(|&(.., left)| left > 0);
+                }
+
+                continue;
+            }
+
+            let Some((name_or_alias, mut value)) = parsed else {
+                redacted.push(arg.to_string());
+                continue;
+            };
+
+            let Ok((_, mut option, alias)) = self.resolve(name_or_alias) else {
+                redacted.push(arg.to_string());
+                continue;
+            };
+
+            // In a cluster like `-vt`, the value belongs to the first alias that takes one
+            while alias.is_some()
+                && matches!(option.kind, OptionalArgKind::Flag | OptionalArgKind::Count)
+                && !value.is_empty()
+                && !value.starts_with('=')
+            {
+                let (next, rest) = value.split_at(value.chars().next().map_or(0, char::len_utf8));
+
+                match self.resolve(next) {
+                    Ok((_, next, _)) => (option, value) = (next, rest),
+                    Err(_) => break,
+                }
+            }
+
+            let Some(redactor) = option.redactor.as_ref() else {
+                redacted.push(arg.to_string());
+                continue;
+            };
+
+            let value = value.strip_prefix('=').unwrap_or(value);
+            // Flags and counts take no value, and an optional one can only ever be attached
+            let takes = match option.kind {
+                OptionalArgKind::RequiredValue => 1,
+                OptionalArgKind::Values => *option.arity.end(),
+                _ => 0,
+            };
+
+            if value.is_empty() {
+                redacted.push(arg.to_string());
+                pending = Some((option, redactor, takes)).filter(|&(.., left)| left > 0);
+            } else {
+                let given = &arg[..arg.len() - value.len()];
+
+                redacted.push(format!("{}{}", given, redactor.redact(value)));
+
+                if option.kind == OptionalArgKind::Values {
+                    pending = Some((option, redactor, takes - 1)).filter(|&(.., left)| left > 0);
+                }
+            }
+        }
+
+        redacted
+    }
+}
+
+#[test]
+fn test_redactor() {
+    let r = Redactor::new(|s| {
+        let tail = s.len().saturating_sub(4);
+
+        format!("***{}", &s[tail..])
+    });
+
+    assert_eq!("***6789", r.redact("123456789"));
+    assert_eq!("***12", r.redact("12"));
+    assert_eq!(r, r.clone());
+}
+
+#[test]
+fn test_redact_error() -> Result<(), ArgParserError> {
+    use super::{OptionalArg, ParsedArg, ValueStyle};
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(
+            OptionalArg::required_value("token")
+                .alias("t")
+                .choices(&["abc"])
+                .redact(|_| "***".to_string()),
+        )?
+        .add_option(
+            OptionalArg::required_value("key")
+                .alias("k")
+                .value_style(ValueStyle::EqualsOnly)
+                .redact(|_| "***".to_string()),
+        )?
+        .add_option(OptionalArg::required_value("user").choices(&["admin"]))?;
+
+    let rejected = "--token cannot accept '***': must be one of abc";
+
+    assert_eq!(
+        rejected,
+        parser
+            .parse(&["--token", "hunter2"])
+            .unwrap_err()
+            .to_string()
+    );
+    assert_eq!(
+        "-k cannot accept '***' without '=', use -k=***",
+        parser.parse(&["-k", "hunter2"]).unwrap_err().to_string()
+    );
+    assert_eq!(
+        "--user cannot accept 'root': must be one of admin",
+        parser.parse(&["--user=root"]).unwrap_err().to_string()
+    );
+    assert_eq!(
+        vec![rejected.to_string()],
+        parser
+            .parse_report(&["-thunter2"])
+            .errors
+            .iter()
+            .map(|error| error.to_string())
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        rejected,
+        parser
+            .validate_value("t", "hunter2")
+            .unwrap_err()
+            .to_string()
+    );
+
+    // The values in `ParsedArgs` themselves stay intact
+    assert_eq!(
+        ParsedArg::RequiredValue {
+            name: "token",
+            value: "abc".to_string()
+        },
+        parser.parse(&["--token=abc"])?[0]
+    );
+
+    Ok(())
+}
@@ -0,0 +1,101 @@
+use super::{ArgParser, ArgParserError, ArgVisitor, ParsedArgs, Resolver};
+
+// Whatever could be parsed along with every error on the way, in the order they were found
+#[derive(Debug, Default, PartialEq)]
+pub struct ParseReport {
+    pub args: ParsedArgs,
+    pub errors: Vec<ArgParserError>,
+}
+
+impl ParseReport {
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    pub fn into_result(self) -> Result<ParsedArgs, Vec<ArgParserError>> {
+        if self.errors.is_empty() {
+            Ok(self.args)
+        } else {
+            Err(self.errors)
+        }
+    }
+}
+
+// Only the errors matter here, the args come with the result
+struct Errors<'a>(&'a mut Vec<ArgParserError>);
+
+impl ArgVisitor for Errors<'_> {
+    fn on_error(&mut self, error: ArgParserError) {
+        self.0.push(error);
+    }
+}
+
+impl ArgParser {
+    pub fn parse_report(&self, args: &[&str]) -> ParseReport {
+        Resolver::new(self).report(args)
+    }
+}
+
+impl Resolver<'_> {
+    // Like `resolve` but going on past errors, see `ArgParser::parse_lenient`
+    pub fn report(&self, args: &[&str]) -> ParseReport {
+        let mut errors = vec![];
+
+        match self
+            .parser
+            .parse_with(args, self, Some(&mut Errors(&mut errors)))
+        {
+            Ok(args) => ParseReport { args, errors },
+            Err(error) => {
+                errors.push(error);
+
+                ParseReport {
+                    args: ParsedArgs::default(),
+                    errors,
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_parse_report() -> Result<(), ArgParserError> {
+    use super::{ArgSelector, OptionalArg, PositionalArg};
+    use ArgParserError::*;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::required_value("port").range(1..=65535))?
+        .add_option(OptionalArg::flag("json").conflicts_with("yaml"))?
+        .add_option(OptionalArg::flag("yaml"))?
+        .add_positional(PositionalArg::named().name("host"))?;
+
+    let args = ["--port=0", "--json", "--yaml", "--bogus"];
+    let report = parser.parse_report(&args);
+
+    assert!(!report.is_ok());
+    assert!(matches!(
+        &report.errors[..],
+        [
+            RejectedValue { name: "port", .. },
+            UnknownOption { .. },
+            MissingArgs { .. },
+            ConflictingOptions { .. }
+        ]
+    ));
+    assert_eq!(Some(&report.errors[0]), parser.parse(&args).err().as_ref());
+    assert_eq!(Err(report.errors), parser.parse_report(&args).into_result());
+
+    let report = Resolver::new(&parser)
+        .without_env()
+        .report(&["--port=80", "example.org"]);
+
+    assert!(report.is_ok());
+    assert_eq!(
+        Some(&"80".to_string()),
+        ArgSelector::from(&report.args).get_value("port")
+    );
+
+    Ok(())
+}
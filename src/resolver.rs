@@ -0,0 +1,164 @@
+use super::{ArgParser, ArgParserError, EnvSource, ParsedArgs, ProcessEnv};
+use std::ffi::OsStr;
+
+pub struct Resolver<'a> {
+    pub(crate) parser: &'a ArgParser,
+    pub(crate) env: Option<Box<dyn EnvSource + 'a>>,
+    #[cfg(feature = "config")]
+    pub(crate) config: bool,
+    pub(crate) defaults: bool,
+    // Set by `parse_os`, which parses their lossy conversion
+    pub(crate) os_args: Option<&'a [&'a OsStr]>,
+}
+
+impl<'a> Resolver<'a> {
+    pub fn new(parser: &'a ArgParser) -> Self {
+        Self {
+            parser,
+            env: Some(Box::new(ProcessEnv)),
+            #[cfg(feature = "config")]
+            config: true,
+            defaults: true,
+            os_args: None,
+        }
+    }
+
+    pub fn env(mut self, env: impl Fn(&str) -> Option<String> + 'a) -> Self {
+        self.env = Some(Box::new(env));
+        self
+    }
+
+    pub fn env_source(mut self, env: impl EnvSource + 'a) -> Self {
+        self.env = Some(Box::new(env));
+        self
+    }
+
+    pub fn without_env(mut self) -> Self {
+        self.env = None;
+        self
+    }
+
+    #[cfg(feature = "config")]
+    pub fn without_config(mut self) -> Self {
+        self.config = false;
+        self
+    }
+
+    pub fn without_defaults(mut self) -> Self {
+        self.defaults = false;
+        self
+    }
+
+    pub fn resolve(&self, args: &[&str]) -> Result<ParsedArgs, ArgParserError> {
+        self.parser.parse_with(args, self, None)
+    }
+}
+
+#[test]
+fn test_resolver() -> Result<(), ArgParserError> {
+    use super::{ArgSelector, OptionalArg, ValueSource};
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(
+            OptionalArg::required_value("region")
+                .env("MYAPP_REGION")
+                .default_value("eu-west-1"),
+        )?
+        .add_option(OptionalArg::required_value("zone").default_value_if("region", None, "a"))?
+        .add_option(OptionalArg::count("retries").default_value("3"))?;
+
+    let env = |var: &str| (var == "MYAPP_REGION").then(|| "us-east-1".to_string());
+
+    let args = Resolver::new(&parser).env(env).resolve(&["--zone=b"])?;
+    let sel = ArgSelector::from(&args);
+
+    assert_eq!(Some(&"us-east-1".to_string()), sel.get_value("region"));
+    assert_eq!(Some(&"b".to_string()), sel.get_value("zone"));
+    assert_eq!(3, sel.get_count("retries"));
+    assert_eq!(
+        Some(ValueSource::Env("MYAPP_REGION".to_string())),
+        args.source("region")
+    );
+    assert_eq!(Some(ValueSource::CommandLine), args.source("zone"));
+    assert_eq!(Some(ValueSource::Default), args.source("retries"));
+
+    let args = Resolver::new(&parser).env(env).without_env().resolve(&[])?;
+    let sel = ArgSelector::from(&args);
+
+    assert_eq!(Some(&"eu-west-1".to_string()), sel.get_value("region"));
+    assert_eq!(Some(&"a".to_string()), sel.get_value("zone"));
+    assert_eq!(Some(ValueSource::Default), args.source("region"));
+    assert_eq!(Some(ValueSource::Default), args.source("zone"));
+
+    let args = Resolver::new(&parser)
+        .without_env()
+        .without_defaults()
+        .resolve(&[])?;
+
+    assert!(args.is_empty());
+    assert_eq!(None, args.source("region"));
+
+    let args = Resolver::new(&parser)
+        .env(env)
+        .resolve(&["--zone=b", "x"])?;
+    let sel = ArgSelector::from(&args);
+
+    assert_eq!(Some(ValueSource::CommandLine), sel.get_source("zone"));
+    assert_eq!(
+        vec![
+            "zone: command line",
+            "x: -",
+            "region: environment variable $MYAPP_REGION",
+            "retries: default"
+        ],
+        args.with_sources()
+            .into_iter()
+            .map(|(arg, source)| format!(
+                "{}: {}",
+                arg.name().unwrap_or("x"),
+                source.map_or("-".to_string(), |source| source.to_string())
+            ))
+            .collect::<Vec<_>>()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_resolver_meta() -> Result<(), ArgParserError> {
+    use super::{OptionalArg, ParseMeta, PositionalArg, RestPolicy};
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::count("verbose").alias("v"))?
+        .add_option(OptionalArg::required_value("token").env("MYAPP_TOKEN"))?
+        .add_option(OptionalArg::required_value("user").default_value("root"))?
+        .add_positional(PositionalArg::rest())?;
+
+    let env = |var: &str| (var == "MYAPP_TOKEN").then(|| "secret".to_string());
+
+    assert_eq!(
+        &ParseMeta {
+            options: 1,
+            positional: 2,
+            bytes: 9,
+            env: true,
+            config: false,
+            rest_policy: RestPolicy::Lazy,
+            eager: None
+        },
+        Resolver::new(&parser)
+            .env(env)
+            .resolve(&["-vv", "foo", "bar"])?
+            .meta()
+    );
+    assert_eq!(
+        &ParseMeta::default(),
+        Resolver::new(&parser).without_env().resolve(&[])?.meta()
+    );
+
+    Ok(())
+}
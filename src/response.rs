@@ -0,0 +1,230 @@
+use super::{split::split_shell, ArgParser, ArgParserError};
+use std::{fs, io::Read};
+
+impl ArgParser {
+    pub fn response_files(&mut self, enable: bool) -> &mut Self {
+        self.response_files = enable;
+        self
+    }
+
+    pub fn args_from_stdin(&mut self, enable: bool) -> &mut Self {
+        self.args_from_stdin = enable;
+        self
+    }
+
+    pub fn max_response_file_depth(&mut self, depth: usize) -> &mut Self {
+        self.max_response_file_depth = depth;
+        self
+    }
+
+    // `None` when neither response files nor stdin are enabled, so the args are used as they are
+    pub(crate) fn expand_response_files(
+        &self,
+        args: &[&str],
+        stdin: &mut dyn Read,
+    ) -> Result<Option<Vec<String>>, ArgParserError> {
+        let mut read = args.iter().map(|arg| arg.len()).sum();
+
+        self.check_input_bytes(read)?;
+
+        if !self.response_files && !self.args_from_stdin {
+            self.check_arg_count(args.len())?;
+
+            return Ok(None);
+        }
+
+        let mut expanded = vec![];
+
+        self.expand_into(
+            args,
+            &mut vec![],
+            &mut expanded,
+            &mut Some(stdin),
+            &mut read,
+        )?;
+
+        Ok(Some(expanded))
+    }
+
+    fn expand_into(
+        &self,
+        args: &[impl AsRef<str>],
+        files: &mut Vec<String>,
+        expanded: &mut Vec<String>,
+        stdin: &mut Option<&mut dyn Read>,
+        read: &mut usize,
+    ) -> Result<(), ArgParserError> {
+        use ArgParserError::*;
+
+        for (idx, arg) in args.iter().map(AsRef::as_ref).enumerate() {
+            if self.terminator == Some(arg) {
+                expanded.extend(args[idx..].iter().map(|arg| arg.as_ref().to_string()));
+                self.check_arg_count(expanded.len())?;
+                break;
+            }
+
+            if arg == "@-" && self.args_from_stdin {
+                let invalid = |reason: &str| InvalidResponseFile {
+                    path: "-".to_string(),
+                    reason: reason.to_string(),
+                };
+
+                let source = self
+                    .read_limited(
+                        stdin
+                            .take()
+                            .ok_or_else(|| invalid("stdin can only be read once"))?,
+                        read,
+                    )?
+                    .map_err(|err| invalid(&err.to_string()))?;
+
+                expanded.extend(
+                    source
+                        .lines()
+                        .filter(|line| !line.is_empty())
+                        .map(String::from),
+                );
+                self.check_arg_count(expanded.len())?;
+                continue;
+            }
+
+            let Some(path) = arg
+                .strip_prefix('@')
+                .filter(|path| !path.is_empty() && self.response_files)
+            else {
+                expanded.push(arg.to_string());
+                self.check_arg_count(expanded.len())?;
+                continue;
+            };
+
+            if files.len() >= self.max_response_file_depth || files.iter().any(|file| file == path)
+            {
+                return Err(ResponseFileTooDeep {
+                    path: path.to_string(),
+                    depth: files.len() + 1,
+                });
+            }
+
+            let invalid = |reason: String| InvalidResponseFile {
+                path: path.to_string(),
+                reason,
+            };
+
+            let source = fs::File::open(path)
+                .map_err(|err| invalid(err.to_string()))
+                .and_then(|mut file| self.read_limited(&mut file, read))?
+                .map_err(|err| invalid(err.to_string()))?;
+            let file_args = split_shell(&source).map_err(invalid)?;
+
+            files.push(path.to_string());
+            self.expand_into(&file_args, files, expanded, stdin, read)?;
+            files.pop();
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn test_parse_response_files() -> Result<(), ArgParserError> {
+    use super::{ArgSelector, OptionalArg, PositionalArg};
+
+    let dir = std::env::temp_dir().join(format!("rs-args-response-{}", std::process::id()));
+    let path = |name: &str| dir.join(name).display().to_string();
+
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        path("main.rsp"),
+        format!("--jobs 4\n@{}\nmain.o", path("libs.rsp")),
+    )
+    .unwrap();
+    fs::write(path("libs.rsp"), "'lib one.a' lib2.a").unwrap();
+    fs::write(path("loop.rsp"), format!("@{}", path("loop.rsp"))).unwrap();
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::required_value("jobs"))?
+        .add_positional(PositionalArg::rest())?;
+
+    let main = format!("@{}", path("main.rsp"));
+
+    assert_eq!(
+        vec![&main],
+        ArgSelector::from(&parser.parse(&[&main])?).get_positional()
+    );
+
+    parser.response_files(true);
+
+    let args = parser.parse(&[&main, "@", "--", &main])?;
+    let sel = ArgSelector::from(&args);
+
+    assert_eq!(Some(&"4".to_string()), sel.get_value("jobs"));
+    assert_eq!(
+        vec![&main[..], "@", "--", &main],
+        args.raw_args()
+            .iter()
+            .map(|arg| arg.to_str().unwrap())
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        vec!["lib one.a", "lib2.a", "main.o", "@", &main],
+        sel.get_positional()
+    );
+
+    assert_eq!(
+        Err(ArgParserError::ResponseFileTooDeep {
+            path: path("loop.rsp"),
+            depth: 2
+        }),
+        parser
+            .parse(&[&format!("@{}", path("loop.rsp"))])
+            .map(|_| ())
+    );
+
+    parser.max_response_file_depth(1);
+
+    assert_eq!(
+        Err(ArgParserError::ResponseFileTooDeep {
+            path: path("libs.rsp"),
+            depth: 2
+        }),
+        parser.parse(&[&main]).map(|_| ())
+    );
+    assert!(matches!(
+        parser.parse(&[&format!("@{}", path("missing.rsp"))]),
+        Err(ArgParserError::InvalidResponseFile { .. })
+    ));
+
+    fs::remove_dir_all(&dir).unwrap();
+
+    Ok(())
+}
+
+#[test]
+fn test_expand_args_from_stdin() -> Result<(), ArgParserError> {
+    let mut parser = ArgParser::default();
+
+    let expand = |parser: &ArgParser, args: &[&str]| {
+        parser.expand_response_files(args, &mut "src/a b.rs\r\n\nsrc/c.rs\n".as_bytes())
+    };
+
+    assert_eq!(None, expand(&parser, &["-x", "@-"])?);
+
+    parser.args_from_stdin(true);
+
+    assert_eq!(
+        vec!["-x", "src/a b.rs", "src/c.rs", "-y", "--", "@-"],
+        expand(&parser, &["-x", "@-", "-y", "--", "@-"])?.unwrap()
+    );
+    assert_eq!(vec!["@foo"], expand(&parser, &["@foo"])?.unwrap());
+    assert_eq!(
+        Err(ArgParserError::InvalidResponseFile {
+            path: "-".to_string(),
+            reason: "stdin can only be read once".to_string()
+        }),
+        expand(&parser, &["@-", "@-"])
+    );
+
+    Ok(())
+}
@@ -0,0 +1,314 @@
+use super::{ArgParser, ArgParserError, PositionalArg};
+use std::fmt::Write;
+
+// FNV-1a, because it is trivial and its output doesn't depend on the Rust version
+fn fnv1a(data: &str) -> u64 {
+    data.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+impl ArgParser {
+    // Completions show help texts too, so they go stale along with anything else in the schema
+    pub fn schema_hash(&self) -> String {
+        format!("{:016x}", fnv1a(&self.canonical_schema()))
+    }
+
+    // Everything that defines the command line, with collections sorted wherever their order
+    // doesn't matter for parsing; closures can only be compared for being present
+    pub fn canonical_schema(&self) -> String {
+        fn sorted<T: Ord + Clone>(items: &[T]) -> Vec<T> {
+            let mut items = items.to_vec();
+            items.sort();
+            items
+        }
+
+        let mut schema = String::new();
+
+        let _ = writeln!(
+            schema,
+            "parser {:?} prefixes={:?} unicode_aliases={} abbreviations={} \
+             ignore_case={} normalize_underscores={} allow_unknown={} stop_at_unknown={} \
+             capture_rest={} terminator={:?} keep_terminator={} negative_numbers={} \
+             plus_flags={} slash_options={} colon_separator={} value_style={:?} \
+             split_style={:?} env_prefix={:?} strict_positional={} \
+             exit_codes={:?} suggestion_distance={}",
+            self.mode,
+            (self.long_prefix, self.short_prefix),
+            self.unicode_aliases,
+            self.abbreviations,
+            self.ignore_case,
+            self.normalize_underscores,
+            self.allow_unknown,
+            self.stop_at_unknown,
+            self.capture_rest,
+            self.terminator,
+            self.keep_terminator,
+            self.negative_numbers,
+            self.plus_flags,
+            self.slash_options,
+            self.colon_separator,
+            self.value_style,
+            self.split_style,
+            self.env_prefix,
+            self.strict_positional,
+            self.exit_codes,
+            self.suggestion_distance,
+        );
+        let _ = writeln!(
+            schema,
+            "input response_files={} args_from_stdin={} max_depth={} max_args={:?} \
+             max_input_bytes={:?}",
+            self.response_files,
+            self.args_from_stdin,
+            self.max_response_file_depth,
+            self.max_args,
+            self.max_input_bytes,
+        );
+        let _ = writeln!(
+            schema,
+            "names ignored={:?} reserved={:?}",
+            sorted(&self.ignored_options),
+            sorted(&self.reserved),
+        );
+
+        #[cfg(feature = "config")]
+        let _ = writeln!(
+            schema,
+            "config deny_unknown_keys={}",
+            self.deny_unknown_config_keys
+        );
+
+        let mut names = self.options.keys().copied().collect::<Vec<_>>();
+
+        names.sort_unstable();
+
+        for name in names {
+            let option = &self.options[name];
+
+            let _ = writeln!(
+                schema,
+                "option --{} {:?} aliases={:?} alias_help={:?} arity={:?} multiple={} \
+                 negatable={} plus_negatable={} hidden={} value_style={:?} \
+                 duplicates={:?} occurrences={:?} value_name={:?} \
+                 delimiter={:?} value_terminator={:?} eager={} \
+                 allow_hyphen_values={} validator={} range={:?} redactor={} choices={:?} \
+                 or_other={} encoding={:?} key_values={:?} hint={:?} default={:?} default_missing={:?} \
+                 conditional_defaults={:?} requires={:?} conflicts={:?} \
+                 env={:?} env_only={} config_key={:?} mounted={:?} help={:?}",
+                name,
+                option.kind,
+                sorted(&option.aliases),
+                sorted(&option.alias_help),
+                option.arity,
+                option.multiple,
+                option.negatable,
+                option.plus_negatable,
+                option.hidden,
+                option.value_style,
+                option.duplicates,
+                option.occurrences,
+                option.value_name,
+                option.delimiter,
+                option.value_terminator,
+                option.eager,
+                option.allow_hyphen_values,
+                option.validator.is_some(),
+                option.range,
+                option.redactor.is_some(),
+                sorted(&option.choices),
+                option.or_other,
+                option.encoding,
+                option.key_values,
+                option.hint,
+                option.default,
+                option.default_missing,
+                sorted(&option.conditional_defaults),
+                sorted(&option.requires),
+                sorted(&option.conflicts),
+                option.env,
+                option.env_only,
+                option.config_key,
+                self.mounted.get(name).map(|mounted| (mounted.prefix, mounted.name)),
+                option.help,
+            );
+        }
+
+        for arg in &self.positional {
+            let _ = writeln!(schema, "positional {}", canonical_positional(arg));
+        }
+
+        for signature in &self.signatures {
+            let args = signature
+                .iter()
+                .map(canonical_positional)
+                .collect::<Vec<_>>();
+            let _ = writeln!(schema, "signature {:?}", args);
+        }
+
+        let mut groups = self
+            .groups
+            .iter()
+            .map(|group| {
+                format!(
+                    "group {} {:?} {:?}",
+                    group.name,
+                    group.kind,
+                    sorted(&group.options)
+                )
+            })
+            .collect::<Vec<_>>();
+
+        groups.sort_unstable();
+
+        for group in groups {
+            let _ = writeln!(schema, "{}", group);
+        }
+
+        schema
+    }
+
+    pub fn same_schema(&self, other: &ArgParser) -> bool {
+        self.canonical_schema() == other.canonical_schema()
+    }
+
+    pub fn check_schema_hash(&self, hash: &str) -> Result<(), ArgParserError> {
+        let expected = self.schema_hash();
+
+        if hash != expected {
+            return Err(ArgParserError::StaleSchema {
+                expected,
+                actual: hash.to_string(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+pub(crate) fn canonical_positional(arg: &PositionalArg) -> String {
+    format!(
+        "{:?} {:?} default={:?} hint={:?} rest_policy={:?} validator={} range={:?} choices={:?} \
+         must_exist={} min={}",
+        arg.kind,
+        arg.name,
+        arg.default,
+        arg.hint,
+        arg.rest_policy,
+        arg.validator.is_some(),
+        arg.range,
+        arg.choices,
+        arg.must_exist,
+        arg.min
+    )
+}
+
+#[test]
+fn test_fnv1a() {
+    assert_eq!(0xcbf29ce484222325, fnv1a(""));
+    assert_eq!(0xaf63dc4c8601ec8c, fnv1a("a"));
+}
+
+#[test]
+fn test_schema_hash() -> Result<(), ArgParserError> {
+    use super::OptionalArg;
+
+    let mut first = ArgParser::default();
+    let mut second = ArgParser::default();
+
+    first
+        .add_option(OptionalArg::flag("json").help("Print JSON"))?
+        .add_option(OptionalArg::required_value("user").alias("u"))?;
+    second
+        .add_option(OptionalArg::required_value("user").alias("u"))?
+        .add_option(OptionalArg::flag("json").help("Print JSON"))?;
+
+    let hash = first.schema_hash();
+
+    assert_eq!(16, hash.len());
+    assert_eq!(hash, second.schema_hash());
+    assert_eq!(Ok(()), second.check_schema_hash(&hash));
+
+    // Anything the canonical schema has counts, not only what tells options apart
+    for option in [
+        OptionalArg::flag("json"),
+        OptionalArg::flag("json").help("Print JSON").hidden(),
+        OptionalArg::flag("json").help("Print JSON").env("JSON"),
+    ] {
+        let mut other = ArgParser::default();
+
+        other
+            .add_option(option)?
+            .add_option(OptionalArg::required_value("user").alias("u"))?;
+
+        assert_ne!(hash, other.schema_hash());
+    }
+
+    // Completions show the placeholder, e.g. `--user <NAME>`
+    let mut other = ArgParser::default();
+
+    other
+        .add_option(OptionalArg::flag("json").help("Print JSON"))?
+        .add_option(
+            OptionalArg::required_value("user")
+                .alias("u")
+                .value_name("NAME"),
+        )?;
+
+    assert_ne!(hash, other.schema_hash());
+
+    second.add_option(OptionalArg::flag("yaml"))?;
+
+    assert_eq!(
+        Err(ArgParserError::StaleSchema {
+            expected: second.schema_hash(),
+            actual: hash.clone()
+        }),
+        second.check_schema_hash(&hash)
+    );
+    assert_ne!(hash, second.schema_hash());
+
+    Ok(())
+}
+
+#[test]
+fn test_same_schema() -> Result<(), ArgParserError> {
+    use super::{ArgGroup, OptionalArg};
+
+    let mut first = ArgParser::default();
+    let mut second = ArgParser::default();
+
+    first
+        .add_option(OptionalArg::flag("json").alias("j").alias("as-json"))?
+        .add_option(OptionalArg::flag("yaml"))?
+        .add_option(OptionalArg::required_value("user").validator(|_| Ok(())))?
+        .add_group(ArgGroup::at_most_one("format", &["json", "yaml"]))?
+        .add_positional(PositionalArg::rest())?;
+    second
+        .add_positional(PositionalArg::rest())?
+        .add_option(OptionalArg::required_value("user").validator(|_| Ok(())))?
+        .add_option(OptionalArg::flag("yaml"))?
+        .add_option(OptionalArg::flag("json").alias("as-json").alias("j"))?
+        .add_group(ArgGroup::at_most_one("format", &["yaml", "json"]))?;
+
+    assert_ne!(first, second);
+    assert!(first.same_schema(&second));
+    assert_eq!(first.canonical_schema(), second.canonical_schema());
+
+    second.ignore_options(&["legacy"]);
+
+    assert!(!first.same_schema(&second));
+
+    first.ignore_options(&["legacy"]);
+
+    assert!(first.same_schema(&second));
+
+    second.add_option(OptionalArg::flag("toml").help("Print TOML"))?;
+
+    assert!(!first.same_schema(&second));
+    assert!(second
+        .canonical_schema()
+        .contains("option --toml Flag aliases=[]"));
+
+    Ok(())
+}
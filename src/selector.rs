@@ -1,14 +1,64 @@
-use super::ParsedArg;
+use super::{ParsedArg, ParsedArgs, ValueSource};
 
-pub struct ArgSelector<'a> {
-    pub(crate) args: &'a Vec<ParsedArg>,
+// Selectors made with `ArgSelector::from(&parsed)` also know about the parser's schema (sources,
+// groups, positional names, encodings), so only they have getters depending on it
+pub struct ArgSelector<'a, P = ()> {
+    pub(crate) args: &'a [ParsedArg],
+    pub(crate) parsed: P,
 }
 
 impl<'a> ArgSelector<'a> {
-    pub fn new(args: &'a Vec<ParsedArg>) -> Self {
-        Self { args }
+    pub fn new<T: AsRef<[ParsedArg]> + ?Sized>(args: &'a T) -> Self {
+        Self {
+            args: args.as_ref(),
+            parsed: (),
+        }
+    }
+}
+
+impl<'a> ArgSelector<'a, &'a ParsedArgs> {
+    pub fn get_source(&self, name: &str) -> Option<ValueSource> {
+        self.parsed.source(name)
+    }
+
+    pub fn get_choice(&self, group: &str) -> Option<&'static str> {
+        let members = self.parsed.tables.groups.get(group)?;
+
+        self.args.iter().rev().find_map(|arg| match *arg {
+            ParsedArg::Flag { value: false, .. } => None,
+            _ => arg.name().filter(|name| members.contains(name)),
+        })
+    }
+
+    // A named rest arg only stands for its first value
+    pub fn get_positional_named(&self, name: &str) -> Option<&'a String> {
+        match self.find_positional_named(name)? {
+            ParsedArg::Positional { value } => Some(value),
+            _ => None,
+        }
     }
 
+    // Non-unicode positional args still take up their position
+    pub(crate) fn find_positional_named(&self, name: &str) -> Option<&'a ParsedArg> {
+        let idx = self
+            .parsed
+            .positional_names()
+            .iter()
+            .position(|&other| other == Some(name))?;
+
+        self.args
+            .iter()
+            .filter(|arg| {
+                matches!(
+                    arg,
+                    ParsedArg::Positional { .. } | ParsedArg::PositionalOs { .. }
+                )
+            })
+            .nth(idx)
+    }
+}
+
+impl<'a, P> ArgSelector<'a, P> {
     pub fn get_positional(&self) -> Vec<&'a String> {
         self.args
             .iter()
@@ -19,6 +69,17 @@ impl<'a> ArgSelector<'a> {
             .collect()
     }
 
+    pub fn get_rest(&self) -> Vec<&'a String> {
+        self.args
+            .iter()
+            .filter_map(|arg| match arg {
+                ParsedArg::Rest { values } => Some(values),
+                _ => None,
+            })
+            .flatten()
+            .collect()
+    }
+
     pub fn get_flag(&self, name: &str, default: bool) -> bool {
         self.args
             .iter()
@@ -29,6 +90,20 @@ impl<'a> ArgSelector<'a> {
             .unwrap_or(default)
     }
 
+    pub fn get_count(&self, name: &str) -> usize {
+        self.args
+            .iter()
+            .find_map(|arg| match arg {
+                &ParsedArg::Count { name: _name, value } if name == _name => Some(value),
+                _ => None,
+            })
+            .unwrap_or(0)
+    }
+
+    pub fn get_level<T: Clone>(&self, name: &str, levels: &[T]) -> Option<T> {
+        levels.get(self.get_count(name)).or(levels.last()).cloned()
+    }
+
     pub fn get_value(&self, name: &str) -> Option<&'a String> {
         self.args.iter().find_map(|arg| match arg {
             &ParsedArg::RequiredValue {
@@ -52,6 +127,19 @@ impl<'a> ArgSelector<'a> {
             .collect()
     }
 
+    pub fn get_grouped_values(&self, name: &str) -> Vec<&'a [String]> {
+        self.args
+            .iter()
+            .filter_map(|arg| match arg {
+                &ParsedArg::Values {
+                    name: _name,
+                    ref values,
+                } if name == _name => Some(&values[..]),
+                _ => None,
+            })
+            .collect()
+    }
+
     pub fn get_optional_value(&self, name: &str, default: &'a String) -> &'a String {
         self.args
             .iter()
@@ -66,6 +154,15 @@ impl<'a> ArgSelector<'a> {
     }
 }
 
+impl<'a> From<&'a ParsedArgs> for ArgSelector<'a, &'a ParsedArgs> {
+    fn from(args: &'a ParsedArgs) -> Self {
+        Self {
+            args: args.as_ref(),
+            parsed: args,
+        }
+    }
+}
+
 #[test]
 fn test_arg_selector() {
     use ParsedArg::*;
@@ -86,6 +183,14 @@ fn test_arg_selector() {
             name: "baz",
             value: Some("789".to_string()),
         },
+        Count {
+            name: "qux",
+            value: 2,
+        },
+        Values {
+            name: "size",
+            values: vec!["1".to_string(), "2".to_string()],
+        },
     ];
 
     let s = ArgSelector::new(&args);
@@ -96,12 +201,112 @@ fn test_arg_selector() {
     assert!(!s.get_flag("bar", false));
     assert!(s.get_flag("bar", true));
 
+    assert_eq!(2, s.get_count("qux"));
+    assert_eq!(0, s.get_count("foo"));
+    assert_eq!(
+        Some("info"),
+        s.get_level("foo", &["info", "debug", "trace"])
+    );
+    assert_eq!(
+        Some("trace"),
+        s.get_level("qux", &["info", "debug", "trace"])
+    );
+    assert_eq!(Some("debug"), s.get_level("qux", &["info", "debug"]));
+    assert_eq!(None::<&str>, s.get_level("qux", &[]));
+
     assert_eq!(None, s.get_value("foo"));
     assert_eq!(Some(&"456".to_string()), s.get_value("bar"));
 
     assert_eq!(vec!["456"], s.get_values("bar"));
     assert!(s.get_values("baz").is_empty());
 
+    assert_eq!(
+        vec![&["1".to_string(), "2".to_string()][..]],
+        s.get_grouped_values("size")
+    );
+
     assert_eq!("abc", s.get_optional_value("bar", &"abc".to_string()));
     assert_eq!("789", s.get_optional_value("baz", &"abc".to_string()));
 }
+
+#[test]
+fn test_arg_selector_slices() {
+    use ParsedArg::*;
+
+    let args = [
+        Positional {
+            value: "foo".to_string(),
+        },
+        Positional {
+            value: "bar".to_string(),
+        },
+    ];
+
+    assert_eq!(vec!["bar"], ArgSelector::new(&args[1..]).get_positional());
+    assert_eq!(
+        vec!["foo", "bar"],
+        ArgSelector::from(&ParsedArgs {
+            args: args.to_vec(),
+            ..Default::default()
+        })
+        .get_positional()
+    );
+}
+
+#[test]
+fn test_get_positional_named() -> Result<(), super::ArgParserError> {
+    use super::{ArgParser, PositionalArg};
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_positional(PositionalArg::named().name("src"))?
+        .add_positional(PositionalArg::optional().name("dst"))?;
+
+    let args = parser.parse(&["a.txt"])?;
+    let sel = ArgSelector::from(&args);
+
+    assert_eq!(Some(&"a.txt".to_string()), sel.get_positional_named("src"));
+    assert_eq!(None, sel.get_positional_named("dst"));
+    assert_eq!(None, sel.get_positional_named("other"));
+
+    let args = parser.parse(&["a.txt", "b.txt"])?;
+
+    assert_eq!(
+        Some(&"b.txt".to_string()),
+        ArgSelector::from(&args).get_positional_named("dst")
+    );
+    assert_eq!(
+        "missing required argument <src>",
+        parser.parse(&[]).unwrap_err().to_string()
+    );
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_signature(vec![
+            PositionalArg::named().name("file"),
+            PositionalArg::optional().name("out").default_value("a.out"),
+        ])?
+        .add_signature(vec![
+            PositionalArg::named().name("src"),
+            PositionalArg::named().name("dst"),
+            PositionalArg::named().name("mode"),
+        ])?;
+
+    let args = parser.parse(&["x.c"])?;
+    let sel = ArgSelector::from(&args);
+
+    assert_eq!(Some(&"x.c".to_string()), sel.get_positional_named("file"));
+    assert_eq!(Some(&"a.out".to_string()), sel.get_positional_named("out"));
+    assert_eq!(None, sel.get_positional_named("src"));
+
+    let args = parser.parse(&["a", "b", "c"])?;
+
+    assert_eq!(
+        Some(&"c".to_string()),
+        ArgSelector::from(&args).get_positional_named("mode")
+    );
+
+    Ok(())
+}
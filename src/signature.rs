@@ -0,0 +1,196 @@
+use super::{ArgParser, ArgParserError, ParsedArg, PositionalArg, PositionalArgKind};
+
+impl ArgParser {
+    pub fn add_signature(
+        &mut self,
+        signature: Vec<PositionalArg>,
+    ) -> Result<&mut Self, ArgParserError> {
+        let rest = signature
+            .iter()
+            .filter(|arg| arg.kind == PositionalArgKind::Rest)
+            .count();
+
+        if rest > 1 {
+            return Err(ArgParserError::InvalidRestArg);
+        }
+
+        for (idx, arg) in signature.iter().enumerate() {
+            arg.check_settings(idx)?;
+        }
+
+        self.signatures.push(signature);
+        self.tables.clear();
+
+        Ok(self)
+    }
+
+    // With `defaults`, a signature only matches once its own defaults are filled in, so one whose
+    // default is rejected doesn't match by leaving the optional arg out
+    pub(crate) fn match_signature(
+        &self,
+        args: &[ParsedArg],
+        defaults: bool,
+    ) -> Result<Option<usize>, ArgParserError> {
+        if self.signatures.is_empty() {
+            return Ok(None);
+        }
+
+        let values = Self::positional_values(args);
+
+        self.signatures
+            .iter()
+            .position(|signature| match defaults {
+                true => Self::fill_defaults(signature, &values).is_some(),
+                false => Self::matches_signature(signature, &values),
+            })
+            .map(Some)
+            .ok_or(ArgParserError::NoMatchingSignature {
+                actual: values.len(),
+            })
+    }
+
+    // The first signature the given values fit once its defaults are filled in, along with those
+    // defaults, which are checked by the match like given values
+    pub(crate) fn signature_defaults(
+        &self,
+        args: &[ParsedArg],
+    ) -> Option<(usize, Vec<(usize, &'static str)>)> {
+        let values = Self::positional_values(args);
+
+        self.signatures
+            .iter()
+            .enumerate()
+            .find_map(|(idx, signature)| {
+                Self::fill_defaults(signature, &values).map(|defaults| (idx, defaults))
+            })
+    }
+
+    fn fill_defaults(
+        signature: &[PositionalArg],
+        values: &[&str],
+    ) -> Option<Vec<(usize, &'static str)>> {
+        let defaults = PositionalArg::defaults(signature, values.len());
+        let filled = values
+            .iter()
+            .copied()
+            .chain(defaults.iter().map(|&(_, value)| value))
+            .collect::<Vec<_>>();
+
+        Self::matches_signature(signature, &filled).then_some(defaults)
+    }
+
+    fn positional_values(args: &[ParsedArg]) -> Vec<&str> {
+        args.iter()
+            .filter_map(|arg| match arg {
+                ParsedArg::Positional { value } => Some(value.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    // Named args take a value each, then optional ones take what's left in order, and a rest arg
+    // takes whatever is left after them
+    fn matches_signature(signature: &[PositionalArg], values: &[&str]) -> bool {
+        let rest = signature
+            .iter()
+            .any(|arg| arg.kind == PositionalArgKind::Rest);
+        let required = signature
+            .iter()
+            .map(|arg| match arg.kind {
+                PositionalArgKind::Named => 1,
+                PositionalArgKind::Optional => 0,
+                PositionalArgKind::Rest => arg.min,
+            })
+            .sum::<usize>();
+
+        if values.len() < required || (!rest && values.len() > signature.len()) {
+            return false;
+        }
+
+        let mut spare = values.len() - required;
+        let mut layout = vec![];
+
+        for arg in signature {
+            let taken = match arg.kind {
+                PositionalArgKind::Named => 1,
+                PositionalArgKind::Optional if spare > 0 => {
+                    spare -= 1;
+                    1
+                }
+                PositionalArgKind::Optional => 0,
+                PositionalArgKind::Rest => arg.min + std::mem::take(&mut spare),
+            };
+
+            layout.extend(std::iter::repeat_n(arg, taken));
+        }
+
+        layout
+            .iter()
+            .zip(values)
+            .all(|(arg, value)| arg.check_value(value).is_ok())
+    }
+}
+
+#[test]
+fn test_parse_signatures() -> Result<(), ArgParserError> {
+    let mut parser = ArgParser::default();
+
+    assert_eq!(
+        Err(ArgParserError::InvalidRestArg),
+        parser
+            .add_signature(vec![PositionalArg::rest(), PositionalArg::rest()])
+            .map(|_| ())
+    );
+
+    parser
+        .add_signature(vec![
+            PositionalArg::named(),
+            PositionalArg::named().validator(|s| {
+                if s.ends_with('/') {
+                    Err("is a directory".to_string())
+                } else {
+                    Ok(())
+                }
+            }),
+        ])?
+        .add_signature(vec![PositionalArg::rest(), PositionalArg::named()])?;
+
+    assert_eq!(Some(0), parser.parse(&["foo", "bar"])?.signature());
+    assert_eq!(Some(1), parser.parse(&["foo", "bar/"])?.signature());
+    assert_eq!(Some(1), parser.parse(&["foo", "bar", "baz/"])?.signature());
+    assert_eq!(
+        Err(ArgParserError::NoMatchingSignature { actual: 0 }),
+        parser.parse(&[])
+    );
+
+    assert_eq!(None, ArgParser::default().parse(&["foo"])?.signature());
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_signature_optional() -> Result<(), ArgParserError> {
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_signature(vec![
+            PositionalArg::named().name("file"),
+            PositionalArg::optional().name("out"),
+        ])?
+        .add_signature(vec![
+            PositionalArg::named().choices(&["cp"]),
+            PositionalArg::optional(),
+            PositionalArg::rest().min(2),
+        ])?;
+
+    assert_eq!(Some(0), parser.parse(&["a.txt"])?.signature());
+    assert_eq!(Some(0), parser.parse(&["a.txt", "b.txt"])?.signature());
+    assert_eq!(Some(1), parser.parse(&["cp", "a", "b"])?.signature());
+    assert_eq!(Some(1), parser.parse(&["cp", "a", "b", "c"])?.signature());
+    assert_eq!(
+        Err(ArgParserError::NoMatchingSignature { actual: 3 }),
+        parser.parse(&["a", "b", "c"])
+    );
+
+    Ok(())
+}
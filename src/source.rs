@@ -0,0 +1,211 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+pub trait EnvSource {
+    fn var(&self, name: &str) -> Option<String>;
+}
+
+pub trait FileSource {
+    fn read_to_string(&self, path: &Path) -> io::Result<String>;
+}
+
+// Closures keep working wherever an environment is expected
+impl<F: Fn(&str) -> Option<String>> EnvSource for F {
+    fn var(&self, name: &str) -> Option<String> {
+        self(name)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ProcessEnv;
+
+impl EnvSource for ProcessEnv {
+    fn var(&self, name: &str) -> Option<String> {
+        env::var(name).ok()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FileSystem;
+
+impl FileSource for FileSystem {
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        fs::read_to_string(path)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MemoryEnv {
+    vars: HashMap<String, String>,
+}
+
+impl MemoryEnv {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set(mut self, name: &str, value: &str) -> Self {
+        self.vars.insert(name.to_string(), value.to_string());
+        self
+    }
+}
+
+impl EnvSource for MemoryEnv {
+    fn var(&self, name: &str) -> Option<String> {
+        self.vars.get(name).cloned()
+    }
+}
+
+// Remembers what a slow or rate limited environment (e.g. a keyring) returned, so long-running
+// processes can resolve again and again without asking it every time. The cache goes with the
+// source rather than the parser, and resolvers borrow it as `env_source(&cached)`.
+#[derive(Debug, Default)]
+pub struct CachedEnv<S> {
+    source: S,
+    cache: RefCell<HashMap<String, Option<String>>>,
+}
+
+impl<S: EnvSource> CachedEnv<S> {
+    pub fn new(source: S) -> Self {
+        Self {
+            source,
+            cache: RefCell::default(),
+        }
+    }
+
+    pub fn invalidate(&self) {
+        self.cache.borrow_mut().clear();
+    }
+}
+
+impl<S: EnvSource> EnvSource for CachedEnv<S> {
+    fn var(&self, name: &str) -> Option<String> {
+        self.cache
+            .borrow_mut()
+            .entry(name.to_string())
+            .or_insert_with(|| self.source.var(name))
+            .clone()
+    }
+}
+
+impl<S: EnvSource> EnvSource for &CachedEnv<S> {
+    fn var(&self, name: &str) -> Option<String> {
+        (*self).var(name)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MemoryFiles {
+    files: HashMap<PathBuf, String>,
+}
+
+impl MemoryFiles {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn file(mut self, path: impl AsRef<Path>, contents: &str) -> Self {
+        self.files
+            .insert(path.as_ref().to_path_buf(), contents.to_string());
+        self
+    }
+}
+
+impl FileSource for MemoryFiles {
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        self.files
+            .get(path)
+            .cloned()
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))
+    }
+}
+
+#[test]
+fn test_sources() -> Result<(), super::ArgParserError> {
+    use super::{ArgParser, ArgSelector, OptionalArg, Resolver, ValueSource};
+
+    let mut parser = ArgParser::default();
+
+    parser.add_option(OptionalArg::required_value("region").env("MYAPP_REGION"))?;
+
+    let env = MemoryEnv::new().set("MYAPP_REGION", "us-east-1");
+    let args = Resolver::new(&parser)
+        .env_source(env.clone())
+        .resolve(&[])?;
+
+    assert_eq!(
+        Some(&"us-east-1".to_string()),
+        ArgSelector::from(&args).get_value("region")
+    );
+    assert_eq!(
+        Some(ValueSource::Env("MYAPP_REGION".to_string())),
+        args.source("region")
+    );
+    assert!(Resolver::new(&parser)
+        .env_source(MemoryEnv::new())
+        .resolve(&[])?
+        .is_empty());
+
+    let files = MemoryFiles::new().file("app.toml", "region = 'eu'");
+
+    assert_eq!(
+        "region = 'eu'",
+        files.read_to_string(Path::new("app.toml")).unwrap()
+    );
+    assert_eq!(
+        io::ErrorKind::NotFound,
+        files
+            .read_to_string(Path::new("other.toml"))
+            .unwrap_err()
+            .kind()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cached_env() -> Result<(), super::ArgParserError> {
+    use super::{ArgParser, ArgParserError, ArgSelector, OptionalArg, Resolver};
+    use std::cell::Cell;
+
+    fn token(parser: &ArgParser, env: impl EnvSource) -> Result<Option<String>, ArgParserError> {
+        let args = Resolver::new(parser).env_source(env).resolve(&[])?;
+
+        Ok(ArgSelector::from(&args).get_value("token").cloned())
+    }
+
+    let mut parser = ArgParser::default();
+
+    parser.add_option(OptionalArg::required_value("token").env("API_TOKEN"))?;
+
+    let fresh = parser.clone();
+    let lookups = Cell::new(0);
+    let env = CachedEnv::new(|_: &str| {
+        lookups.set(lookups.get() + 1);
+        Some(format!("secret{}", lookups.get()))
+    });
+
+    for _ in 0..3 {
+        assert_eq!(Some("secret1".to_string()), token(&parser, &env)?);
+    }
+
+    assert_eq!(1, lookups.get());
+
+    env.invalidate();
+
+    assert_eq!(Some("secret2".to_string()), token(&parser, &env)?);
+
+    // Each source has a cache of its own, and the parser itself stays as it was
+    let one = CachedEnv::new(MemoryEnv::new().set("API_TOKEN", "one"));
+    let two = CachedEnv::new(MemoryEnv::new().set("API_TOKEN", "two"));
+
+    assert_eq!(Some("one".to_string()), token(&parser, &one)?);
+    assert_eq!(Some("two".to_string()), token(&parser, &two)?);
+    assert!(parser == fresh);
+
+    Ok(())
+}
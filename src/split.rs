@@ -0,0 +1,193 @@
+use super::{ArgParser, ArgParserError, ParsedArgs};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SplitStyle {
+    Posix,
+    Windows,
+}
+
+pub(crate) fn split_shell(source: &str) -> Result<Vec<String>, String> {
+    let mut args = vec![];
+    let mut arg = None::<String>;
+    let mut quote = None;
+    let mut chars = source.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (None, '#') if arg.is_none() => {
+                chars.by_ref().take_while(|&c| c != '\n').for_each(drop);
+            }
+            (None, c) if c.is_whitespace() => args.extend(arg.take()),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                arg.get_or_insert_with(String::new);
+            }
+            (Some(q), c) if c == q => quote = None,
+            (None | Some('"'), '\\') => {
+                let escaped = chars.next().ok_or("trailing backslash")?;
+
+                // Inside double quotes, a backslash only escapes characters that are special there
+                if quote.is_some() && !matches!(escaped, '"' | '\\' | '$' | '`' | '\n') {
+                    arg.get_or_insert_with(String::new).push('\\');
+                }
+
+                if escaped != '\n' {
+                    arg.get_or_insert_with(String::new).push(escaped);
+                }
+            }
+            (_, c) => arg.get_or_insert_with(String::new).push(c),
+        }
+    }
+
+    if quote.is_some() {
+        return Err("unterminated quote".to_string());
+    }
+
+    args.extend(arg);
+
+    Ok(args)
+}
+
+// Follows the rules of CommandLineToArgvW (minus the special handling of the program name):
+// backslashes are literal unless they precede a quote, and "" inside quotes is a literal quote
+pub(crate) fn split_windows(source: &str) -> Vec<String> {
+    let mut args = vec![];
+    let mut arg = None::<String>;
+    let mut quoted = false;
+    let mut chars = source.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            ' ' | '\t' if !quoted => args.extend(arg.take()),
+            '\\' => {
+                let mut backslashes = 1;
+
+                while chars.next_if_eq(&'\\').is_some() {
+                    backslashes += 1;
+                }
+
+                let arg = arg.get_or_insert_with(String::new);
+
+                if chars.peek() == Some(&'"') {
+                    arg.push_str(&"\\".repeat(backslashes / 2));
+
+                    if backslashes % 2 == 1 {
+                        arg.push('"');
+                        chars.next();
+                    }
+                } else {
+                    arg.push_str(&"\\".repeat(backslashes));
+                }
+            }
+            '"' if quoted && chars.next_if_eq(&'"').is_some() => {
+                arg.get_or_insert_with(String::new).push('"');
+            }
+            '"' => {
+                quoted = !quoted;
+                arg.get_or_insert_with(String::new);
+            }
+            c => arg.get_or_insert_with(String::new).push(c),
+        }
+    }
+
+    args.extend(arg);
+    args
+}
+
+impl ArgParser {
+    pub fn split_style(&mut self, style: SplitStyle) -> &mut Self {
+        self.split_style = style;
+        self
+    }
+
+    pub fn parse_str(&self, line: &str) -> Result<ParsedArgs, ArgParserError> {
+        self.check_input_bytes(line.len())?;
+
+        let args = match self.split_style {
+            SplitStyle::Posix => {
+                split_shell(line).map_err(|reason| ArgParserError::InvalidCommandLine { reason })?
+            }
+            SplitStyle::Windows => split_windows(line),
+        };
+
+        self.check_arg_count(args.len())?;
+
+        self.parse(&args.iter().map(|arg| arg.as_str()).collect::<Vec<_>>())
+    }
+}
+
+#[test]
+fn test_split_shell() {
+    assert_eq!(
+        Ok(vec![
+            "-o".to_string(),
+            "out dir/a.out".to_string(),
+            "--define=A B".to_string(),
+            "".to_string(),
+            "say \"hi\"".to_string(),
+            "C:\\dir\\n".to_string(),
+            "foo.o".to_string(),
+        ]),
+        split_shell(
+            "-o 'out dir/a.out'\n# comment\n--define=\"A B\" ''\n\"say \\\"hi\\\"\" 'C:\\dir\\n'\nfoo.o # trailing\n"
+        )
+        .map(|args| args.into_iter().take(7).collect())
+    );
+    assert_eq!(
+        Ok(vec!["a b".to_string(), "c".to_string()]),
+        split_shell("a\\ b c\\\n")
+    );
+    assert_eq!(Err("unterminated quote".to_string()), split_shell("'a"));
+}
+
+#[test]
+fn test_split_windows() {
+    let split = |s| split_windows(s);
+
+    assert_eq!(vec!["a b", "c", "d"], split("\"a b\" c\td"));
+    assert_eq!(
+        vec!["C:\\Program Files\\", "x"],
+        split(r#""C:\Program Files\\" x"#)
+    );
+    assert_eq!(vec!["a\\\\b", "c\"d"], split(r#"a\\b c\"d"#));
+    assert_eq!(vec!["a\\\"b"], split(r#""a\\\"b""#));
+    assert_eq!(vec!["say \"hi\"", ""], split(r#""say ""hi""" """#));
+    assert_eq!(vec!["unterminated quote"], split("\"unterminated quote"));
+}
+
+#[test]
+fn test_parse_str() -> Result<(), ArgParserError> {
+    use super::{ArgSelector, OptionalArg, PositionalArg};
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::required_value("foo"))?
+        .add_option(OptionalArg::required_value("bar"))?
+        .add_positional(PositionalArg::rest())?;
+
+    let args = parser.parse_str("--foo 'a b' --bar=\"x\" \"\\$HOME\" c\\ d")?;
+    let sel = ArgSelector::from(&args);
+
+    assert_eq!(Some(&"a b".to_string()), sel.get_value("foo"));
+    assert_eq!(Some(&"x".to_string()), sel.get_value("bar"));
+    assert_eq!(vec!["$HOME", "c d"], sel.get_positional());
+
+    assert_eq!(
+        "invalid command line: unterminated quote",
+        parser.parse_str("--foo 'a").unwrap_err().to_string()
+    );
+
+    parser.split_style(SplitStyle::Windows);
+
+    let args = parser.parse_str(r#"--foo "C:\Program Files\\" 'a b'"#)?;
+    let sel = ArgSelector::from(&args);
+
+    assert_eq!(
+        Some(&"C:\\Program Files\\".to_string()),
+        sel.get_value("foo")
+    );
+    assert_eq!(vec!["'a", "b'"], sel.get_positional());
+
+    Ok(())
+}
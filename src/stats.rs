@@ -0,0 +1,97 @@
+use super::{mount::MountedOption, ArgParser, OptionalArg, PositionalArg};
+use std::mem;
+
+#[derive(Debug, PartialEq)]
+pub struct ParserStats {
+    pub options: usize,
+    pub aliases: usize,
+    pub positional: usize,
+    pub constraints: usize,
+    pub parse_cost: usize,
+    pub memory_footprint: usize,
+}
+
+impl ArgParser {
+    pub fn stats(&self) -> ParserStats {
+        let constraints = self
+            .options
+            .values()
+            .map(|option| {
+                option.validator.is_some() as usize
+                    + option.range.is_some() as usize
+                    + !option.choices.is_empty() as usize
+                    + option.encoding.is_some() as usize
+                    + option.conditional_defaults.len()
+                    + option.requires.len()
+                    + option.conflicts.len()
+            })
+            .sum::<usize>()
+            + self.groups.len()
+            + self
+                .positional
+                .iter()
+                .map(|arg| {
+                    arg.validator.is_some() as usize
+                        + arg.range.is_some() as usize
+                        + !arg.choices.is_empty() as usize
+                        + arg.must_exist as usize
+                })
+                .sum::<usize>();
+
+        let conditional_defaults = self
+            .options
+            .values()
+            .map(|option| option.conditional_defaults.capacity())
+            .sum::<usize>();
+
+        // An estimate: map overhead and allocator bookkeeping aren't accounted for
+        let memory_footprint = mem::size_of::<ArgParser>()
+            + self.options.capacity() * mem::size_of::<(&str, OptionalArg)>()
+            + self.aliases.capacity() * mem::size_of::<(&str, &str)>()
+            + self.mounted.capacity() * mem::size_of::<(&str, MountedOption)>()
+            + self.positional.capacity() * mem::size_of::<PositionalArg>()
+            + conditional_defaults * mem::size_of::<(&str, Option<&str>, &str)>();
+
+        ParserStats {
+            options: self.options.len(),
+            aliases: self.aliases.len(),
+            positional: self.positional.len(),
+            constraints,
+            // Per argument: a lookup, plus a check of every constraint in the post-parse passes
+            parse_cost: 1 + constraints,
+            memory_footprint,
+        }
+    }
+}
+
+#[test]
+fn test_stats() -> Result<(), super::ArgParserError> {
+    let mut parser = ArgParser::default();
+
+    let empty = parser.stats();
+
+    assert_eq!(0, empty.options);
+    assert_eq!(1, empty.parse_cost);
+
+    parser
+        .add_positional(PositionalArg::named().validator(|_| Ok(())))?
+        .add_option(OptionalArg::flag("foo").alias("f"))?
+        .add_option(OptionalArg::required_value("bar").range(0..))?
+        .add_option(
+            OptionalArg::required_value("baz")
+                .validator(|_| Ok(()))
+                .default_value_if("foo", None, "qux")
+                .requires("bar"),
+        )?;
+
+    let stats = parser.stats();
+
+    assert_eq!(3, stats.options);
+    assert_eq!(1, stats.aliases);
+    assert_eq!(1, stats.positional);
+    assert_eq!(5, stats.constraints);
+    assert_eq!(6, stats.parse_cost);
+    assert!(stats.memory_footprint > empty.memory_footprint);
+
+    Ok(())
+}
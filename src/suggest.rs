@@ -0,0 +1,138 @@
+use super::ArgParser;
+
+impl ArgParser {
+    // 0 turns suggestions off
+    pub fn suggestion_distance(&mut self, max: usize) -> &mut Self {
+        self.suggestion_distance = max;
+        self
+    }
+
+    pub(crate) fn suggest_option(&self, given: &str) -> Option<&'static str> {
+        let mut candidates = self
+            .options
+            .values()
+            .filter(|option| !option.hidden && !option.env_only)
+            .flat_map(|option| {
+                option
+                    .aliases
+                    .iter()
+                    .copied()
+                    .filter(|alias| !self.is_valid_alias(alias))
+                    .chain([option.name])
+            })
+            .map(|name| (edit_distance(given, name), name))
+            .filter(|&(distance, _)| distance > 0 && distance <= self.suggestion_distance)
+            .collect::<Vec<_>>();
+
+        candidates.sort_unstable();
+        candidates.first().map(|&(_, name)| name)
+    }
+
+    // Any two single characters are one edit apart, so only a mistyped case is worth suggesting
+    pub(crate) fn suggest_alias(&self, given: &str) -> Option<&'static str> {
+        if self.suggestion_distance == 0 {
+            return None;
+        }
+
+        let mut candidates = self
+            .aliases
+            .iter()
+            .filter(|(alias, name)| {
+                alias.to_lowercase() == given.to_lowercase()
+                    && !self.options[*name].hidden
+                    && !self.options[*name].env_only
+            })
+            .map(|(&alias, _)| alias)
+            .collect::<Vec<_>>();
+
+        candidates.sort_unstable();
+        candidates.first().copied()
+    }
+}
+
+// Levenshtein distance over chars, with a single row of the usual table
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+
+        row[0] = i + 1;
+
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = diagonal + (ca != cb) as usize;
+
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+
+    row[b.len()]
+}
+
+#[test]
+fn test_suggestions() -> Result<(), super::ArgParserError> {
+    use super::{ArgParserError, OptionalArg};
+    use ArgParserError::*;
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::count("verbose").alias("v"))?
+        .add_option(OptionalArg::flag("version").alias("V"))?
+        .add_option(OptionalArg::flag("colour").alias("color"))?
+        .add_option(OptionalArg::flag("debug-internals").hidden())?;
+
+    assert_eq!(
+        "--verbos is undefined, did you mean --verbose?",
+        parser.parse(&["--verbos"]).unwrap_err().to_string()
+    );
+    assert_eq!(
+        Err(UnknownOption {
+            name: "colr".to_string(),
+            suggestion: Some("color")
+        }),
+        parser.parse(&["--colr"])
+    );
+    assert_eq!(
+        Err(UnknownOption {
+            name: "debug-internal".to_string(),
+            suggestion: None
+        }),
+        parser.parse(&["--debug-internal"])
+    );
+    assert_eq!(
+        Err(UnknownAlias {
+            alias: "x".to_string(),
+            suggestion: None
+        }),
+        parser.parse(&["-x"])
+    );
+
+    parser.suggestion_distance(1);
+
+    assert_eq!(
+        "--verison is undefined",
+        parser.parse(&["--verison"]).unwrap_err().to_string()
+    );
+
+    parser.suggestion_distance(0);
+
+    assert_eq!(
+        "--verbos is undefined",
+        parser.parse(&["--verbos"]).unwrap_err().to_string()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_edit_distance() {
+    assert_eq!(0, edit_distance("verbose", "verbose"));
+    assert_eq!(1, edit_distance("verbos", "verbose"));
+    assert_eq!(2, edit_distance("vrebose", "verbose"));
+    assert_eq!(3, edit_distance("", "foo"));
+    assert_eq!(3, edit_distance("kitten", "sitting"));
+    assert_eq!(1, edit_distance("日本", "日"));
+}
@@ -0,0 +1,116 @@
+use std::{
+    fmt,
+    ops::{Bound, RangeBounds, RangeInclusive},
+    sync::Arc,
+};
+
+// Shared between threads along with the parser, so it has to be thread-safe
+type ValidatorFn = dyn Fn(&str) -> Result<(), String> + Send + Sync;
+
+#[derive(Clone)]
+pub struct Validator(Arc<ValidatorFn>);
+
+impl Validator {
+    pub fn new(f: impl Fn(&str) -> Result<(), String> + Send + Sync + 'static) -> Self {
+        Self(Arc::new(f))
+    }
+
+    pub fn validate(&self, value: &str) -> Result<(), String> {
+        (self.0)(value)
+    }
+}
+
+// Shared by options and positional arguments, both of which take `.range(..)`. There is nothing
+// to check for `..`, while a range with no numbers in it, e.g. `5..1` or `..i64::MIN`, stays empty
+// for `add_option` and `add_positional` to reject.
+pub(crate) fn inclusive_range(range: impl RangeBounds<i64>) -> Option<RangeInclusive<i64>> {
+    let start = match range.start_bound() {
+        Bound::Included(&n) => Some(n),
+        Bound::Excluded(&n) => n.checked_add(1),
+        Bound::Unbounded => Some(i64::MIN),
+    };
+
+    let end = match range.end_bound() {
+        Bound::Included(&n) => Some(n),
+        Bound::Excluded(&n) => n.checked_sub(1),
+        Bound::Unbounded => Some(i64::MAX),
+    };
+
+    match (range.start_bound(), range.end_bound(), start, end) {
+        (Bound::Unbounded, Bound::Unbounded, ..) => None,
+        (.., Some(start), Some(end)) => Some(start..=end),
+        _ => Some(RangeInclusive::new(1, 0)),
+    }
+}
+
+pub(crate) fn check_range(range: &RangeInclusive<i64>, value: &str) -> Result<(), String> {
+    let n = value
+        .parse::<i64>()
+        .map_err(|_| "not a number".to_string())?;
+
+    if range.contains(&n) {
+        return Ok(());
+    }
+
+    Err(match (*range.start(), *range.end()) {
+        (i64::MIN, end) => format!("must be at most {}", end),
+        (start, i64::MAX) => format!("must be at least {}", start),
+        (start, end) => format!("must be in range {}..={}", start, end),
+    })
+}
+
+pub(crate) fn check_choices(choices: &[&str], value: &str) -> Result<(), String> {
+    if choices.is_empty() || choices.contains(&value) {
+        Ok(())
+    } else {
+        Err(format!("must be one of {}", choices.join(", ")))
+    }
+}
+
+impl fmt::Debug for Validator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Validator")
+    }
+}
+
+impl PartialEq for Validator {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+#[test]
+fn test_validator() {
+    let v = Validator::new(|s| {
+        if s.is_empty() {
+            Err("must not be empty".to_string())
+        } else {
+            Ok(())
+        }
+    });
+
+    assert_eq!(Ok(()), v.validate("foo"));
+    assert_eq!(Err("must not be empty".to_string()), v.validate(""));
+    assert_eq!(v, v.clone());
+    assert_ne!(v, Validator::new(|_| Ok(())));
+}
+
+#[test]
+fn test_inclusive_range() {
+    assert_eq!(Some(1..=65535), inclusive_range(1..=65535));
+    assert_eq!(Some(1..=i64::MAX), inclusive_range(1..));
+    assert_eq!(Some(i64::MIN..=19), inclusive_range(..20));
+    assert_eq!(None, inclusive_range(..));
+
+    // Such as from a config where the bounds got swapped
+    let (min, max) = (5, 1);
+
+    for range in [
+        inclusive_range(min..max),
+        inclusive_range(min..min),
+        inclusive_range(..i64::MIN),
+        inclusive_range((Bound::Excluded(i64::MAX), Bound::Unbounded)),
+    ] {
+        assert!(range.is_some_and(|range| range.is_empty()));
+    }
+}
@@ -0,0 +1,111 @@
+use super::{ArgParser, ArgParserError, ParsedArg, Resolver};
+
+pub trait ArgVisitor {
+    fn on_flag(&mut self, _name: &'static str, _value: bool) {}
+
+    fn on_count(&mut self, _name: &'static str, _value: usize) {}
+
+    fn on_value(&mut self, _name: &'static str, _value: Option<&str>) {}
+
+    fn on_values(&mut self, _name: &'static str, _values: &[String]) {}
+
+    fn on_positional(&mut self, _value: &str) {}
+
+    fn on_unknown(&mut self, _raw: &str) {}
+
+    fn on_rest(&mut self, _values: &[String]) {}
+
+    fn on_error(&mut self, error: ArgParserError);
+}
+
+impl ArgParser {
+    // The visitor is called as the args are parsed, then for values from env, config and defaults,
+    // and for every error on the way without stopping, like `parse_report`. It is a view over the
+    // same parse, so the `ParsedArgs` is still built, and only dropped at the end.
+    pub fn drive(&self, args: &[&str], visitor: &mut impl ArgVisitor) {
+        if let Err(error) = self.parse_with(args, &Resolver::new(self), Some(visitor)) {
+            visitor.on_error(error);
+        }
+    }
+}
+
+pub(crate) fn visit(visitor: &mut dyn ArgVisitor, arg: &ParsedArg) {
+    use ParsedArg::*;
+
+    match arg {
+        Positional { value } => visitor.on_positional(value),
+        PositionalOs { value } => visitor.on_positional(&value.to_string_lossy()),
+        Flag { name, value } => visitor.on_flag(name, *value),
+        Count { name, value } => visitor.on_count(name, *value),
+        RequiredValue { name, value } => visitor.on_value(name, Some(value)),
+        ValueOs { name, value } => visitor.on_value(name, Some(&value.to_string_lossy())),
+        OptionalValue { name, value } => visitor.on_value(name, value.as_deref()),
+        Values { name, values } => visitor.on_values(name, values),
+        Unknown { raw } => visitor.on_unknown(raw),
+        Rest { values } => visitor.on_rest(values),
+    }
+}
+
+#[test]
+fn test_drive() -> Result<(), ArgParserError> {
+    use super::{OptionalArg, PositionalArg};
+
+    #[derive(Default)]
+    struct Events(Vec<String>);
+
+    impl ArgVisitor for Events {
+        fn on_flag(&mut self, name: &'static str, value: bool) {
+            self.0.push(format!("flag {}={}", name, value));
+        }
+
+        fn on_count(&mut self, name: &'static str, value: usize) {
+            self.0.push(format!("count {}={}", name, value));
+        }
+
+        fn on_value(&mut self, name: &'static str, value: Option<&str>) {
+            self.0.push(format!("value {}={:?}", name, value));
+        }
+
+        fn on_positional(&mut self, value: &str) {
+            self.0.push(format!("positional {}", value));
+        }
+
+        fn on_error(&mut self, error: ArgParserError) {
+            self.0.push(format!("error {}", error));
+        }
+    }
+
+    let mut parser = ArgParser::default();
+
+    parser
+        .add_option(OptionalArg::flag("verbose"))?
+        .add_option(OptionalArg::count("jobs"))?
+        .add_option(OptionalArg::optional_value("color"))?
+        .add_option(OptionalArg::required_value("level").default_value("info"))?
+        .add_positional(PositionalArg::rest())?;
+
+    let mut events = Events::default();
+
+    parser.drive(&["a", "--verbose", "--jobs", "--color", "b"], &mut events);
+    parser.drive(&["--foo", "c", "--jobs", "--level", "--jobs"], &mut events);
+
+    assert_eq!(
+        vec![
+            "positional a",
+            "flag verbose=true",
+            "count jobs=1",
+            "value color=None",
+            "positional b",
+            "value level=Some(\"info\")",
+            "error --foo is undefined",
+            "positional c",
+            "count jobs=1",
+            "error --level is missing a value",
+            "count jobs=2",
+            "value level=Some(\"info\")"
+        ],
+        events.0
+    );
+
+    Ok(())
+}
@@ -0,0 +1,89 @@
+use rs_args::{ArgParser, ArgParserError, OptionalArg};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+
+// Counts the allocations made on the current thread, so tests running in parallel don't see each
+// other's
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+
+        // SAFETY: forwarded as is, with the caller's guarantees
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        // SAFETY: forwarded as is, with the caller's guarantees
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn count_allocations(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.with(|count| count.get());
+
+    f();
+
+    ALLOCATIONS.with(|count| count.get()) - before
+}
+
+#[test]
+fn test_parse_allocations() -> Result<(), ArgParserError> {
+    let mut parser = ArgParser::default();
+
+    for n in 0..32 {
+        let name = format!("option-{}", n).leak();
+
+        parser.add_option(match n % 4 {
+            0 => OptionalArg::flag(name),
+            1 => OptionalArg::required_value(name).choices(&["a", "b"]),
+            2 => OptionalArg::required_value(name).redact(|_| "***".to_string()),
+            _ => OptionalArg::optional_value(name).multiple(),
+        })?;
+    }
+
+    let args = ["--option-0", "--option-1=a", "file"];
+
+    // The first parse works out what every other one shares
+    parser.parse(&args)?;
+
+    let allocations = count_allocations(|| {
+        let _ = parser.parse(&args);
+    });
+
+    assert!(allocations <= 24, "{} allocations", allocations);
+
+    Ok(())
+}
+
+#[test]
+fn test_arena_allocations() -> Result<(), ArgParserError> {
+    let mut parser = ArgParser::default();
+
+    parser.add_option(OptionalArg::count("verbose").alias("v"))?;
+
+    // Every alias of a cluster but the last leaves the rest of it to be parsed as a new arg
+    let args = vec!["-vvvvvvvvvv"; 100];
+    let allocations = count_allocations(|| {
+        let _ = parser.parse(&args);
+    });
+
+    // 900 strings left behind by the clusters, which share a few chunks with the feature
+    if cfg!(feature = "arena") {
+        assert!(allocations < 900, "{} allocations", allocations);
+    } else {
+        assert!(allocations >= 900, "{} allocations", allocations);
+    }
+
+    Ok(())
+}